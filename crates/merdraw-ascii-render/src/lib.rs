@@ -1,12 +1,17 @@
 use std::collections::HashMap;
 
 use merdraw_layout::{LayoutGraph, LayoutNode, LayoutSubgraph};
+use merdraw_parser::{char_width, label_lines, EdgeArrow, EdgeStyle};
 
 #[derive(Debug, Clone)]
 pub struct AsciiRenderOptions {
     pub max_width: usize,
     pub max_height: usize,
     pub show_arrows: bool,
+    pub center: bool,
+    // Box-drawing characters and triangle arrowheads instead of `-`, `|`, and `<>^v`.
+    pub unicode: bool,
+    pub show_labels: bool,
 }
 
 impl Default for AsciiRenderOptions {
@@ -15,6 +20,9 @@ impl Default for AsciiRenderOptions {
             max_width: 80,
             max_height: 30,
             show_arrows: true,
+            center: false,
+            unicode: false,
+            show_labels: true,
         }
     }
 }
@@ -38,11 +46,12 @@ pub fn render_ascii(layout: &LayoutGraph, options: &AsciiRenderOptions) -> Strin
 
     let bounds = build_bounds(&layout.nodes, scale);
 
-    draw_subgraphs(&mut grid, &layout.subgraphs, &bounds);
-    let mut edge_paths: Vec<Vec<(i32, i32)>> = Vec::new();
+    let unicode = options.unicode;
+    draw_subgraphs(&mut grid, &layout.subgraphs, &bounds, unicode);
+    let mut edge_paths: Vec<EdgePath> = Vec::new();
 
     // Edges first so nodes appear on top.
-    for edge in &layout.edges {
+    for edge in layout.edges.iter().filter(|edge| edge.style != EdgeStyle::Invisible) {
         let mut points: Vec<(i32, i32)> = edge
             .points
             .iter()
@@ -53,60 +62,121 @@ pub fn render_ascii(layout: &LayoutGraph, options: &AsciiRenderOptions) -> Strin
             if let Some(bound) = bounds.get(&edge.from) {
                 let next = points[1];
                 points[0] = clip_point(points[0], next, bound);
+                if options.show_arrows && edge.arrow.has_start_head() {
+                    points[0] = step_outside(points[0], next, bound);
+                }
             }
             if let Some(bound) = bounds.get(&edge.to) {
                 let last = points.len() - 1;
                 let prev = points[last - 1];
                 points[last] = clip_point(points[last], prev, bound);
-                if options.show_arrows {
-                    let dx = (points[last].0 - prev.0).signum();
-                    let dy = (points[last].1 - prev.1).signum();
-                    if dy > 0 {
-                        points[last].1 = bound.top - 1;
-                    } else if dy < 0 {
-                        points[last].1 = bound.bottom + 1;
-                    }
-                    if dx > 0 {
-                        points[last].0 = bound.left - 1;
-                    } else if dx < 0 {
-                        points[last].0 = bound.right + 1;
-                    }
+                if options.show_arrows && edge.arrow.has_end_head() {
+                    points[last] = step_outside(points[last], prev, bound);
                 }
             }
         }
 
         for segment in points.windows(2) {
-            draw_line(&mut grid, segment[0].0, segment[0].1, segment[1].0, segment[1].1);
+            draw_line(&mut grid, segment[0], segment[1], unicode);
         }
-        edge_paths.push(points);
+        edge_paths.push((points, edge.arrow.clone(), edge.label.clone()));
     }
 
     for node in &layout.nodes {
         if node.is_dummy {
             continue;
         }
-        draw_node(&mut grid, node, scale);
+        draw_node(&mut grid, node, scale, unicode);
     }
 
     if options.show_arrows {
-        for points in &edge_paths {
-            draw_arrow(&mut grid, points);
+        for (points, arrow, _) in &edge_paths {
+            if arrow.has_end_head() {
+                draw_arrow(&mut grid, points, arrow, unicode);
+            }
+            if arrow.has_start_head() {
+                let reversed: Vec<(i32, i32)> = points.iter().rev().copied().collect();
+                draw_arrow(&mut grid, &reversed, arrow, unicode);
+            }
+        }
+    }
+
+    if options.show_labels {
+        for (points, _, label) in &edge_paths {
+            if let Some(label) = label.as_deref().filter(|label| !label.trim().is_empty()) {
+                draw_edge_label(&mut grid, points, label, &bounds);
+            }
         }
     }
 
-    grid.into_iter()
-        .map(|row| row.into_iter().collect::<String>().trim_end().to_string())
-        .collect::<Vec<_>>()
-        .join("\n")
+    let lines = grid
+        .into_iter()
+        .map(|row| {
+            row.into_iter()
+                .filter(|ch| *ch != WIDE_CONTINUATION)
+                .map(full_line)
+                .collect::<String>()
+                .trim_end()
+                .to_string()
+        })
+        .collect::<Vec<_>>();
+    if options.center {
+        center_lines(lines, options.max_width, options.max_height).join("\n")
+    } else {
+        lines.join("\n")
+    }
+}
+
+fn center_lines(lines: Vec<String>, max_width: usize, max_height: usize) -> Vec<String> {
+    let first = lines.iter().position(|line| !line.is_empty());
+    let last = lines.iter().rposition(|line| !line.is_empty());
+    let (Some(first), Some(last)) = (first, last) else {
+        return lines;
+    };
+    let content = &lines[first..=last];
+    let indent = content
+        .iter()
+        .filter(|line| !line.is_empty())
+        .map(|line| line.chars().take_while(|ch| *ch == ' ').count())
+        .min()
+        .unwrap_or(0);
+    let content: Vec<String> = content
+        .iter()
+        .map(|line| line.chars().skip(indent).collect())
+        .collect();
+    let content_width = content
+        .iter()
+        .map(|line| display_width(line))
+        .max()
+        .unwrap_or(0);
+
+    let width = max_width.max(content_width);
+    let left = (width - content_width) / 2;
+    let top = max_height.saturating_sub(content.len()) / 2;
+    let bottom = max_height.saturating_sub(content.len() + top);
+
+    let blank = " ".repeat(width);
+    let mut out = Vec::with_capacity(top + content.len() + bottom);
+    out.extend(std::iter::repeat_n(blank.clone(), top));
+    for line in content {
+        let mut row = " ".repeat(left);
+        row.push_str(&line);
+        let len = display_width(&row);
+        row.push_str(&" ".repeat(width - len));
+        out.push(row);
+    }
+    out.extend(std::iter::repeat_n(blank, bottom));
+    out
 }
 
 fn draw_subgraphs(
     grid: &mut [Vec<char>],
     subgraphs: &[LayoutSubgraph],
     bounds: &HashMap<String, Bounds>,
+    unicode: bool,
 ) {
     for subgraph in subgraphs {
-        draw_subgraph(grid, subgraph, bounds);
+        draw_subgraph(grid, subgraph, bounds, unicode);
     }
 }
 
@@ -114,6 +184,7 @@ fn draw_subgraph(
     grid: &mut [Vec<char>],
     subgraph: &LayoutSubgraph,
     bounds: &HashMap<String, Bounds>,
+    unicode: bool,
 ) -> Option<Bounds> {
     let mut min_left = i32::MAX;
     let mut max_right = i32::MIN;
@@ -132,7 +203,7 @@ fn draw_subgraph(
     }
 
     for child in &subgraph.subgraphs {
-        if let Some(child_bounds) = draw_subgraph(grid, child, bounds) {
+        if let Some(child_bounds) = draw_subgraph(grid, child, bounds, unicode) {
             has_node = true;
             min_left = min_left.min(child_bounds.left);
             max_right = max_right.max(child_bounds.right);
@@ -154,34 +225,29 @@ fn draw_subgraph(
     let label = subgraph.title.as_deref().unwrap_or(subgraph.id.as_str());
     if !label.is_empty() {
         let current_width = right - left + 1;
-        let label_width = label.chars().count() as i32 + 2;
+        let label_width = display_width(label) as i32 + 2;
         if label_width > current_width {
             right += label_width - current_width;
         }
     }
 
-    for x in left..=right {
-        set_cell(grid, x, top, '-');
-        set_cell(grid, x, bottom, '-');
-    }
-    for y in top..=bottom {
-        set_cell(grid, left, y, '|');
-        set_cell(grid, right, y, '|');
-    }
+    draw_box(
+        grid,
+        Bounds {
+            left,
+            right,
+            top,
+            bottom,
+        },
+        unicode,
+    );
 
     let label = subgraph.title.as_deref().unwrap_or(subgraph.id.as_str());
     if !label.is_empty() {
         let available = (right - left - 1).max(0) as usize;
         if available > 0 {
-            let mut text = label.to_string();
-            if text.len() > available {
-                text.truncate(available);
-            }
-            let start_x = left + 2;
-            let label_y = top;
-            for (idx, ch) in text.chars().enumerate() {
-                set_cell(grid, start_x + idx as i32, label_y, ch);
-            }
+            let text = truncate_to_width(label, available);
+            put_text(grid, left + 2, top, &text);
         }
     }
 
@@ -199,46 +265,43 @@ fn map_point(point: (f32, f32), scale: f32) -> (i32, i32) {
     (x, y)
 }
 
-fn draw_node(grid: &mut [Vec<char>], node: &LayoutNode, scale: f32) {
+fn draw_node(grid: &mut [Vec<char>], node: &LayoutNode, scale: f32, unicode: bool) {
     let (cx, cy) = map_point((node.x, node.y), scale);
-    let label = node.label.as_deref().unwrap_or(node.id.as_str());
-    let min_width = 3usize;
-    let box_width = (label.chars().count() + 2).max(min_width) as i32;
-    let box_height = 3i32;
+    let lines = label_lines(node.label.as_deref().unwrap_or(node.id.as_str()));
+    let (box_width, box_height) = node_box_size(&lines);
 
     let left = cx - box_width / 2;
     let right = left + box_width - 1;
     let top = cy - box_height / 2;
     let bottom = top + box_height - 1;
 
-    for x in left..=right {
-        set_cell(grid, x, top, '-');
-        set_cell(grid, x, bottom, '-');
-    }
-    for y in top..=bottom {
-        set_cell(grid, left, y, '|');
-        set_cell(grid, right, y, '|');
-    }
-
-    set_cell(grid, left, top, '-');
-    set_cell(grid, right, top, '-');
-    set_cell(grid, left, bottom, '-');
-    set_cell(grid, right, bottom, '-');
+    draw_box(
+        grid,
+        Bounds {
+            left,
+            right,
+            top,
+            bottom,
+        },
+        unicode,
+    );
 
     let available = (right - left - 1).max(0) as usize;
     if available > 0 {
-        let mut text = label.to_string();
-        if text.len() > available {
-            text.truncate(available);
-        }
-        let start_x = left + 1 + ((available.saturating_sub(text.len())) / 2) as i32;
-        let label_y = top + (bottom - top) / 2;
-        for (idx, ch) in text.chars().enumerate() {
-            set_cell(grid, start_x + idx as i32, label_y, ch);
+        for (row, line) in lines.iter().enumerate() {
+            let text = truncate_to_width(line, available);
+            let start_x = left + 1 + ((available.saturating_sub(display_width(&text))) / 2) as i32;
+            put_text(grid, start_x, top + 1 + row as i32, &text);
         }
     }
 }
 
+// One row per label line between the top and bottom borders.
+fn node_box_size(lines: &[&str]) -> (i32, i32) {
+    let widest = lines.iter().map(|line| display_width(line)).max().unwrap_or(0);
+    ((widest + 2).max(3) as i32, lines.len() as i32 + 2)
+}
+
 #[derive(Debug, Clone, Copy)]
 struct Bounds {
     left: i32,
@@ -254,10 +317,8 @@ fn build_bounds(nodes: &[LayoutNode], scale: f32) -> HashMap<String, Bounds> {
             continue;
         }
         let (cx, cy) = map_point((node.x, node.y), scale);
-        let label = node.label.as_deref().unwrap_or(node.id.as_str());
-        let min_width = 3usize;
-        let box_width = (label.chars().count() + 2).max(min_width) as i32;
-        let box_height = 3i32;
+        let lines = label_lines(node.label.as_deref().unwrap_or(node.id.as_str()));
+        let (box_width, box_height) = node_box_size(&lines);
 
         let left = cx - box_width / 2;
         let right = left + box_width - 1;
@@ -293,11 +354,29 @@ fn clip_point(point: (i32, i32), other: (i32, i32), bounds: &Bounds) -> (i32, i3
     (x, y)
 }
 
-fn draw_line(grid: &mut [Vec<char>], x1: i32, y1: i32, x2: i32, y2: i32) {
+fn draw_box(grid: &mut [Vec<char>], bounds: Bounds, unicode: bool) {
+    let Bounds {
+        left,
+        right,
+        top,
+        bottom,
+    } = bounds;
+    draw_line(grid, (left, top), (right, top), unicode);
+    draw_line(grid, (left, bottom), (right, bottom), unicode);
+    draw_line(grid, (left, top), (left, bottom), unicode);
+    draw_line(grid, (right, top), (right, bottom), unicode);
+}
+
+fn draw_line(grid: &mut [Vec<char>], (x1, y1): (i32, i32), (x2, y2): (i32, i32), unicode: bool) {
     if x1 == x2 {
         let (start, end) = if y1 <= y2 { (y1, y2) } else { (y2, y1) };
         for y in start..=end {
-            set_cell(grid, x1, y, '|');
+            let arms = if start == end {
+                UP | DOWN
+            } else {
+                (if y > start { UP } else { 0 }) | (if y < end { DOWN } else { 0 })
+            };
+            set_line(grid, x1, y, arms, unicode);
         }
         return;
     }
@@ -305,15 +384,15 @@ fn draw_line(grid: &mut [Vec<char>], x1: i32, y1: i32, x2: i32, y2: i32) {
     if y1 == y2 {
         let (start, end) = if x1 <= x2 { (x1, x2) } else { (x2, x1) };
         for x in start..=end {
-            set_cell(grid, x, y1, '-');
+            let arms = (if x > start { LEFT } else { 0 }) | (if x < end { RIGHT } else { 0 });
+            set_line(grid, x, y1, arms, unicode);
         }
         return;
     }
 
     // Manhattan corner: draw two segments.
-    draw_line(grid, x1, y1, x1, y2);
-    draw_line(grid, x1, y2, x2, y2);
-    set_cell(grid, x1, y2, '-');
+    draw_line(grid, (x1, y1), (x1, y2), unicode);
+    draw_line(grid, (x1, y2), (x2, y2), unicode);
 }
 
 fn set_cell(grid: &mut [Vec<char>], x: i32, y: i32, ch: char) {
@@ -327,25 +406,254 @@ fn set_cell(grid: &mut [Vec<char>], x: i32, y: i32, ch: char) {
     }
 
     let existing = grid[y][x];
-    if existing == ' ' || existing == ch || existing == '-' || existing == '|' {
-        grid[y][x] = merge_char(existing, ch);
+    if existing == ' ' || line_arms(existing).is_some() {
+        grid[y][x] = ch;
     }
 }
 
-fn merge_char(existing: char, incoming: char) -> char {
-    if existing == ' ' {
-        return incoming;
+// Adds line arms to a cell, joining them with whatever line already runs through it.
+// Text is never overwritten.
+fn set_line(grid: &mut [Vec<char>], x: i32, y: i32, arms: u8, unicode: bool) {
+    if y < 0 || x < 0 {
+        return;
     }
-    if existing == incoming {
-        return existing;
+    let y = y as usize;
+    let x = x as usize;
+    if y >= grid.len() || x >= grid[y].len() {
+        return;
+    }
+
+    let existing = grid[y][x];
+    let merged = match existing {
+        ' ' => arms,
+        _ => match line_arms(existing) {
+            Some(existing_arms) => existing_arms | arms,
+            None => return,
+        },
+    };
+    grid[y][x] = line_glyph(merged, unicode);
+}
+
+const UP: u8 = 1;
+const DOWN: u8 = 2;
+const LEFT: u8 = 4;
+const RIGHT: u8 = 8;
+
+// Half lines keep a lone arm exact while drawing, so a corner joins as `┌` rather
+// than `┬`; `full_line` widens any that are left over.
+const BOX_GLYPHS: [(u8, char); 15] = [
+    (UP, '╵'),
+    (DOWN, '╷'),
+    (LEFT, '╴'),
+    (RIGHT, '╶'),
+    (UP | DOWN, '│'),
+    (LEFT | RIGHT, '─'),
+    (DOWN | RIGHT, '┌'),
+    (DOWN | LEFT, '┐'),
+    (UP | RIGHT, '└'),
+    (UP | LEFT, '┘'),
+    (UP | DOWN | RIGHT, '├'),
+    (UP | DOWN | LEFT, '┤'),
+    (DOWN | LEFT | RIGHT, '┬'),
+    (UP | LEFT | RIGHT, '┴'),
+    (UP | DOWN | LEFT | RIGHT, '┼'),
+];
+
+// Plain ASCII can't show junctions, so any cell with a horizontal arm reads as `-`.
+fn line_glyph(arms: u8, unicode: bool) -> char {
+    if !unicode {
+        return if arms & (LEFT | RIGHT) != 0 { '-' } else { '|' };
+    }
+    BOX_GLYPHS
+        .iter()
+        .find(|(glyph_arms, _)| *glyph_arms == arms)
+        .map_or('│', |(_, glyph)| *glyph)
+}
+
+fn full_line(ch: char) -> char {
+    match ch {
+        '╵' | '╷' => '│',
+        '╴' | '╶' => '─',
+        _ => ch,
     }
-    match (existing, incoming) {
-        ('-', '|') | ('|', '-') => '-',
-        _ => incoming,
+}
+
+fn line_arms(ch: char) -> Option<u8> {
+    match ch {
+        '-' => Some(LEFT | RIGHT),
+        '|' => Some(UP | DOWN),
+        _ => BOX_GLYPHS
+            .iter()
+            .find(|(_, glyph)| *glyph == ch)
+            .map(|(arms, _)| *arms),
     }
 }
 
-fn draw_arrow(grid: &mut [Vec<char>], points: &[(i32, i32)]) {
+// Grid points, the edge's arrow (which picks the ends and their glyphs), and its label.
+type EdgePath = (Vec<(i32, i32)>, EdgeArrow, Option<String>);
+
+// Writes the label over the horizontal segment nearest the middle of the path, keeping a
+// line cell at each end. Paths with no room on a horizontal run get the label beside the
+// midpoint instead, as far right as the blank cells allow.
+fn draw_edge_label(
+    grid: &mut [Vec<char>],
+    points: &[(i32, i32)],
+    label: &str,
+    bounds: &HashMap<String, Bounds>,
+) {
+    let text = label_lines(label).join(" ");
+    let segments = manhattan_segments(points);
+    let total: i32 = segments.iter().map(|(a, b)| segment_len(*a, *b)).sum();
+    if segments.is_empty() {
+        return;
+    }
+
+    let mut midpoint = segments[0].0;
+    let mut travelled = 0;
+    let mut segment_mids = Vec::with_capacity(segments.len());
+    for &(a, b) in &segments {
+        let len = segment_len(a, b);
+        if travelled <= total / 2 && total / 2 <= travelled + len {
+            let step = total / 2 - travelled;
+            midpoint = (a.0 + (b.0 - a.0).signum() * step, a.1 + (b.1 - a.1).signum() * step);
+        }
+        segment_mids.push(travelled + len / 2);
+        travelled += len;
+    }
+
+    let horizontal = segments
+        .iter()
+        .zip(&segment_mids)
+        .filter(|((a, b), _)| {
+            // Room for the whole label, or at least a few characters before the `…`.
+            let room = segment_len(*a, *b) - 3;
+            a.1 == b.1 && (room >= display_width(&text) as i32 || room >= 4)
+        })
+        .min_by_key(|(_, mid)| (**mid - total / 2).abs());
+    if let Some((&(a, b), _)) = horizontal {
+        let (start, end) = (a.0.min(b.0), a.0.max(b.0));
+        let available = (end - start - 3) as usize;
+        let text = fit_label(&text, available);
+        let x = start + 2 + ((available - display_width(&text)) / 2) as i32;
+        put_label(grid, x, a.1, &text, bounds);
+        return;
+    }
+
+    // Beside the midpoint, trying the right then the left side on its row and the rows
+    // around it: the first spot the whole label fits wins, otherwise the roomiest one.
+    let width = display_width(&text);
+    let mut best: Option<(usize, i32, i32, bool)> = None;
+    for y in [midpoint.1, midpoint.1 - 1, midpoint.1 + 1] {
+        let free = |cx: &i32| cell(grid, *cx, y) == Some(' ') && !inside_any(bounds, *cx, y);
+        let x = midpoint.0;
+        let right = (x + 2..).take_while(free).count().saturating_sub(1);
+        let left = (0..=x - 2).rev().take_while(free).count().saturating_sub(1);
+        for (room, on_right) in [(right, true), (left, false)] {
+            if best.is_none_or(|(best_room, ..)| best_room < width.min(room)) {
+                best = Some((width.min(room), x, y, on_right));
+            }
+        }
+        if best.is_some_and(|(room, ..)| room == width) {
+            break;
+        }
+    }
+    let Some((room, x, y, on_right)) = best else {
+        return;
+    };
+    let text = fit_label(&text, room);
+    let x = if on_right { x + 2 } else { x - 1 - display_width(&text) as i32 };
+    put_label(grid, x, y, &text, bounds);
+}
+
+// Splits diagonal steps the way `draw_line` draws them, vertical first, then joins
+// collinear runs so a straight edge through dummy points is one segment.
+fn manhattan_segments(points: &[(i32, i32)]) -> Vec<((i32, i32), (i32, i32))> {
+    let mut segments: Vec<((i32, i32), (i32, i32))> = Vec::new();
+    for pair in points.windows(2) {
+        let (a, b) = (pair[0], pair[1]);
+        let steps = if a.0 != b.0 && a.1 != b.1 {
+            vec![(a, (a.0, b.1)), ((a.0, b.1), b)]
+        } else {
+            vec![(a, b)]
+        };
+        for (a, b) in steps.into_iter().filter(|(a, b)| a != b) {
+            match segments.last_mut() {
+                Some(last) if (last.0.0 == b.0 && a.0 == b.0) || (last.0.1 == b.1 && a.1 == b.1) => {
+                    last.1 = b;
+                }
+                _ => segments.push((a, b)),
+            }
+        }
+    }
+    segments
+}
+
+fn segment_len(a: (i32, i32), b: (i32, i32)) -> i32 {
+    (b.0 - a.0).abs() + (b.1 - a.1).abs()
+}
+
+fn fit_label(text: &str, max_width: usize) -> String {
+    if display_width(text) <= max_width {
+        return text.to_string();
+    }
+    if max_width == 0 {
+        return String::new();
+    }
+    let mut truncated = truncate_to_width(text, max_width - 1).trim_end().to_string();
+    truncated.push('…');
+    truncated
+}
+
+fn put_label(
+    grid: &mut [Vec<char>],
+    x: i32,
+    y: i32,
+    text: &str,
+    bounds: &HashMap<String, Bounds>,
+) {
+    let mut x = x;
+    for ch in text.chars() {
+        let width = char_width(ch) as i32;
+        if (x..x + width).all(|cx| !inside_any(bounds, cx, y)) {
+            set_cell(grid, x, y, ch);
+            if width == 2 {
+                set_cell(grid, x + 1, y, WIDE_CONTINUATION);
+            }
+        }
+        x += width;
+    }
+}
+
+fn inside_any(bounds: &HashMap<String, Bounds>, x: i32, y: i32) -> bool {
+    bounds.values().any(|bound| {
+        x >= bound.left && x <= bound.right && y >= bound.top && y <= bound.bottom
+    })
+}
+
+fn cell(grid: &[Vec<char>], x: i32, y: i32) -> Option<char> {
+    let row = grid.get(usize::try_from(y).ok()?)?;
+    row.get(usize::try_from(x).ok()?).copied()
+}
+
+// Moves an endpoint that sits on a node border one cell out, leaving room for the head.
+fn step_outside(point: (i32, i32), toward: (i32, i32), bound: &Bounds) -> (i32, i32) {
+    let dx = (point.0 - toward.0).signum();
+    let dy = (point.1 - toward.1).signum();
+    let mut point = point;
+    if dy > 0 {
+        point.1 = bound.top - 1;
+    } else if dy < 0 {
+        point.1 = bound.bottom + 1;
+    }
+    if dx > 0 {
+        point.0 = bound.left - 1;
+    } else if dx < 0 {
+        point.0 = bound.right + 1;
+    }
+    point
+}
+
+fn draw_arrow(grid: &mut [Vec<char>], points: &[(i32, i32)], arrow: &EdgeArrow, unicode: bool) {
     if points.len() < 2 {
         return;
     }
@@ -358,14 +666,23 @@ fn draw_arrow(grid: &mut [Vec<char>], points: &[(i32, i32)]) {
     }
     let arrow_x = x2;
     let arrow_y = y2;
-    let ch = if dx > 0 {
-        '>'
+    let (right, left, down, up) = if unicode {
+        ('▶', '◀', '▼', '▲')
+    } else {
+        ('>', '<', 'v', '^')
+    };
+    let ch = if *arrow == EdgeArrow::Cross {
+        'x'
+    } else if *arrow == EdgeArrow::Circle {
+        'o'
+    } else if dx > 0 {
+        right
     } else if dx < 0 {
-        '<'
+        left
     } else if dy > 0 {
-        'v'
+        down
     } else {
-        '^'
+        up
     };
     set_arrow_cell(grid, arrow_x, arrow_y, ch);
 }
@@ -386,8 +703,36 @@ fn max_subgraph_label_len(subgraphs: &[LayoutSubgraph]) -> usize {
     let mut max_len = 0usize;
     for subgraph in subgraphs {
         let label = subgraph.title.as_deref().unwrap_or(subgraph.id.as_str());
-        max_len = max_len.max(label.chars().count());
+        max_len = max_len.max(display_width(label));
         max_len = max_len.max(max_subgraph_label_len(&subgraph.subgraphs));
     }
     max_len
 }
+
+// Marks the second grid cell covered by a double-width character; skipped on output.
+const WIDE_CONTINUATION: char = '\0';
+
+fn put_text(grid: &mut [Vec<char>], x: i32, y: i32, text: &str) {
+    let mut x = x;
+    for ch in text.chars() {
+        set_cell(grid, x, y, ch);
+        if char_width(ch) == 2 {
+            set_cell(grid, x + 1, y, WIDE_CONTINUATION);
+        }
+        x += char_width(ch) as i32;
+    }
+}
+
+fn truncate_to_width(text: &str, max_width: usize) -> String {
+    let mut width = 0;
+    text.chars()
+        .take_while(|ch| {
+            width += char_width(*ch);
+            width <= max_width
+        })
+        .collect()
+}
+
+fn display_width(text: &str) -> usize {
+    text.chars().map(char_width).sum()
+}
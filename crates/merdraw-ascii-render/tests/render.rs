@@ -1,6 +1,6 @@
 use merdraw_ascii_render::{render_ascii, AsciiRenderOptions};
 use merdraw_layout::{layout_flowchart, LayoutStyle};
-use merdraw_parser::parse_flowchart;
+use merdraw_parser::{parse_flowchart, EdgeStyle};
 
 #[test]
 fn renders_basic_ascii() {
@@ -22,6 +22,33 @@ fn renders_arrowheads_for_edges() {
     assert!(arrow_count >= 2);
 }
 
+#[test]
+fn hides_arrowheads_when_disabled() {
+    let graph = parse_flowchart("flowchart TB\nA-->B\nA<-->C\n").expect("parse failed");
+    let layout = layout_flowchart(&graph, &LayoutStyle::default());
+    let options = AsciiRenderOptions {
+        show_arrows: false,
+        ..AsciiRenderOptions::default()
+    };
+    let output = render_ascii(&layout, &options);
+    assert!(!output.contains(['v', '^', '<', '>']));
+}
+
+#[test]
+fn renders_heads_at_both_ends_of_bidirectional_edges() {
+    let graph = parse_flowchart("flowchart TB\nA<-->B\n").expect("parse failed");
+    let layout = layout_flowchart(&graph, &LayoutStyle::default());
+    let output = render_ascii(&layout, &AsciiRenderOptions::default());
+    assert!(output.contains('^'), "{output}");
+    assert!(output.contains('v'), "{output}");
+
+    let graph = parse_flowchart("flowchart TB\nA<--B\n").expect("parse failed");
+    let layout = layout_flowchart(&graph, &LayoutStyle::default());
+    let output = render_ascii(&layout, &AsciiRenderOptions::default());
+    assert!(output.contains('^'), "{output}");
+    assert!(!output.contains('v'), "{output}");
+}
+
 #[test]
 fn keeps_box_borders_intact() {
     let graph = parse_flowchart("flowchart TB\nA-->B\n").expect("parse failed");
@@ -55,6 +82,15 @@ fn prefers_node_label_over_id() {
     assert!(output.contains("Alpha Beta"));
 }
 
+#[test]
+fn draws_id_for_empty_shaped_labels() {
+    let graph = parse_flowchart("flowchart TB\nAlpha[]-->Beta()\n").expect("parse failed");
+    let layout = layout_flowchart(&graph, &LayoutStyle::default());
+    let output = render_ascii(&layout, &AsciiRenderOptions::default());
+    assert!(output.contains("Alpha"));
+    assert!(output.contains("Beta"));
+}
+
 #[test]
 fn renders_subgraph_title() {
     let graph = parse_flowchart(
@@ -97,3 +133,177 @@ fn max_run(line: &str, ch: char) -> usize {
     }
     best
 }
+
+#[test]
+fn centers_diagram_within_max_size() {
+    let graph = parse_flowchart("flowchart TB\nA-->B\n").expect("parse failed");
+    let layout = layout_flowchart(&graph, &LayoutStyle::default());
+    let options = AsciiRenderOptions {
+        max_width: 60,
+        max_height: 30,
+        center: true,
+        ..AsciiRenderOptions::default()
+    };
+    let output = render_ascii(&layout, &options);
+    let lines = output.lines().collect::<Vec<_>>();
+    assert_eq!(lines.len(), 30);
+    assert!(lines.iter().all(|line| line.chars().count() == 60));
+
+    let above = lines.iter().take_while(|line| line.trim().is_empty()).count();
+    let below = lines
+        .iter()
+        .rev()
+        .take_while(|line| line.trim().is_empty())
+        .count();
+    assert!(above > 0);
+    assert!(above.abs_diff(below) <= 1);
+
+    let left = lines
+        .iter()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| line.len() - line.trim_start().len())
+        .min()
+        .unwrap();
+    let right = lines
+        .iter()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| line.len() - line.trim_end().len())
+        .min()
+        .unwrap();
+    assert!(left.abs_diff(right) <= 1);
+}
+
+#[test]
+fn sizes_box_for_double_width_labels() {
+    let graph = parse_flowchart("flowchart TB\nA[漢字ラベル]\n").expect("parse failed");
+    let layout = layout_flowchart(&graph, &LayoutStyle::default());
+    let output = render_ascii(&layout, &AsciiRenderOptions::default());
+    let lines = output.lines().collect::<Vec<_>>();
+    let (label_line, label_index) = find_label_line(&lines, "漢字ラベル").expect("label not found");
+    let top_border = lines.get(label_index.saturating_sub(1)).unwrap();
+    // Five wide characters take ten terminal columns, plus the two borders.
+    assert!(max_run(top_border, '-') >= 12);
+    let label_start = label_line.find('|').unwrap();
+    let inner = &label_line[label_start + 1..];
+    let inner = &inner[..inner.find('|').expect("right border missing")];
+    assert!(inner.contains("漢字ラベル"));
+}
+
+#[test]
+fn invisible_edges_shape_layers_but_are_not_drawn() {
+    let graph = parse_flowchart("flowchart TB\nA ~~~ B\nB ~~~ C\n").expect("parse failed");
+    assert!(graph.edges.iter().all(|edge| edge.style == EdgeStyle::Invisible));
+    let layout = layout_flowchart(&graph, &LayoutStyle::default());
+    let layer = |id: &str| layout.nodes.iter().find(|node| node.id == id).unwrap().layer;
+    assert!(layer("A") < layer("B") && layer("B") < layer("C"));
+
+    let output = render_ascii(&layout, &AsciiRenderOptions::default());
+    let mut bare = layout.clone();
+    bare.edges.clear();
+    assert_eq!(output, render_ascii(&bare, &AsciiRenderOptions::default()));
+}
+
+#[test]
+fn bt_arrows_point_upward() {
+    for source in [
+        "flowchart BT\nA-->B-->C\n",
+        "flowchart BT\nsubgraph g\nA-->B\nend\nB-->C\n",
+    ] {
+        let graph = parse_flowchart(source).expect("parse failed");
+        let layout = layout_flowchart(&graph, &LayoutStyle::default());
+        let output = render_ascii(&layout, &AsciiRenderOptions::default());
+        assert!(output.matches('^').count() >= 2, "{source}\n{output}");
+        assert!(!output.contains('v'), "{source}\n{output}");
+    }
+}
+
+#[test]
+fn grouped_rl_arrows_point_left() {
+    for source in [
+        "flowchart RL\nA-->B-->C\n",
+        "flowchart RL\nsubgraph g\nA-->B\nend\nB-->C\n",
+    ] {
+        let graph = parse_flowchart(source).expect("parse failed");
+        let layout = layout_flowchart(&graph, &LayoutStyle::default());
+        let output = render_ascii(&layout, &AsciiRenderOptions::default());
+        assert!(output.matches('<').count() >= 2, "{source}\n{output}");
+        assert!(!output.contains('>'), "{source}\n{output}");
+        let column = |id: &str| output.lines().find_map(|line| line.find(&format!("|{id}|")));
+        assert!(column("C") < column("B") && column("B") < column("A"), "{source}\n{output}");
+    }
+}
+
+#[test]
+fn leading_glyph_stays_inside_the_box() {
+    let graph = parse_flowchart("flowchart TB\nA[🚀 Launch]-->B\n").expect("parse failed");
+    let layout = layout_flowchart(&graph, &LayoutStyle::default());
+    let output = render_ascii(&layout, &AsciiRenderOptions::default());
+    let line = output.lines().find(|line| line.contains('🚀')).expect("glyph missing");
+    assert!(line.contains("|🚀 Launch|") || line.contains("| 🚀 Launch |"), "{output}");
+}
+
+#[test]
+fn br_labels_render_on_separate_rows() {
+    let graph = parse_flowchart("flowchart TB\nA[First<br>Second line]-->B\n").expect("parse failed");
+    let layout = layout_flowchart(&graph, &LayoutStyle::default());
+    let output = render_ascii(&layout, &AsciiRenderOptions::default());
+    let rows: Vec<&str> = output.lines().collect();
+    let first = rows.iter().position(|row| row.contains("First")).expect("first line missing");
+    assert!(rows[first + 1].contains("Second line"), "{output}");
+    assert!(!output.contains("<br>"));
+}
+
+#[test]
+fn unicode_option_draws_box_characters_and_triangle_heads() {
+    let graph = parse_flowchart("flowchart TB\nsubgraph g [Group]\nA-->B\nend\nA-->C\n")
+        .expect("parse failed");
+    let layout = layout_flowchart(&graph, &LayoutStyle::default());
+    let options = AsciiRenderOptions {
+        unicode: true,
+        ..AsciiRenderOptions::default()
+    };
+    let output = render_ascii(&layout, &options);
+    assert!(output.contains('┌') && output.contains('─'), "{output}");
+    assert!(output.contains(['┘', '└', '┐']), "{output}");
+    assert!(output.contains('▼'), "{output}");
+    assert!(!output.contains(['-', '|', 'v']), "{output}");
+
+    let plain = render_ascii(&layout, &AsciiRenderOptions::default());
+    assert!(!plain.contains(['┌', '─', '▼']), "{plain}");
+}
+
+#[test]
+fn cross_and_circle_edges_draw_their_own_markers() {
+    for (source, marker) in [
+        ("flowchart TB\nA --x B\n", 'x'),
+        ("flowchart TB\nA -.-o B\n", 'o'),
+        ("flowchart LR\nA ==x B\n", 'x'),
+    ] {
+        let graph = parse_flowchart(source).expect("parse failed");
+        let layout = layout_flowchart(&graph, &LayoutStyle::default());
+        let output = render_ascii(&layout, &AsciiRenderOptions::default());
+        assert_eq!(output.matches(marker).count(), 1, "{source}\n{output}");
+        assert!(!output.contains(['v', '>']), "{source}\n{output}");
+    }
+
+    let graph = parse_flowchart("flowchart TB\nA <--> B\n").expect("parse failed");
+    let layout = layout_flowchart(&graph, &LayoutStyle::default());
+    let output = render_ascii(&layout, &AsciiRenderOptions::default());
+    assert!(output.contains('v') && output.contains('^'), "{output}");
+}
+
+#[test]
+fn edge_labels_are_drawn_unless_disabled() {
+    for source in ["flowchart TB\nA-->|go|B\n", "flowchart LR\nA-->|go|B\n"] {
+        let graph = parse_flowchart(source).expect("parse failed");
+        let layout = layout_flowchart(&graph, &LayoutStyle::default());
+        let output = render_ascii(&layout, &AsciiRenderOptions::default());
+        assert!(output.contains("go"), "{source}\n{output}");
+
+        let options = AsciiRenderOptions {
+            show_labels: false,
+            ..AsciiRenderOptions::default()
+        };
+        assert!(!render_ascii(&layout, &options).contains("go"), "{source}");
+    }
+}
@@ -0,0 +1,101 @@
+use merdraw_ascii_render::render_ascii;
+use merdraw_layout::{layout_flowchart, LayerAssignment};
+use merdraw_parser::parse_flowchart;
+
+pub use merdraw_ascii_render::AsciiRenderOptions;
+pub use merdraw_layout::{LayoutGraph, LayoutStyle};
+pub use merdraw_parser::{Graph, ParseError};
+#[cfg(feature = "skia")]
+pub use merdraw_skia_render::{ImageFormat, SkiaLayoutOptions, SkiaRenderError, SkiaRenderOptions};
+
+#[derive(Debug)]
+pub enum Error {
+    Parse(ParseError),
+    #[cfg(feature = "skia")]
+    Render(SkiaRenderError),
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::Parse(err) => write!(f, "parse failed: {err}"),
+            #[cfg(feature = "skia")]
+            Error::Render(err) => write!(f, "render failed: {err}"),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Parse(err) => Some(err),
+            #[cfg(feature = "skia")]
+            Error::Render(err) => Some(err),
+        }
+    }
+}
+
+impl From<ParseError> for Error {
+    fn from(err: ParseError) -> Self {
+        Error::Parse(err)
+    }
+}
+
+#[cfg(feature = "skia")]
+impl From<SkiaRenderError> for Error {
+    fn from(err: SkiaRenderError) -> Self {
+        Error::Render(err)
+    }
+}
+
+// The tighter style the CLI uses for ASCII output: character cells are much coarser than
+// pixels, so the default gaps would leave the text grid mostly empty.
+pub fn ascii_layout_style() -> LayoutStyle {
+    LayoutStyle {
+        min_width: 24.0,
+        min_height: 16.0,
+        char_width: 6.0,
+        char_height: 10.0,
+        node_padding_x: 6.0,
+        node_padding_y: 4.0,
+        node_gap: 8.0,
+        layer_gap: 12.0,
+        dummy_width: 6.0,
+        straighten_tolerance: 0.0,
+        layer_assignment: LayerAssignment::LongestPath,
+        direction: None,
+        extent_includes_edges: false,
+        virtual_group_margin: 0.0,
+        node_gap_lr: None,
+        layer_gap_lr: None,
+    }
+}
+
+pub fn render_mermaid_ascii(source: &str, options: &AsciiRenderOptions) -> Result<String, Error> {
+    let graph = parse_flowchart(source)?;
+    let layout = layout_flowchart(&graph, &ascii_layout_style());
+    Ok(render_ascii(&layout, options))
+}
+
+// A zero `width` or `height` in `options` sizes the canvas to fit the layout, as the CLI does
+// when neither `--width` nor `--height` is given.
+#[cfg(feature = "skia")]
+pub fn render_mermaid(
+    source: &str,
+    format: ImageFormat,
+    options: &SkiaRenderOptions,
+) -> Result<Vec<u8>, Error> {
+    let graph = parse_flowchart(source)?;
+    let layout =
+        merdraw_skia_render::layout_flowchart_skia(&graph, options, &SkiaLayoutOptions::default())?;
+    if options.width > 0 && options.height > 0 {
+        return Ok(merdraw_skia_render::render_to_bytes(&layout, format, options)?);
+    }
+    let (width, height) = merdraw_layout::suggest_canvas_size(&layout, options.padding, 1.0);
+    let options = SkiaRenderOptions {
+        width,
+        height: height + merdraw_skia_render::footer_band_height(options).ceil() as u32,
+        ..options.clone()
+    };
+    Ok(merdraw_skia_render::render_to_bytes(&layout, format, &options)?)
+}
@@ -0,0 +1,69 @@
+use merdraw_core::{render_mermaid_ascii, AsciiRenderOptions, Error};
+#[cfg(feature = "skia")]
+use merdraw_core::{render_mermaid, ImageFormat, SkiaRenderOptions};
+
+#[cfg(feature = "skia")]
+#[test]
+fn renders_png_in_one_call() {
+    let bytes = render_mermaid(
+        "flowchart TB\nA-->B\n",
+        ImageFormat::Png,
+        &SkiaRenderOptions::default(),
+    )
+    .expect("render failed");
+    assert!(bytes.starts_with(&[0x89, b'P', b'N', b'G']));
+}
+
+#[cfg(feature = "skia")]
+#[test]
+fn zero_size_fits_the_canvas_to_the_layout() {
+    let options = SkiaRenderOptions {
+        width: 0,
+        height: 0,
+        ..SkiaRenderOptions::default()
+    };
+    let bytes = render_mermaid("flowchart LR\nA-->B\n", ImageFormat::Png, &options)
+        .expect("render failed");
+    let width = u32::from_be_bytes(bytes[16..20].try_into().unwrap());
+    let height = u32::from_be_bytes(bytes[20..24].try_into().unwrap());
+    assert!(width > 0 && height > 0 && width > height);
+}
+
+#[test]
+fn renders_ascii_in_one_call() {
+    let output = render_mermaid_ascii("flowchart TB\nA-->B\n", &AsciiRenderOptions::default())
+        .expect("render failed");
+    assert!(output.contains('A'));
+    assert!(output.contains('B'));
+    assert!(output.contains('v'));
+}
+
+#[test]
+fn parse_errors_come_back_located() {
+    let source = "flowchart TB\nA --> é\n";
+    let err = render_mermaid_ascii(source, &AsciiRenderOptions::default())
+        .expect_err("should not parse");
+    let Error::Parse(parse) = &err else {
+        panic!("expected a parse error, got {err:?}");
+    };
+    assert_eq!((parse.line, parse.column), (2, 7));
+    assert!(err.to_string().starts_with("parse failed: unexpected character 'é' at 2:7"));
+    #[cfg(feature = "skia")]
+    assert!(render_mermaid(source, ImageFormat::Png, &SkiaRenderOptions::default()).is_err());
+}
+
+#[cfg(feature = "skia")]
+#[test]
+fn render_errors_display_without_debug_formatting() {
+    let options = SkiaRenderOptions {
+        width: 1,
+        height: 1,
+        font_path: Some("does/not/exist.ttf".into()),
+        ..SkiaRenderOptions::default()
+    };
+    let err = render_mermaid("flowchart TB\nA-->B\n", ImageFormat::Png, &options)
+        .expect_err("missing font should fail");
+    let message = err.to_string();
+    assert!(message.starts_with("render failed: failed to read font"), "{message}");
+    assert!(!message.contains("FontLoadFailed"), "{message}");
+}
@@ -0,0 +1,135 @@
+use merdraw_parser::{Direction, EdgeArrow, EdgeStyle, Graph, NodeShape, Subgraph};
+
+// Writes `graph` as Graphviz DOT. Charts whose edges are all headless become an
+// undirected `graph`; otherwise edges use `->` and carry `dir` when their heads differ
+// from the default.
+pub fn to_dot(graph: &Graph) -> String {
+    let directed = graph.edges.iter().any(|edge| edge.arrow != EdgeArrow::None);
+    let (keyword, op) = if directed {
+        ("digraph", "->")
+    } else {
+        ("graph", "--")
+    };
+    let rankdir = match graph.direction {
+        Direction::TB => "TB",
+        Direction::BT => "BT",
+        Direction::LR => "LR",
+        Direction::RL => "RL",
+    };
+
+    let mut out = format!("{keyword} G {{\n");
+    out.push_str(&format!("  rankdir={rankdir};\n"));
+    out.push_str("  node [shape=box];\n");
+    for node in &graph.nodes {
+        let mut attrs = Vec::new();
+        if let Some(label) = node.label.as_deref() {
+            attrs.push(format!("label={}", quote(label)));
+        }
+        attrs.extend(shape_attrs(&node.shape).iter().map(|attr| attr.to_string()));
+        out.push_str(&format!("  {}{};\n", id(&node.id), attr_list(&attrs)));
+    }
+    for subgraph in &graph.subgraphs {
+        write_subgraph(&mut out, subgraph, 1);
+    }
+    for set in &graph.same_rank {
+        let members: Vec<String> = set.iter().map(|member| id(member)).collect();
+        out.push_str(&format!("  {{ rank=same; {} }}\n", members.join("; ")));
+    }
+    for edge in &graph.edges {
+        let mut attrs = Vec::new();
+        if let Some(label) = edge.label.as_deref() {
+            attrs.push(format!("label={}", quote(label)));
+        }
+        match edge.style {
+            EdgeStyle::Solid => {}
+            EdgeStyle::Dotted => attrs.push("style=dotted".to_string()),
+            EdgeStyle::Thick => attrs.push("style=bold".to_string()),
+            EdgeStyle::Invisible => attrs.push("style=invis".to_string()),
+        }
+        if directed {
+            match edge.arrow {
+                EdgeArrow::Forward => {}
+                EdgeArrow::None => attrs.push("dir=none".to_string()),
+                EdgeArrow::Backward => attrs.push("dir=back".to_string()),
+                EdgeArrow::Both => attrs.push("dir=both".to_string()),
+                // Graphviz has no cross head; `tee` is the closest bar-shaped one.
+                EdgeArrow::Cross => attrs.push("arrowhead=tee".to_string()),
+                EdgeArrow::Circle => attrs.push("arrowhead=odot".to_string()),
+            }
+        }
+        out.push_str(&format!(
+            "  {} {op} {}{};\n",
+            id(&edge.from),
+            id(&edge.to),
+            attr_list(&attrs)
+        ));
+    }
+    out.push_str("}\n");
+    out
+}
+
+fn write_subgraph(out: &mut String, subgraph: &Subgraph, depth: usize) {
+    let indent = "  ".repeat(depth);
+    // Graphviz only frames subgraphs whose names start with `cluster`.
+    let name = if subgraph.id.starts_with("cluster") {
+        subgraph.id.clone()
+    } else {
+        format!("cluster_{}", subgraph.id)
+    };
+    out.push_str(&format!("{indent}subgraph {} {{\n", id(&name)));
+    let title = subgraph.title.as_deref().unwrap_or(subgraph.id.as_str());
+    out.push_str(&format!("{indent}  label={};\n", quote(title)));
+    for member in &subgraph.nodes {
+        out.push_str(&format!("{indent}  {};\n", id(member)));
+    }
+    for child in &subgraph.subgraphs {
+        write_subgraph(out, child, depth + 1);
+    }
+    out.push_str(&format!("{indent}}}\n"));
+}
+
+fn shape_attrs(shape: &NodeShape) -> &'static [&'static str] {
+    match shape {
+        NodeShape::Plain | NodeShape::Bracket => &[],
+        NodeShape::Round => &["shape=ellipse"],
+        NodeShape::Circle => &["shape=circle"],
+        NodeShape::Diamond => &["shape=diamond"],
+        NodeShape::Hexagon => &["shape=hexagon"],
+        NodeShape::Stadium => &["style=rounded"],
+        NodeShape::Subroutine => &["peripheries=2"],
+        NodeShape::Cylinder => &["shape=cylinder"],
+    }
+}
+
+fn attr_list(attrs: &[String]) -> String {
+    if attrs.is_empty() {
+        String::new()
+    } else {
+        format!(" [{}]", attrs.join(", "))
+    }
+}
+
+const KEYWORDS: [&str; 6] = ["node", "edge", "graph", "digraph", "subgraph", "strict"];
+
+fn id(value: &str) -> String {
+    let bare = !value.is_empty()
+        && value.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+        && !value.starts_with(|c: char| c.is_ascii_digit())
+        && !KEYWORDS.iter().any(|keyword| keyword.eq_ignore_ascii_case(value));
+    if bare { value.to_string() } else { quote(value) }
+}
+
+fn quote(value: &str) -> String {
+    let mut out = String::with_capacity(value.len() + 2);
+    out.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            _ => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
@@ -0,0 +1,148 @@
+use merdraw_parser::ParseError;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TokenKind {
+    Id(String),
+    // Quoted and HTML strings: ids that never read as keywords.
+    QuotedId(String),
+    LBrace,
+    RBrace,
+    LBracket,
+    RBracket,
+    Equals,
+    Semicolon,
+    Comma,
+    Colon,
+    Arrow,
+    Line,
+    Eof,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Token {
+    pub kind: TokenKind,
+    pub start: usize,
+}
+
+pub struct Lexer<'a> {
+    input: &'a str,
+    pos: usize,
+}
+
+impl<'a> Lexer<'a> {
+    pub fn new(input: &'a str) -> Self {
+        Self { input, pos: 0 }
+    }
+
+    pub fn next_token(&mut self) -> Result<Token, ParseError> {
+        self.skip_trivia()?;
+        let start = self.pos;
+        let rest = &self.input[start..];
+        let Some(ch) = rest.chars().next() else {
+            return Ok(Token {
+                kind: TokenKind::Eof,
+                start,
+            });
+        };
+
+        let (kind, len) = match ch {
+            '{' => (TokenKind::LBrace, 1),
+            '}' => (TokenKind::RBrace, 1),
+            '[' => (TokenKind::LBracket, 1),
+            ']' => (TokenKind::RBracket, 1),
+            '=' => (TokenKind::Equals, 1),
+            ';' => (TokenKind::Semicolon, 1),
+            ',' => (TokenKind::Comma, 1),
+            ':' => (TokenKind::Colon, 1),
+            '-' if rest.starts_with("->") => (TokenKind::Arrow, 2),
+            '-' if rest.starts_with("--") => (TokenKind::Line, 2),
+            '"' => return self.read_quoted(start),
+            '<' => return self.read_html(start),
+            _ if ch.is_alphanumeric() || ch == '_' || ch == '.' => {
+                let len = rest
+                    .find(|c: char| !(c.is_alphanumeric() || c == '_' || c == '.'))
+                    .unwrap_or(rest.len());
+                (TokenKind::Id(rest[..len].to_string()), len)
+            }
+            _ => {
+                return Err(ParseError::new(format!("unexpected character '{ch}'"), start));
+            }
+        };
+        self.pos += len;
+        Ok(Token { kind, start })
+    }
+
+    fn skip_trivia(&mut self) -> Result<(), ParseError> {
+        loop {
+            let rest = &self.input[self.pos..];
+            let trimmed = rest.trim_start();
+            self.pos += rest.len() - trimmed.len();
+            // `#` lines are C preprocessor output, which Graphviz discards.
+            let at_line_start = self.input[..self.pos]
+                .rsplit('\n')
+                .next()
+                .is_some_and(|line| line.trim().is_empty());
+            if trimmed.starts_with("//") || (trimmed.starts_with('#') && at_line_start) {
+                self.pos += trimmed.find('\n').unwrap_or(trimmed.len());
+            } else if trimmed.starts_with("/*") {
+                let end = trimmed
+                    .find("*/")
+                    .ok_or_else(|| ParseError::new("unterminated comment".to_string(), self.pos))?;
+                self.pos += end + 2;
+            } else {
+                return Ok(());
+            }
+        }
+    }
+
+    fn read_quoted(&mut self, start: usize) -> Result<Token, ParseError> {
+        let mut value = String::new();
+        let mut chars = self.input[start + 1..].char_indices();
+        while let Some((offset, ch)) = chars.next() {
+            match ch {
+                '"' => {
+                    self.pos = start + 1 + offset + 1;
+                    return Ok(Token {
+                        kind: TokenKind::QuotedId(value),
+                        start,
+                    });
+                }
+                '\\' => match chars.next() {
+                    Some((_, '"')) => value.push('"'),
+                    Some((_, 'n')) | Some((_, 'l')) | Some((_, 'r')) => value.push('\n'),
+                    Some((_, '\n')) => {}
+                    Some((_, other)) => {
+                        value.push('\\');
+                        value.push(other);
+                    }
+                    None => break,
+                },
+                _ => value.push(ch),
+            }
+        }
+        Err(ParseError::new("unterminated string".to_string(), start))
+    }
+
+    // HTML-like labels keep their markup; only the outer angle brackets are stripped.
+    fn read_html(&mut self, start: usize) -> Result<Token, ParseError> {
+        let mut depth = 0usize;
+        for (offset, ch) in self.input[start..].char_indices() {
+            match ch {
+                '<' => depth += 1,
+                '>' => {
+                    depth -= 1;
+                    if depth == 0 {
+                        self.pos = start + offset + 1;
+                        let value = self.input[start + 1..start + offset].to_string();
+                        return Ok(Token {
+                            kind: TokenKind::QuotedId(value),
+                            start,
+                        });
+                    }
+                }
+                _ => {}
+            }
+        }
+        Err(ParseError::new("unterminated HTML label".to_string(), start))
+    }
+}
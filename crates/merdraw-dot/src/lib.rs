@@ -0,0 +1,364 @@
+mod export;
+mod lexer;
+
+use std::collections::HashMap;
+
+use merdraw_parser::{
+    Direction, Edge, EdgeArrow, EdgeLabelPosition, EdgeStyle, Graph, Node, NodeShape, ParseError,
+    Subgraph,
+};
+
+use crate::lexer::{Lexer, Token, TokenKind};
+
+pub use crate::export::to_dot;
+
+// Reads the subset of Graphviz DOT that maps onto a flowchart: node and edge statements
+// with `label`/`shape`/`style`/`dir`, `rankdir`, `cluster*` subgraphs, and `rank=same`
+// blocks. Other attributes are accepted and ignored.
+pub fn parse_dot(input: &str) -> Result<Graph, ParseError> {
+    let mut parser = Parser::new(input).map_err(|err| err.located(input))?;
+    parser.parse_graph().map_err(|err| err.located(input))?;
+    Ok(parser.graph)
+}
+
+type Attrs = Vec<(String, String)>;
+
+#[derive(Default)]
+struct Block {
+    members: Vec<String>,
+    children: Vec<Subgraph>,
+    title: Option<String>,
+    same_rank: bool,
+}
+
+struct Parser<'a> {
+    lexer: Lexer<'a>,
+    current: Token,
+    graph: Graph,
+    nodes_by_id: HashMap<String, usize>,
+}
+
+impl<'a> Parser<'a> {
+    fn new(input: &'a str) -> Result<Self, ParseError> {
+        let mut lexer = Lexer::new(input);
+        let current = lexer.next_token()?;
+        Ok(Self {
+            lexer,
+            current,
+            graph: Graph::new(Direction::TB),
+            nodes_by_id: HashMap::new(),
+        })
+    }
+
+    fn parse_graph(&mut self) -> Result<(), ParseError> {
+        if self.is_keyword("strict") {
+            self.advance()?;
+        }
+        if !self.is_keyword("digraph") && !self.is_keyword("graph") {
+            return Err(self.error_here("expected 'graph' or 'digraph'"));
+        }
+        self.advance()?;
+        if let TokenKind::Id(_) | TokenKind::QuotedId(_) = self.current.kind {
+            self.advance()?;
+        }
+        self.expect(TokenKind::LBrace, "expected '{'")?;
+
+        let block = self.parse_block()?;
+        self.graph.subgraphs = block.children;
+        if self.current.kind != TokenKind::Eof {
+            return Err(self.error_here("unexpected content after graph"));
+        }
+        Ok(())
+    }
+
+    // Parses statements up to and including the closing brace.
+    fn parse_block(&mut self) -> Result<Block, ParseError> {
+        let mut block = Block::default();
+        loop {
+            match self.current.kind.clone() {
+                TokenKind::RBrace => {
+                    self.advance()?;
+                    break;
+                }
+                TokenKind::Semicolon | TokenKind::Comma => self.advance()?,
+                TokenKind::Eof => return Err(self.error_here("expected '}'")),
+                TokenKind::LBrace => {
+                    self.advance()?;
+                    self.parse_nested(None, &mut block)?;
+                }
+                TokenKind::Id(id) => self.parse_statement(id, false, &mut block)?,
+                TokenKind::QuotedId(id) => self.parse_statement(id, true, &mut block)?,
+                _ => return Err(self.error_here("expected statement")),
+            }
+        }
+        if block.same_rank && block.members.len() > 1 {
+            self.graph.same_rank.push(block.members.clone());
+        }
+        Ok(block)
+    }
+
+    // A quoted `id` is always a node, even when it spells a keyword.
+    fn parse_statement(
+        &mut self,
+        id: String,
+        quoted: bool,
+        block: &mut Block,
+    ) -> Result<(), ParseError> {
+        self.advance()?;
+        let keyword = if quoted { "" } else { id.as_str() };
+        match (keyword, &self.current.kind) {
+            ("subgraph", TokenKind::Id(name) | TokenKind::QuotedId(name)) => {
+                let name = name.clone();
+                self.advance()?;
+                self.expect(TokenKind::LBrace, "expected '{' after subgraph name")?;
+                self.parse_nested(Some(name), block)
+            }
+            ("subgraph", TokenKind::LBrace) => {
+                self.advance()?;
+                self.parse_nested(None, block)
+            }
+            ("graph" | "node" | "edge", TokenKind::LBracket) => {
+                let attrs = self.parse_attr_lists()?;
+                if id == "graph" {
+                    for (key, value) in &attrs {
+                        self.apply_graph_attr(key, value, block);
+                    }
+                }
+                Ok(())
+            }
+            (_, TokenKind::Equals) => {
+                self.advance()?;
+                let value = self.expect_id("expected attribute value")?;
+                self.apply_graph_attr(&id, &value, block);
+                Ok(())
+            }
+            _ => {
+                self.skip_port()?;
+                let attrs = self.parse_attr_lists()?;
+                self.upsert_node(&id, &attrs);
+                remember(&mut block.members, &id);
+                if matches!(self.current.kind, TokenKind::Arrow | TokenKind::Line) {
+                    return self.parse_edge_chain(id, block);
+                }
+                Ok(())
+            }
+        }
+    }
+
+    // Only `cluster*` subgraphs get a frame, as in Graphviz; other blocks just lend
+    // their members to the parent (and may carry `rank=same`).
+    fn parse_nested(&mut self, name: Option<String>, parent: &mut Block) -> Result<(), ParseError> {
+        let block = self.parse_block()?;
+        for id in &block.members {
+            remember(&mut parent.members, id);
+        }
+        match name {
+            Some(name) if name.starts_with("cluster") => {
+                let mut subgraph = Subgraph::new(name, block.title);
+                subgraph.nodes = block.members;
+                subgraph.subgraphs = block.children;
+                parent.children.push(subgraph);
+            }
+            _ => parent.children.extend(block.children),
+        }
+        Ok(())
+    }
+
+    fn parse_edge_chain(&mut self, first: String, block: &mut Block) -> Result<(), ParseError> {
+        let mut ids = vec![first];
+        // DOT uses `->` in digraphs and `--` in graphs; the first operator decides for the chain.
+        let arrow = if self.current.kind == TokenKind::Arrow {
+            EdgeArrow::Forward
+        } else {
+            EdgeArrow::None
+        };
+        while matches!(self.current.kind, TokenKind::Arrow | TokenKind::Line) {
+            self.advance()?;
+            if self.current.kind == TokenKind::LBrace || self.is_keyword("subgraph") {
+                return Err(self.error_here("subgraphs as edge endpoints are not supported"));
+            }
+            ids.push(self.expect_id("expected node id after edge operator")?);
+            self.skip_port()?;
+        }
+        let attrs = self.parse_attr_lists()?;
+        for id in &ids {
+            self.upsert_node(id, &[]);
+            remember(&mut block.members, id);
+        }
+        let (label, style, arrow) = edge_attrs(&attrs, arrow);
+        for pair in ids.windows(2) {
+            self.graph.edges.push(Edge {
+                from: pair[0].clone(),
+                to: pair[1].clone(),
+                label: label.clone(),
+                style: style.clone(),
+                arrow: arrow.clone(),
+                label_position: EdgeLabelPosition::Auto,
+            });
+        }
+        Ok(())
+    }
+
+    fn parse_attr_lists(&mut self) -> Result<Attrs, ParseError> {
+        let mut attrs = Vec::new();
+        while self.current.kind == TokenKind::LBracket {
+            self.advance()?;
+            loop {
+                match self.current.kind.clone() {
+                    TokenKind::RBracket => {
+                        self.advance()?;
+                        break;
+                    }
+                    TokenKind::Comma | TokenKind::Semicolon => self.advance()?,
+                    TokenKind::Id(key) | TokenKind::QuotedId(key) => {
+                        self.advance()?;
+                        self.expect(TokenKind::Equals, "expected '=' in attribute list")?;
+                        let value = self.expect_id("expected attribute value")?;
+                        attrs.push((key, value));
+                    }
+                    _ => return Err(self.error_here("expected attribute or ']'")),
+                }
+            }
+        }
+        Ok(attrs)
+    }
+
+    fn apply_graph_attr(&mut self, key: &str, value: &str, block: &mut Block) {
+        match key {
+            "rankdir" => {
+                if let Some(direction) = dot_direction(value) {
+                    self.graph.direction = direction;
+                }
+            }
+            "label" => block.title = Some(value.to_string()),
+            "rank" => block.same_rank = value == "same",
+            _ => {}
+        }
+    }
+
+    fn upsert_node(&mut self, id: &str, attrs: &[(String, String)]) {
+        let idx = match self.nodes_by_id.get(id) {
+            Some(&idx) => idx,
+            None => {
+                let idx = self.graph.nodes.len();
+                self.graph.nodes.push(Node {
+                    id: id.to_string(),
+                    label: None,
+                    shape: NodeShape::Plain,
+                });
+                self.nodes_by_id.insert(id.to_string(), idx);
+                idx
+            }
+        };
+        let node = &mut self.graph.nodes[idx];
+        for (key, value) in attrs {
+            match key.as_str() {
+                "label" => {
+                    node.label = Some(value.clone());
+                    if node.shape == NodeShape::Plain {
+                        node.shape = NodeShape::Bracket;
+                    }
+                }
+                "shape" => {
+                    if let Some(shape) = dot_shape(value) {
+                        node.shape = shape;
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn skip_port(&mut self) -> Result<(), ParseError> {
+        while self.current.kind == TokenKind::Colon {
+            self.advance()?;
+            self.expect_id("expected port name")?;
+        }
+        Ok(())
+    }
+
+    fn is_keyword(&self, keyword: &str) -> bool {
+        matches!(&self.current.kind, TokenKind::Id(id) if id.eq_ignore_ascii_case(keyword))
+    }
+
+    fn expect(&mut self, kind: TokenKind, message: &str) -> Result<(), ParseError> {
+        if self.current.kind != kind {
+            return Err(self.error_here(message));
+        }
+        self.advance()
+    }
+
+    fn expect_id(&mut self, message: &str) -> Result<String, ParseError> {
+        match self.current.kind.clone() {
+            TokenKind::Id(id) | TokenKind::QuotedId(id) => {
+                self.advance()?;
+                Ok(id)
+            }
+            _ => Err(self.error_here(message)),
+        }
+    }
+
+    fn advance(&mut self) -> Result<(), ParseError> {
+        self.current = self.lexer.next_token()?;
+        Ok(())
+    }
+
+    fn error_here(&self, message: &str) -> ParseError {
+        ParseError::new(message.to_string(), self.current.start)
+    }
+}
+
+fn remember(members: &mut Vec<String>, id: &str) {
+    if !members.iter().any(|existing| existing == id) {
+        members.push(id.to_string());
+    }
+}
+
+fn edge_attrs(attrs: &[(String, String)], mut arrow: EdgeArrow) -> (Option<String>, EdgeStyle, EdgeArrow) {
+    let mut label = None;
+    let mut style = EdgeStyle::Solid;
+    let mut marker = None;
+    for (key, value) in attrs {
+        match (key.as_str(), value.as_str()) {
+            ("label", _) => label = Some(value.clone()),
+            ("style", "dotted" | "dashed") => style = EdgeStyle::Dotted,
+            ("style", "bold") | ("penwidth", "2" | "3" | "4") => style = EdgeStyle::Thick,
+            ("style", "invis") => style = EdgeStyle::Invisible,
+            ("dir", "forward") => arrow = EdgeArrow::Forward,
+            ("dir", "back") => arrow = EdgeArrow::Backward,
+            ("dir", "both") => arrow = EdgeArrow::Both,
+            ("dir", "none") => arrow = EdgeArrow::None,
+            ("arrowhead", "tee") => marker = Some(EdgeArrow::Cross),
+            ("arrowhead", "dot" | "odot") => marker = Some(EdgeArrow::Circle),
+            _ => {}
+        }
+    }
+    // Cross and circle markers only exist on the target end of a one-way edge.
+    if let Some(marker) = marker.filter(|_| arrow == EdgeArrow::Forward) {
+        arrow = marker;
+    }
+    (label, style, arrow)
+}
+
+fn dot_direction(value: &str) -> Option<Direction> {
+    match value {
+        "TB" => Some(Direction::TB),
+        "BT" => Some(Direction::BT),
+        "LR" => Some(Direction::LR),
+        "RL" => Some(Direction::RL),
+        _ => None,
+    }
+}
+
+fn dot_shape(value: &str) -> Option<NodeShape> {
+    match value {
+        "box" | "rect" | "rectangle" | "square" => Some(NodeShape::Bracket),
+        "ellipse" | "oval" => Some(NodeShape::Round),
+        "circle" | "doublecircle" | "point" => Some(NodeShape::Circle),
+        "diamond" => Some(NodeShape::Diamond),
+        "hexagon" => Some(NodeShape::Hexagon),
+        "cylinder" => Some(NodeShape::Cylinder),
+        "plaintext" | "plain" | "none" => Some(NodeShape::Plain),
+        _ => None,
+    }
+}
@@ -0,0 +1,102 @@
+use merdraw_dot::{parse_dot, to_dot};
+use merdraw_parser::{parse_flowchart, Direction, EdgeArrow, EdgeStyle, NodeShape};
+
+#[test]
+fn parses_small_digraph() {
+    let graph = parse_dot(
+        "digraph G {\n\
+         // comment\n\
+         rankdir=LR;\n\
+         node [fontname=Helvetica];\n\
+         a [label=\"Start\", shape=box, color=red];\n\
+         a -> b -> c [label=next, style=dotted];\n\
+         b -> d;\n\
+         subgraph cluster_x { label=\"Group\"; c; d }\n\
+         { rank=same; b; e }\n\
+         }\n",
+    )
+    .expect("parse failed");
+
+    assert_eq!(graph.direction, Direction::LR);
+    let ids: Vec<&str> = graph.nodes.iter().map(|n| n.id.as_str()).collect();
+    assert_eq!(ids, vec!["a", "b", "c", "d", "e"]);
+    assert_eq!(graph.nodes[0].label.as_deref(), Some("Start"));
+    assert_eq!(graph.nodes[0].shape, NodeShape::Bracket);
+
+    let edges: Vec<(&str, &str, Option<&str>, EdgeStyle)> = graph
+        .edges
+        .iter()
+        .map(|e| (e.from.as_str(), e.to.as_str(), e.label.as_deref(), e.style.clone()))
+        .collect();
+    assert_eq!(
+        edges,
+        vec![
+            ("a", "b", Some("next"), EdgeStyle::Dotted),
+            ("b", "c", Some("next"), EdgeStyle::Dotted),
+            ("b", "d", None, EdgeStyle::Solid),
+        ]
+    );
+    assert!(graph.edges.iter().all(|e| e.arrow == EdgeArrow::Forward));
+
+    assert_eq!(graph.subgraphs.len(), 1);
+    assert_eq!(graph.subgraphs[0].title.as_deref(), Some("Group"));
+    assert_eq!(graph.subgraphs[0].nodes, vec!["c", "d"]);
+    assert_eq!(graph.same_rank, vec![vec!["b", "e"]]);
+}
+
+#[test]
+fn undirected_edges_have_no_heads_and_errors_report_offsets() {
+    let graph = parse_dot("graph { a -- b }").expect("parse failed");
+    assert_eq!(graph.edges[0].arrow, EdgeArrow::None);
+
+    let err = parse_dot("digraph { a -> }").unwrap_err();
+    assert_eq!(err.offset, 15);
+    assert_eq!((err.line, err.column), (1, 16));
+}
+
+#[test]
+fn exports_dot_that_parses_back() {
+    let source = "flowchart LR\nsubgraph g [Group]\nA[He said \"hi\"] -->|go| B{Check}\nend\nB -.-> C((Done))\nC <--> node\n";
+    let graph = parse_flowchart(source).expect("parse failed");
+    let dot = to_dot(&graph);
+    assert!(dot.starts_with("digraph"));
+    assert!(dot.contains("rankdir=LR"));
+    assert!(dot.contains("A -> B [label=\"go\"]"));
+    assert!(dot.contains("subgraph cluster_g"));
+    assert!(dot.contains("A [label=\"He said \\\"hi\\\"\"];"));
+    assert!(dot.contains("C -> \"node\" [dir=both];"));
+
+    let reparsed = parse_dot(&dot).expect("exported DOT did not parse");
+    assert_eq!(reparsed.direction, Direction::LR);
+    assert_eq!(reparsed.nodes.len(), graph.nodes.len());
+    for (ours, theirs) in graph.edges.iter().zip(&reparsed.edges) {
+        assert_eq!((&ours.from, &ours.to), (&theirs.from, &theirs.to));
+        assert_eq!(ours.style, theirs.style);
+        assert_eq!(ours.arrow, theirs.arrow);
+    }
+    let label = |graph: &merdraw_parser::Graph| graph.nodes[0].label.clone();
+    assert_eq!(label(&reparsed), label(&graph));
+}
+
+#[test]
+fn nodes_named_after_keywords_keep_their_labels() {
+    let source = "flowchart TB\nA-->node[Label]\nnode-->edge[Edge]\nedge-->strict[S]\n";
+    let graph = parse_flowchart(source).expect("parse failed");
+    let reparsed = parse_dot(&to_dot(&graph)).expect("exported DOT did not parse");
+    let nodes = |graph: &merdraw_parser::Graph| {
+        graph
+            .nodes
+            .iter()
+            .map(|node| (node.id.clone(), node.label.clone()))
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(nodes(&reparsed), nodes(&graph));
+    assert_eq!(nodes(&reparsed)[1], ("node".to_string(), Some("Label".to_string())));
+
+    let graph = parse_dot("digraph { \"graph\" [label=\"G\"]; \"subgraph\" -> \"graph\" }")
+        .expect("parse failed");
+    let ids: Vec<&str> = graph.nodes.iter().map(|node| node.id.as_str()).collect();
+    assert_eq!(ids, ["graph", "subgraph"]);
+    assert_eq!(graph.nodes[0].label.as_deref(), Some("G"));
+    assert_eq!(graph.edges.len(), 1);
+}
@@ -1,7 +1,8 @@
-use std::collections::{HashMap, HashSet, VecDeque};
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
 
 use merdraw_parser::{
-    Direction, Edge, EdgeArrow, EdgeStyle, Graph, Node as ParsedNode, NodeShape, Subgraph,
+    char_width, label_lines, split_label_glyph, Direction, Edge, EdgeArrow, EdgeLabelPosition,
+    EdgeStyle, Graph, Node as ParsedNode, NodeShape, NodeStyle, Subgraph,
 };
 
 #[derive(Debug, Clone)]
@@ -14,6 +15,29 @@ pub struct LayoutStyle {
     pub node_padding_y: f32,
     pub node_gap: f32,
     pub layer_gap: f32,
+    pub dummy_width: f32,
+    pub straighten_tolerance: f32,
+    pub layer_assignment: LayerAssignment,
+    // Lays the chart out in this direction instead of the one in its header.
+    pub direction: Option<Direction>,
+    // Grows `width`/`height` to cover routed edge points, not just node boxes. Grouped
+    // layouts always do, since their cross-edge bands run outside the groups.
+    pub extent_includes_edges: bool,
+    // Space kept around each node outside any subgraph when the chart has subgraphs, on
+    // top of the gap between groups, so lone nodes don't crowd the group frames.
+    pub virtual_group_margin: f32,
+    // LR/RL charts use these instead of `node_gap` (between nodes in a layer, vertical
+    // there) and `layer_gap` (between layers, horizontal there) when set; TB/BT charts
+    // always use `node_gap`/`layer_gap`.
+    pub node_gap_lr: Option<f32>,
+    pub layer_gap_lr: Option<f32>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum LayerAssignment {
+    #[default]
+    LongestPath,
+    TightTree,
 }
 
 impl Default for LayoutStyle {
@@ -27,11 +51,20 @@ impl Default for LayoutStyle {
             node_padding_y: 8.0,
             node_gap: 24.0,
             layer_gap: 40.0,
+            dummy_width: 14.0,
+            straighten_tolerance: 0.0,
+            layer_assignment: LayerAssignment::default(),
+            direction: None,
+            extent_includes_edges: false,
+            virtual_group_margin: 0.0,
+            node_gap_lr: None,
+            layer_gap_lr: None,
         }
     }
 }
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct LayoutNode {
     pub id: String,
     pub label: Option<String>,
@@ -43,9 +76,11 @@ pub struct LayoutNode {
     pub y: f32,
     pub is_dummy: bool,
     pub shape: NodeShape,
+    pub style: NodeStyle,
 }
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct LayoutEdge {
     pub from: String,
     pub to: String,
@@ -53,20 +88,70 @@ pub struct LayoutEdge {
     pub label: Option<String>,
     pub style: EdgeStyle,
     pub arrow: EdgeArrow,
+    pub label_position: EdgeLabelPosition,
     pub reversed: bool,
+    pub source_group: Option<String>,
     pub points: Vec<(f32, f32)>,
 }
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct LayoutGraph {
     pub nodes: Vec<LayoutNode>,
     pub edges: Vec<LayoutEdge>,
     pub subgraphs: Vec<LayoutSubgraph>,
     pub width: f32,
     pub height: f32,
+    // Constraints the layout could not honour, for the caller to report.
+    #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "Vec::is_empty"))]
+    pub warnings: Vec<String>,
+}
+
+impl LayoutGraph {
+    pub fn overlaps(&self) -> Vec<(String, String)> {
+        let boxes: Vec<(&str, Bounds)> = self
+            .nodes
+            .iter()
+            .filter(|node| !node.is_dummy)
+            .map(|node| (node.id.as_str(), node_bounds(node)))
+            .collect();
+        let mut pairs = Vec::new();
+        for i in 0..boxes.len() {
+            for j in (i + 1)..boxes.len() {
+                if bounds_overlap(boxes[i].1, boxes[j].1) {
+                    pairs.push((boxes[i].0.to_string(), boxes[j].0.to_string()));
+                }
+            }
+        }
+        pairs
+    }
+
+    pub fn subgraph_of(&self, node_id: &str) -> Option<String> {
+        let mut path = Vec::new();
+        find_subgraph_path(&self.subgraphs, node_id, &mut path).then(|| path.join("/"))
+    }
+
+    // Grows the reported size to cover every edge point as well as the nodes.
+    pub fn extend_to_edges(&mut self) {
+        (self.width, self.height) = edge_extent(&self.edges, self.width, self.height);
+    }
+}
+
+fn find_subgraph_path(subgraphs: &[LayoutSubgraph], node_id: &str, path: &mut Vec<String>) -> bool {
+    for subgraph in subgraphs {
+        path.push(subgraph.id.clone());
+        if find_subgraph_path(&subgraph.subgraphs, node_id, path)
+            || subgraph.nodes.iter().any(|id| id == node_id)
+        {
+            return true;
+        }
+        path.pop();
+    }
+    false
 }
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct LayoutSubgraphBounds {
     pub path: String,
     pub label: String,
@@ -74,6 +159,11 @@ pub struct LayoutSubgraphBounds {
     pub top: f32,
     pub right: f32,
     pub bottom: f32,
+    pub title_baseline: f32,
+    pub content_left: f32,
+    pub content_top: f32,
+    pub content_right: f32,
+    pub content_bottom: f32,
 }
 
 pub fn suggest_canvas_size(layout: &LayoutGraph, padding: f32, scale: f32) -> (u32, u32) {
@@ -97,12 +187,231 @@ pub fn subgraph_bounds(layout: &LayoutGraph, padding: f32) -> Vec<LayoutSubgraph
     bounds
 }
 
+// Keyed by (from, to, label); parallel edges with the same label share one anchor.
+pub fn label_anchors(layout: &LayoutGraph) -> HashMap<(String, String, String), (f32, f32)> {
+    let mut anchors = HashMap::new();
+    for edge in &layout.edges {
+        let Some(label) = edge.label.as_deref().filter(|label| !label.trim().is_empty()) else {
+            continue;
+        };
+        let Some(index) = label_segment(&edge.points, edge.is_cross, 0.0)
+            .or_else(|| label_segment(&edge.points, false, 0.0))
+        else {
+            continue;
+        };
+        let (start, end) = (edge.points[index], edge.points[index + 1]);
+        anchors.insert(
+            (edge.from.clone(), edge.to.clone(), label.to_string()),
+            ((start.0 + end.0) / 2.0, (start.1 + end.1) / 2.0),
+        );
+    }
+    anchors
+}
+
+// Index of the segment a label should sit on. Cross edges take the first horizontal
+// segment at least `min_cross_len` long (or none); others prefer the longest horizontal one.
+pub fn label_segment(points: &[(f32, f32)], is_cross: bool, min_cross_len: f32) -> Option<usize> {
+    let mut best = None;
+    let mut best_len = 0.0f32;
+    let mut best_is_horizontal = false;
+    for (index, segment) in points.windows(2).enumerate() {
+        let dx = (segment[1].0 - segment[0].0).abs();
+        let dy = (segment[1].1 - segment[0].1).abs();
+        let len = (dx * dx + dy * dy).sqrt();
+        if len <= f32::EPSILON {
+            continue;
+        }
+        let is_horizontal = dy <= dx * 0.3;
+        if is_cross {
+            if is_horizontal && len >= min_cross_len {
+                return Some(index);
+            }
+            continue;
+        }
+        let use_segment = if is_horizontal != best_is_horizontal {
+            is_horizontal
+        } else {
+            len > best_len
+        };
+        if use_segment {
+            best = Some(index);
+            best_len = len;
+            best_is_horizontal = is_horizontal;
+        }
+    }
+    best
+}
+
+pub fn layout_snapshot(layout: &LayoutGraph) -> String {
+    let mut out = format!("size {} {}\n", snap(layout.width), snap(layout.height));
+    for node in layout.nodes.iter().filter(|node| !node.is_dummy) {
+        out.push_str(&format!(
+            "node {} {},{} {}x{}\n",
+            node.id,
+            snap(node.x),
+            snap(node.y),
+            snap(node.width),
+            snap(node.height)
+        ));
+    }
+    for edge in &layout.edges {
+        let points = edge
+            .points
+            .iter()
+            .map(|&(x, y)| format!("{},{}", snap(x), snap(y)))
+            .collect::<Vec<_>>()
+            .join(" ");
+        out.push_str(&format!("edge {}->{} {}\n", edge.from, edge.to, points));
+    }
+    out
+}
+
+// Graphviz `plain` output: inches, origin at the bottom-left, y growing upward.
+pub fn to_graphviz_plain(layout: &LayoutGraph) -> String {
+    const POINTS_PER_INCH: f32 = 72.0;
+    let inches = |value: f32| format!("{:.3}", value / POINTS_PER_INCH);
+    let point = |(x, y): (f32, f32)| format!("{} {}", inches(x), inches(layout.height - y));
+
+    let mut out = format!("graph 1 {} {}\n", inches(layout.width), inches(layout.height));
+    for node in layout.nodes.iter().filter(|node| !node.is_dummy) {
+        let label = node.label.as_deref().unwrap_or(&node.id);
+        out.push_str(&format!(
+            "node {} {} {} {} {} solid {} black lightgrey\n",
+            plain_quote(&node.id),
+            point((node.x, node.y)),
+            inches(node.width),
+            inches(node.height),
+            plain_quote(label),
+            plain_shape(&node.shape)
+        ));
+    }
+    let anchors = label_anchors(layout);
+    for edge in &layout.edges {
+        out.push_str(&format!(
+            "edge {} {} {}",
+            plain_quote(&edge.from),
+            plain_quote(&edge.to),
+            edge.points.len()
+        ));
+        for &p in &edge.points {
+            out.push(' ');
+            out.push_str(&point(p));
+        }
+        if let Some(label) = edge.label.as_deref() {
+            let key = (edge.from.clone(), edge.to.clone(), label.to_string());
+            if let Some(&anchor) = anchors.get(&key) {
+                out.push_str(&format!(" {} {}", plain_quote(label), point(anchor)));
+            }
+        }
+        let style = match edge.style {
+            EdgeStyle::Solid => "solid",
+            EdgeStyle::Dotted => "dotted",
+            EdgeStyle::Thick => "bold",
+            EdgeStyle::Invisible => "invis",
+        };
+        out.push_str(&format!(" {style} black\n"));
+    }
+    out.push_str("stop\n");
+    out
+}
+
+fn plain_quote(value: &str) -> String {
+    let bare = !value.is_empty()
+        && value.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '.')
+        && !value.starts_with(|c: char| c.is_ascii_digit() || c == '.');
+    if bare {
+        value.to_string()
+    } else {
+        format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+    }
+}
+
+fn plain_shape(shape: &NodeShape) -> &'static str {
+    match shape {
+        NodeShape::Plain | NodeShape::Bracket | NodeShape::Subroutine => "box",
+        NodeShape::Round | NodeShape::Stadium => "ellipse",
+        NodeShape::Circle => "circle",
+        NodeShape::Diamond => "diamond",
+        NodeShape::Hexagon => "hexagon",
+        NodeShape::Cylinder => "cylinder",
+    }
+}
+
+// Whole units keep float noise out of snapshots.
+fn snap(value: f32) -> i64 {
+    value.round() as i64
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct PathHighlight {
+    pub nodes: HashSet<String>,
+    pub edges: HashSet<usize>,
+}
+
+impl PathHighlight {
+    pub fn contains_node(&self, id: &str) -> bool {
+        self.nodes.contains(id)
+    }
+
+    pub fn contains_edge(&self, index: usize) -> bool {
+        self.edges.contains(&index)
+    }
+}
+
+pub fn find_highlight_path(layout: &LayoutGraph, from: &str, to: &str) -> Option<PathHighlight> {
+    let exists = |id: &str| layout.nodes.iter().any(|node| !node.is_dummy && node.id == id);
+    if !exists(from) || !exists(to) {
+        return None;
+    }
+
+    let mut outgoing: HashMap<&str, Vec<usize>> = HashMap::new();
+    for (idx, edge) in layout.edges.iter().enumerate() {
+        outgoing.entry(edge.from.as_str()).or_default().push(idx);
+    }
+
+    let mut reached_by: HashMap<&str, usize> = HashMap::new();
+    let mut visited: HashSet<&str> = HashSet::new();
+    let mut queue = VecDeque::new();
+    visited.insert(from);
+    queue.push_back(from);
+    while let Some(current) = queue.pop_front() {
+        if current == to {
+            break;
+        }
+        for &edge_idx in outgoing.get(current).into_iter().flatten() {
+            let next = layout.edges[edge_idx].to.as_str();
+            if visited.insert(next) {
+                reached_by.insert(next, edge_idx);
+                queue.push_back(next);
+            }
+        }
+    }
+
+    if !visited.contains(to) {
+        return None;
+    }
+
+    let mut highlight = PathHighlight::default();
+    let mut current = to;
+    highlight.nodes.insert(current.to_string());
+    while current != from {
+        let edge_idx = reached_by[current];
+        highlight.edges.insert(edge_idx);
+        current = layout.edges[edge_idx].from.as_str();
+        highlight.nodes.insert(current.to_string());
+    }
+    Some(highlight)
+}
+
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct LayoutSubgraph {
     pub id: String,
     pub title: Option<String>,
     pub nodes: Vec<String>,
     pub subgraphs: Vec<LayoutSubgraph>,
+    pub title_height: f32,
+    pub title_baseline_offset: f32,
 }
 
 #[derive(Debug, Clone)]
@@ -129,6 +438,7 @@ struct EdgeMeta {
     label: Option<String>,
     style: EdgeStyle,
     arrow: EdgeArrow,
+    label_position: EdgeLabelPosition,
     reversed: bool,
 }
 
@@ -145,16 +455,130 @@ struct UnitEdge {
 }
 
 pub fn layout_flowchart(graph: &Graph, style: &LayoutStyle) -> LayoutGraph {
-    if graph.subgraphs.is_empty() {
-        return layout_flowchart_flat(graph, style, None);
+    layout_flowchart_with_rank_hints(graph, style, &HashMap::new())
+}
+
+pub fn layout_flowchart_with_rank_hints(
+    graph: &Graph,
+    style: &LayoutStyle,
+    rank_hints: &HashMap<String, usize>,
+) -> LayoutGraph {
+    layout_flowchart_with_options(graph, style, rank_hints, None)
+}
+
+// Sizes are (width, height) in layout units, used as-is. For an overridden node,
+// `char_width`, `char_height`, `node_padding_x/y`, `min_width`/`min_height` and the
+// extra width for crowded edge ports are ignored; every other node is still estimated.
+pub fn layout_flowchart_with_sizes(
+    graph: &Graph,
+    style: &LayoutStyle,
+    sizes: &HashMap<String, (f32, f32)>,
+) -> LayoutGraph {
+    layout_flowchart_with_options(graph, style, &HashMap::new(), Some(sizes))
+}
+
+fn layout_flowchart_with_options(
+    graph: &Graph,
+    style: &LayoutStyle,
+    rank_hints: &HashMap<String, usize>,
+    size_overrides: Option<&HashMap<String, (f32, f32)>>,
+) -> LayoutGraph {
+    let redirected = graph.redirected(style.direction.as_ref());
+    let graph: &Graph = &redirected;
+    let horizontal;
+    let lr_overrides = style.node_gap_lr.is_some() || style.layer_gap_lr.is_some();
+    let style = match graph.direction {
+        Direction::LR | Direction::RL if lr_overrides => {
+            horizontal = LayoutStyle {
+                node_gap: style.node_gap_lr.unwrap_or(style.node_gap),
+                layer_gap: style.layer_gap_lr.unwrap_or(style.layer_gap),
+                ..style.clone()
+            };
+            &horizontal
+        }
+        _ => style,
+    };
+    let components = if graph.subgraphs.is_empty() && rank_hints.is_empty() {
+        weak_components(graph)
+    } else {
+        Vec::new()
+    };
+    let mut layout = if components.len() > 1 {
+        layout_components(graph, &components, style, size_overrides)
+    } else if graph.subgraphs.is_empty() {
+        layout_flowchart_flat(graph, style, size_overrides, rank_hints)
+    } else {
+        layout_flowchart_grouped(graph, style, size_overrides, rank_hints)
+    };
+    if style.straighten_tolerance > 0.0 {
+        for edge in &mut layout.edges {
+            edge.points = straighten_edge_points(&edge.points, style.straighten_tolerance);
+        }
+    }
+    apply_node_styles(&mut layout, graph);
+    layout
+}
+
+pub fn apply_node_styles(layout: &mut LayoutGraph, graph: &Graph) {
+    if graph.class_defs.is_empty() {
+        return;
+    }
+    for node in layout.nodes.iter_mut().filter(|node| !node.is_dummy) {
+        node.style = graph.node_style(&node.id);
+    }
+}
+
+// Snaps segments within `tolerance` of vertical/horizontal onto the axis, then
+// drops repeated and near-collinear interior points. The first point stays fixed.
+pub fn straighten_edge_points(points: &[(f32, f32)], tolerance: f32) -> Vec<(f32, f32)> {
+    let mut points = points.to_vec();
+    for i in 1..points.len() {
+        let (prev, cur) = (points[i - 1], points[i]);
+        let dx = (cur.0 - prev.0).abs();
+        let dy = (cur.1 - prev.1).abs();
+        if dx > 0.0 && dx <= tolerance && dy > dx {
+            points[i].0 = prev.0;
+        } else if dy > 0.0 && dy <= tolerance && dx > dy {
+            points[i].1 = prev.1;
+        }
+    }
+
+    let mut result: Vec<(f32, f32)> = Vec::with_capacity(points.len());
+    for point in points {
+        if result.last() == Some(&point) {
+            continue;
+        }
+        if result.len() >= 2 {
+            let a = result[result.len() - 2];
+            let b = result[result.len() - 1];
+            if distance_to_line(b, a, point) <= tolerance.max(0.01) && is_between(b, a, point) {
+                result.pop();
+            }
+        }
+        result.push(point);
+    }
+    result
+}
+
+fn distance_to_line(point: (f32, f32), a: (f32, f32), b: (f32, f32)) -> f32 {
+    let (dx, dy) = (b.0 - a.0, b.1 - a.1);
+    let len = (dx * dx + dy * dy).sqrt();
+    if len <= f32::EPSILON {
+        return ((point.0 - a.0).powi(2) + (point.1 - a.1).powi(2)).sqrt();
     }
-    layout_flowchart_grouped(graph, style)
+    ((point.0 - a.0) * dy - (point.1 - a.1) * dx).abs() / len
+}
+
+fn is_between(point: (f32, f32), a: (f32, f32), b: (f32, f32)) -> bool {
+    (point.0 - a.0) * (b.0 - a.0) + (point.1 - a.1) * (b.1 - a.1) >= 0.0
+        && (point.0 - b.0) * (a.0 - b.0) + (point.1 - b.1) * (a.1 - b.1) >= 0.0
 }
 
 fn layout_flowchart_flat(
     graph: &Graph,
     style: &LayoutStyle,
     size_overrides: Option<&HashMap<String, (f32, f32)>>,
+    rank_hints: &HashMap<String, usize>,
 ) -> LayoutGraph {
     let mut nodes = Vec::new();
     let mut node_index = HashMap::new();
@@ -195,17 +619,35 @@ fn layout_flowchart_flat(
             label: edge.label.clone(),
             style: edge.style.clone(),
             arrow: edge.arrow.clone(),
+            label_position: edge.label_position.clone(),
             reversed: false,
         });
     }
 
     make_acyclic(&mut edges, nodes.len());
-    if size_overrides.is_none() {
-        adjust_node_sizes_for_ports(&mut nodes, &edges, style, graph.direction.clone());
+    adjust_node_sizes_for_ports(&mut nodes, &edges, style, graph.direction.clone());
+    if let Some(overrides) = size_overrides {
+        for node in &mut nodes {
+            if let Some(&(width, height)) = overrides.get(&node.id) {
+                node.width = width;
+                node.height = height;
+            }
+        }
     }
+    apply_rank_hints(&mut nodes, rank_hints);
     assign_layers(&mut nodes, &edges);
+    if style.layer_assignment == LayerAssignment::TightTree {
+        network_simplex_layers(&mut nodes, &edges);
+        // Hints stay lower bounds; assign_layers only pushes nodes down.
+        apply_rank_hints(&mut nodes, rank_hints);
+        assign_layers(&mut nodes, &edges);
+    }
+    let mut warnings = Vec::new();
+    let same_rank = same_rank_sets(graph, &node_index, &edges, &mut warnings);
+    align_same_ranks(&mut nodes, &edges, &same_rank);
 
-    let (mut chains, unit_edges) = insert_dummy_nodes(&mut nodes, &edges);
+    let (mut chains, unit_edges) =
+        insert_dummy_nodes(&mut nodes, &edges, style, graph.direction.clone());
 
     let mut layers = build_layers(&mut nodes);
     reduce_crossings(&mut nodes, &mut layers, &unit_edges, 6);
@@ -213,21 +655,30 @@ fn layout_flowchart_flat(
     let mut effective_style = style.clone();
     effective_style.layer_gap = compute_layer_gap(&nodes, &edges, style, direction.clone());
     assign_coordinates(&mut nodes, &layers, &effective_style, direction.clone());
+    align_to_neighbors(&mut nodes, &layers, &unit_edges, &effective_style, direction.clone());
     expand_layer_gaps(&mut nodes, &edges, &effective_style, direction.clone());
     separate_subgraphs(&mut nodes, graph, style, direction.clone());
 
     let (width, height) = compute_graph_extent(&nodes, direction.clone());
-    let layout_edges = route_edges(
+    let mut layout_edges = route_edges(
         &nodes,
         &edges,
         &mut chains,
-        direction,
+        direction.clone(),
         &effective_style,
     );
+    // Mirroring reflects across the extent, so it has to cover the edges first.
+    let (width, height) = if style.extent_includes_edges {
+        edge_extent(&layout_edges, width, height)
+    } else {
+        (width, height)
+    };
+    // Routing assumes TB/LR flow, so BT/RL flip the finished geometry instead.
+    mirror_coordinates(&mut nodes, &mut layout_edges, direction, width, height);
     let layout_subgraphs = graph
         .subgraphs
         .iter()
-        .map(build_layout_subgraph)
+        .map(|subgraph| build_layout_subgraph(subgraph, style))
         .collect();
 
     LayoutGraph {
@@ -244,12 +695,152 @@ fn layout_flowchart_flat(
                 y: node.y,
                 is_dummy: node.is_dummy,
                 shape: node.shape,
+                style: NodeStyle::default(),
             })
             .collect(),
         edges: layout_edges,
         subgraphs: layout_subgraphs,
         width,
         height,
+        warnings,
+    }
+}
+
+// Node ids of each weakly connected component, in order of first appearance. `rank same`
+// statements join their members, since they only mean something within one layout.
+pub fn weak_components(graph: &Graph) -> Vec<Vec<String>> {
+    let index: HashMap<&str, usize> = graph
+        .nodes
+        .iter()
+        .enumerate()
+        .map(|(idx, node)| (node.id.as_str(), idx))
+        .collect();
+    let mut parent: Vec<usize> = (0..graph.nodes.len()).collect();
+    fn root(parent: &mut [usize], mut idx: usize) -> usize {
+        while parent[idx] != idx {
+            parent[idx] = parent[parent[idx]];
+            idx = parent[idx];
+        }
+        idx
+    }
+    let links = graph
+        .edges
+        .iter()
+        .map(|edge| (edge.from.as_str(), edge.to.as_str()))
+        .chain(graph.same_rank.iter().flat_map(|ids| {
+            ids.windows(2).map(|pair| (pair[0].as_str(), pair[1].as_str()))
+        }));
+    for (a, b) in links {
+        if let (Some(&a), Some(&b)) = (index.get(a), index.get(b)) {
+            let (a, b) = (root(&mut parent, a), root(&mut parent, b));
+            parent[a.max(b)] = a.min(b);
+        }
+    }
+
+    let mut components: Vec<Vec<String>> = Vec::new();
+    let mut slot: HashMap<usize, usize> = HashMap::new();
+    for (idx, node) in graph.nodes.iter().enumerate() {
+        let root = root(&mut parent, idx);
+        let at = *slot.entry(root).or_insert_with(|| {
+            components.push(Vec::new());
+            components.len() - 1
+        });
+        components[at].push(node.id.clone());
+    }
+    components
+}
+
+// Lays out each component on its own, then packs them.
+fn layout_components(
+    graph: &Graph,
+    components: &[Vec<String>],
+    style: &LayoutStyle,
+    size_overrides: Option<&HashMap<String, (f32, f32)>>,
+) -> LayoutGraph {
+    let parts = components
+        .iter()
+        .map(|ids| {
+            let component = build_subgraph_graph(graph, ids);
+            layout_flowchart_flat(&component, style, size_overrides, &HashMap::new())
+        })
+        .collect();
+    pack_components(graph, parts, style.node_gap)
+}
+
+// Packs separately laid out components row by row with the column count that keeps the
+// overall box closest to square. Nodes and edges keep the graph's order.
+pub fn pack_components(graph: &Graph, parts: Vec<LayoutGraph>, gap: f32) -> LayoutGraph {
+    let pack = |columns: usize| {
+        let mut offsets = Vec::with_capacity(parts.len());
+        let (mut width, mut height) = (0.0f32, 0.0f32);
+        for row in parts.chunks(columns) {
+            let top = if offsets.is_empty() { 0.0 } else { height + gap };
+            let mut left = 0.0f32;
+            for part in row {
+                offsets.push((left, top));
+                left += part.width + gap;
+            }
+            width = width.max(left - gap);
+            height = top + row.iter().map(|part| part.height).fold(0.0, f32::max);
+        }
+        (offsets, width, height)
+    };
+    let (offsets, width, height) = (1..=parts.len().max(1))
+        .map(pack)
+        .min_by(|a, b| a.1.max(a.2).total_cmp(&b.1.max(b.2)))
+        .expect("at least one column count");
+
+    let node_order: HashMap<&str, usize> = graph
+        .nodes
+        .iter()
+        .enumerate()
+        .map(|(idx, node)| (node.id.as_str(), idx))
+        .collect();
+    let edge_key = |from: &str, to: &str, label: Option<&str>| {
+        let index = |id: &str| node_order.get(id).copied();
+        (index(from), index(to), label.map(str::to_owned))
+    };
+    let mut edge_slots: HashMap<_, VecDeque<usize>> = HashMap::new();
+    for (idx, edge) in graph.edges.iter().enumerate() {
+        edge_slots
+            .entry(edge_key(&edge.from, &edge.to, edge.label.as_deref()))
+            .or_default()
+            .push_back(idx);
+    }
+    let warnings = parts.iter().flat_map(|part| part.warnings.clone()).collect();
+    let mut nodes = Vec::new();
+    let mut edges = Vec::new();
+    for (part, (dx, dy)) in parts.into_iter().zip(offsets) {
+        for mut node in part.nodes {
+            node.x += dx;
+            node.y += dy;
+            nodes.push(node);
+        }
+        for mut edge in part.edges {
+            for point in &mut edge.points {
+                point.0 += dx;
+                point.1 += dy;
+            }
+            let key = edge_key(&edge.from, &edge.to, edge.label.as_deref());
+            if let Some(idx) = edge_slots.get_mut(&key).and_then(VecDeque::pop_front) {
+                edges.push((idx, edge));
+            }
+        }
+    }
+    // Real nodes first in source order, then each component's dummies; edges follow the
+    // source order too, matching a single flat layout.
+    nodes.sort_by_key(|node| {
+        (node.is_dummy, node_order.get(node.id.as_str()).copied().unwrap_or(usize::MAX))
+    });
+    edges.sort_by_key(|(idx, _)| *idx);
+
+    LayoutGraph {
+        nodes,
+        edges: edges.into_iter().map(|(_, edge)| edge).collect(),
+        subgraphs: Vec::new(),
+        width,
+        height,
+        warnings,
     }
 }
 
@@ -257,7 +848,6 @@ fn layout_flowchart_flat(
 struct GroupLayout {
     id: String,
     title: Option<String>,
-    node_ids: Vec<String>,
     layout: LayoutGraph,
     width: f32,
     height: f32,
@@ -270,12 +860,18 @@ struct GroupLayout {
 #[derive(Debug, Clone)]
 struct CrossEdge {
     edge: Edge,
+    source_group: Option<String>,
     from: LayoutNode,
     to: LayoutNode,
     forward: bool,
 }
 
-fn layout_flowchart_grouped(graph: &Graph, style: &LayoutStyle) -> LayoutGraph {
+fn layout_flowchart_grouped(
+    graph: &Graph,
+    style: &LayoutStyle,
+    size_overrides: Option<&HashMap<String, (f32, f32)>>,
+    rank_hints: &HashMap<String, usize>,
+) -> LayoutGraph {
     let mut group_nodes: Vec<GroupLayout> = Vec::new();
     let mut node_to_group: HashMap<String, usize> = HashMap::new();
     let mut assigned: HashSet<String> = HashSet::new();
@@ -289,11 +885,17 @@ fn layout_flowchart_grouped(graph: &Graph, style: &LayoutStyle) -> LayoutGraph {
             assigned.insert(node_id.clone());
         }
 
-        let group_graph = build_subgraph_graph(graph, &node_ids);
-        let layout = layout_flowchart_flat(&group_graph, style, None);
+        let mut group_graph = build_subgraph_graph(graph, &node_ids);
+        let layout = if subgraph.subgraphs.is_empty() {
+            layout_flowchart_flat(&group_graph, style, size_overrides, rank_hints)
+        } else {
+            // Child groups become boxes of their own inside this one.
+            group_graph.subgraphs = subgraph.subgraphs.clone();
+            layout_flowchart_grouped(&group_graph, style, size_overrides, rank_hints)
+        };
         let padding_x = style.node_padding_x * 2.0;
         let padding_y = style.node_padding_y * 2.0;
-        let title_height = style.char_height + style.node_padding_y * 2.0;
+        let (title_height, _) = subgraph_title_metrics(style);
         let width = layout.width + padding_x * 2.0;
         let height = layout.height + padding_y * 2.0 + title_height;
 
@@ -304,7 +906,6 @@ fn layout_flowchart_grouped(graph: &Graph, style: &LayoutStyle) -> LayoutGraph {
         group_nodes.push(GroupLayout {
             id: subgraph.id.clone(),
             title: subgraph.title.clone(),
-            node_ids,
             layout,
             width,
             height,
@@ -321,18 +922,17 @@ fn layout_flowchart_grouped(graph: &Graph, style: &LayoutStyle) -> LayoutGraph {
         }
         let node_ids = vec![node.id.clone()];
         let group_graph = build_subgraph_graph(graph, &node_ids);
-        let layout = layout_flowchart_flat(&group_graph, style, None);
-        let padding_x = 0.0;
-        let padding_y = 0.0;
+        let layout = layout_flowchart_flat(&group_graph, style, size_overrides, rank_hints);
+        let padding_x = style.virtual_group_margin.max(0.0);
+        let padding_y = padding_x;
         let title_height = 0.0;
-        let width = layout.width;
-        let height = layout.height;
+        let width = layout.width + padding_x * 2.0;
+        let height = layout.height + padding_y * 2.0;
         let group_index = group_nodes.len();
         node_to_group.insert(node.id.clone(), group_index);
         group_nodes.push(GroupLayout {
             id: format!("__group_{}", node.id),
             title: None,
-            node_ids,
             layout,
             width,
             height,
@@ -343,7 +943,19 @@ fn layout_flowchart_grouped(graph: &Graph, style: &LayoutStyle) -> LayoutGraph {
         });
     }
 
-    let super_layout = build_super_layout_row(&group_nodes, style);
+    // BT is composed in a TB frame (each group flipped upright, titles reserved at the
+    // bottom) and flipped back at the end, so cross edges leave through the bottom band
+    // and enter their targets from below. The flat mirroring each group already got only
+    // flips its own contents; the bands between groups lie on the flow axis, so they need
+    // the whole-layout flip. RL needs neither: groups arrive mirrored, the super row
+    // reverses their order, and the bands run across the flow, where an x flip is a no-op.
+    let bottom_up = graph.direction == Direction::BT;
+    let direction = if bottom_up {
+        Direction::TB
+    } else {
+        graph.direction.clone()
+    };
+    let super_layout = build_super_layout_row(&group_nodes, style, direction.clone());
 
     let mut global_nodes: Vec<LayoutNode> = Vec::new();
     let mut node_lookup: HashMap<String, LayoutNode> = HashMap::new();
@@ -356,16 +968,21 @@ fn layout_flowchart_grouped(graph: &Graph, style: &LayoutStyle) -> LayoutGraph {
             let left = node.x - group.width / 2.0;
             let top = node.y - group.height / 2.0;
             let offset_x = left + group.padding_x;
-            let offset_y = top + group.padding_y + group.title_height;
+            let title_height = if bottom_up { 0.0 } else { group.title_height };
+            let offset_y = top + group.padding_y + title_height;
+            let mut group_layout = group.layout.clone();
+            if bottom_up {
+                flip_layout_vertically(&mut group_layout);
+            }
 
-            for mut node in group.layout.nodes.clone() {
+            for mut node in group_layout.nodes {
                 node.x += offset_x;
                 node.y += offset_y;
                 node_lookup.insert(node.id.clone(), node.clone());
                 global_nodes.push(node);
             }
 
-            for mut edge in group.layout.edges.clone() {
+            for mut edge in group_layout.edges {
                 edge.points = edge
                     .points
                     .into_iter()
@@ -374,13 +991,10 @@ fn layout_flowchart_grouped(graph: &Graph, style: &LayoutStyle) -> LayoutGraph {
                 global_edges.push(edge);
             }
 
-            if !group.is_virtual {
-                subgraphs.push(LayoutSubgraph {
-                    id: group.id.clone(),
-                    title: group.title.clone(),
-                    nodes: group.node_ids.clone(),
-                    subgraphs: Vec::new(),
-                });
+            if !group.is_virtual
+                && let Some(subgraph) = graph.subgraphs.iter().find(|s| s.id == group.id)
+            {
+                subgraphs.push(build_layout_subgraph(subgraph, style));
             }
         }
     }
@@ -407,12 +1021,15 @@ fn layout_flowchart_grouped(graph: &Graph, style: &LayoutStyle) -> LayoutGraph {
                 if !cross_edge_keys.insert(key) {
                     continue;
                 }
-                let forward = match graph.direction {
-                    Direction::TB | Direction::BT => from_node.x <= to_node.x,
-                    Direction::LR | Direction::RL => from_node.y <= to_node.y,
-                };
+                // Groups always sit in one row along x, so x order decides the band.
+                let forward = from_node.x <= to_node.x;
+                let source_group = from_group
+                    .map(|index| &group_nodes[index])
+                    .filter(|group| !group.is_virtual)
+                    .map(|group| group.id.clone());
                 cross_edges.push(CrossEdge {
                     edge: edge.clone(),
+                    source_group,
                     from: from_node.clone(),
                     to: to_node.clone(),
                     forward,
@@ -424,7 +1041,7 @@ fn layout_flowchart_grouped(graph: &Graph, style: &LayoutStyle) -> LayoutGraph {
     let cross_edge_count = cross_edges.len();
 
     let (start_offsets, end_offsets) =
-        compute_cross_edge_ports(&cross_edges, graph.direction.clone(), style);
+        compute_cross_edge_ports(&cross_edges, direction.clone(), style);
 
     let mut forward_indices: Vec<usize> = cross_edges
         .iter()
@@ -508,10 +1125,11 @@ fn layout_flowchart_grouped(graph: &Graph, style: &LayoutStyle) -> LayoutGraph {
         let points = route_cross_edge_band(
             &edge.from,
             &edge.to,
-            graph.direction.clone(),
+            direction.clone(),
             band_y,
             start_offset,
             end_offset,
+            style.node_padding_x,
         );
         global_edges.push(LayoutEdge {
             from: edge.edge.from.clone(),
@@ -520,7 +1138,9 @@ fn layout_flowchart_grouped(graph: &Graph, style: &LayoutStyle) -> LayoutGraph {
             label: edge.edge.label.clone(),
             style: edge.edge.style.clone(),
             arrow: edge.edge.arrow.clone(),
+            label_position: edge.edge.label_position.clone(),
             reversed: false,
+            source_group: edge.source_group.clone(),
             points,
         });
     }
@@ -534,10 +1154,11 @@ fn layout_flowchart_grouped(graph: &Graph, style: &LayoutStyle) -> LayoutGraph {
         let points = route_cross_edge_band(
             &edge.from,
             &edge.to,
-            graph.direction.clone(),
+            direction.clone(),
             band_y,
             start_offset,
             end_offset,
+            style.node_padding_x,
         );
         global_edges.push(LayoutEdge {
             from: edge.edge.from.clone(),
@@ -546,7 +1167,9 @@ fn layout_flowchart_grouped(graph: &Graph, style: &LayoutStyle) -> LayoutGraph {
             label: edge.edge.label.clone(),
             style: edge.edge.style.clone(),
             arrow: edge.edge.arrow.clone(),
+            label_position: edge.edge.label_position.clone(),
             reversed: false,
+            source_group: edge.source_group.clone(),
             points,
         });
     }
@@ -569,16 +1192,36 @@ fn layout_flowchart_grouped(graph: &Graph, style: &LayoutStyle) -> LayoutGraph {
         band_gap,
     );
 
-    LayoutGraph {
+    let mut layout = LayoutGraph {
         nodes: global_nodes,
         edges: global_edges,
         subgraphs,
         width,
         height,
+        warnings: group_nodes
+            .iter()
+            .flat_map(|group| group.layout.warnings.clone())
+            .collect(),
+    };
+    layout.extend_to_edges();
+    if bottom_up {
+        flip_layout_vertically(&mut layout);
     }
+    layout
 }
 
-fn build_layout_subgraph(subgraph: &Subgraph) -> LayoutSubgraph {
+fn flip_layout_vertically(layout: &mut LayoutGraph) {
+    let height = layout.height;
+    for node in &mut layout.nodes {
+        node.y = height - node.y;
+    }
+    for point in layout.edges.iter_mut().flat_map(|edge| edge.points.iter_mut()) {
+        point.1 = height - point.1;
+    }
+}
+
+fn build_layout_subgraph(subgraph: &Subgraph, style: &LayoutStyle) -> LayoutSubgraph {
+    let (title_height, title_baseline_offset) = subgraph_title_metrics(style);
     LayoutSubgraph {
         id: subgraph.id.clone(),
         title: subgraph.title.clone(),
@@ -586,11 +1229,19 @@ fn build_layout_subgraph(subgraph: &Subgraph) -> LayoutSubgraph {
         subgraphs: subgraph
             .subgraphs
             .iter()
-            .map(build_layout_subgraph)
+            .map(|child| build_layout_subgraph(child, style))
             .collect(),
+        title_height,
+        title_baseline_offset,
     }
 }
 
+fn subgraph_title_metrics(style: &LayoutStyle) -> (f32, f32) {
+    let title_height = style.char_height + style.node_padding_y * 2.0;
+    let baseline_offset = style.node_padding_y + style.char_height;
+    (title_height, baseline_offset)
+}
+
 fn collect_group_paths(
     subgraphs: &[Subgraph],
     prefix: &mut Vec<usize>,
@@ -615,7 +1266,7 @@ fn collect_subgraph_nodes(subgraph: &Subgraph, out: &mut Vec<String>) {
     }
 }
 
-fn build_subgraph_graph(graph: &Graph, node_ids: &[String]) -> Graph {
+pub fn build_subgraph_graph(graph: &Graph, node_ids: &[String]) -> Graph {
     let mut set = HashSet::new();
     for id in node_ids {
         set.insert(id.clone());
@@ -632,6 +1283,12 @@ fn build_subgraph_graph(graph: &Graph, node_ids: &[String]) -> Graph {
             subgraph.edges.push(edge.clone());
         }
     }
+    for ids in &graph.same_rank {
+        let members: Vec<String> = ids.iter().filter(|id| set.contains(*id)).cloned().collect();
+        if members.len() > 1 {
+            subgraph.same_rank.push(members);
+        }
+    }
     subgraph
 }
 
@@ -639,7 +1296,11 @@ fn find_layout_node<'a>(layout: &'a LayoutGraph, id: &str) -> Option<&'a LayoutN
     layout.nodes.iter().find(|node| node.id == id)
 }
 
-fn build_super_layout_row(groups: &[GroupLayout], style: &LayoutStyle) -> LayoutGraph {
+fn build_super_layout_row(
+    groups: &[GroupLayout],
+    style: &LayoutStyle,
+    direction: Direction,
+) -> LayoutGraph {
     let mut nodes = Vec::new();
     let mut x = 0.0f32;
     let gap = style.node_gap * 2.0;
@@ -659,6 +1320,7 @@ fn build_super_layout_row(groups: &[GroupLayout], style: &LayoutStyle) -> Layout
             y: center_y,
             is_dummy: false,
             shape: NodeShape::Plain,
+            style: NodeStyle::default(),
         });
         x += group.width + gap;
         max_height = max_height.max(group.height);
@@ -666,6 +1328,11 @@ fn build_super_layout_row(groups: &[GroupLayout], style: &LayoutStyle) -> Layout
 
     let width = if nodes.is_empty() { 0.0 } else { x - gap };
     let height = max_height;
+    if direction == Direction::RL {
+        for node in &mut nodes {
+            node.x = width - node.x;
+        }
+    }
 
     LayoutGraph {
         nodes,
@@ -673,6 +1340,7 @@ fn build_super_layout_row(groups: &[GroupLayout], style: &LayoutStyle) -> Layout
         subgraphs: Vec::new(),
         width,
         height,
+        warnings: Vec::new(),
     }
 }
 
@@ -683,6 +1351,7 @@ fn route_cross_edge_band(
     band_y: f32,
     start_offset: f32,
     end_offset: f32,
+    stub: f32,
 ) -> Vec<(f32, f32)> {
     let mut points = Vec::new();
     match direction {
@@ -695,11 +1364,18 @@ fn route_cross_edge_band(
             push_point(&mut points, end);
         }
         Direction::LR | Direction::RL => {
-            let start = (from.x - from.width / 2.0, from.y + start_offset);
-            let end = (to.x - to.width / 2.0, to.y + end_offset);
+            // Leave through the side facing the target and enter through the side facing
+            // the source; the stubs keep the runs up to the band off the node borders.
+            let toward = if to.x >= from.x { 1.0 } else { -1.0 };
+            let start = (from.x + toward * from.width / 2.0, from.y + start_offset);
+            let end = (to.x - toward * to.width / 2.0, to.y + end_offset);
+            let start_stub = start.0 + toward * stub;
+            let end_stub = end.0 - toward * stub;
             push_point(&mut points, start);
-            push_point(&mut points, (band_y, start.1));
-            push_point(&mut points, (band_y, end.1));
+            push_point(&mut points, (start_stub, start.1));
+            push_point(&mut points, (start_stub, band_y));
+            push_point(&mut points, (end_stub, band_y));
+            push_point(&mut points, (end_stub, end.1));
             push_point(&mut points, end);
         }
     }
@@ -764,8 +1440,9 @@ fn compute_cross_edge_ports(
     direction: Direction,
     style: &LayoutStyle,
 ) -> (HashMap<usize, f32>, HashMap<usize, f32>) {
-    let mut outgoing: HashMap<String, Vec<usize>> = HashMap::new();
-    let mut incoming: HashMap<String, Vec<usize>> = HashMap::new();
+    // Ordered maps keep the pass independent of hash order, so repeated runs match exactly.
+    let mut outgoing: BTreeMap<String, Vec<usize>> = BTreeMap::new();
+    let mut incoming: BTreeMap<String, Vec<usize>> = BTreeMap::new();
     let mut nodes: HashMap<String, LayoutNode> = HashMap::new();
 
     for (idx, edge) in cross_edges.iter().enumerate() {
@@ -929,12 +1606,7 @@ fn collect_subgraph_bounds(
     for node_id in &subgraph.nodes {
         if let Some(node) = node_map.get(node_id.as_str()) {
             has_content = true;
-            let node_bounds = Bounds {
-                left: node.x - node.width / 2.0,
-                right: node.x + node.width / 2.0,
-                top: node.y - node.height / 2.0,
-                bottom: node.y + node.height / 2.0,
-            };
+            let node_bounds = node_bounds(node);
             bounds = Some(match bounds {
                 Some(existing) => union_bounds(existing, node_bounds),
                 None => node_bounds,
@@ -959,11 +1631,15 @@ fn collect_subgraph_bounds(
         return None;
     }
 
-    let mut bounds = bounds.unwrap();
-    bounds.left -= padding;
-    bounds.right += padding;
-    bounds.top -= padding;
-    bounds.bottom += padding;
+    let mut content = bounds.unwrap();
+    content.left -= padding;
+    content.right += padding;
+    content.top -= padding;
+    content.bottom += padding;
+    let bounds = Bounds {
+        top: content.top - subgraph.title_height,
+        ..content
+    };
 
     let label = subgraph.title.as_deref().unwrap_or(subgraph.id.as_str());
     out.push(LayoutSubgraphBounds {
@@ -973,12 +1649,30 @@ fn collect_subgraph_bounds(
         top: bounds.top,
         right: bounds.right,
         bottom: bounds.bottom,
+        title_baseline: bounds.top + subgraph.title_baseline_offset,
+        content_left: content.left,
+        content_top: content.top,
+        content_right: content.right,
+        content_bottom: content.bottom,
     });
 
     path.pop();
     Some(bounds)
 }
 
+fn node_bounds(node: &LayoutNode) -> Bounds {
+    Bounds {
+        left: node.x - node.width / 2.0,
+        right: node.x + node.width / 2.0,
+        top: node.y - node.height / 2.0,
+        bottom: node.y + node.height / 2.0,
+    }
+}
+
+fn bounds_overlap(a: Bounds, b: Bounds) -> bool {
+    a.left < b.right && a.right > b.left && a.top < b.bottom && a.bottom > b.top
+}
+
 fn union_bounds(a: Bounds, b: Bounds) -> Bounds {
     Bounds {
         left: a.left.min(b.left),
@@ -989,10 +1683,19 @@ fn union_bounds(a: Bounds, b: Bounds) -> Bounds {
 }
 
 fn estimate_node_size(node: &ParsedNode, style: &LayoutStyle) -> (f32, f32) {
-    let label = node.label.as_deref().unwrap_or(&node.id);
-    let width = (label.chars().count() as f32 * style.char_width + style.node_padding_x * 2.0)
+    let lines = label_lines(node.label.as_deref().unwrap_or(&node.id));
+    // An icon glyph is drawn double-width, plus a space before the text.
+    let text_columns = |text: &str| text.chars().map(char_width).sum::<usize>();
+    let columns = |line: &str| match split_label_glyph(line) {
+        Some((_, "")) => 2,
+        Some((_, rest)) => 3 + text_columns(rest),
+        None => text_columns(line),
+    };
+    let widest = lines.iter().map(|line| columns(line)).max().unwrap_or(0);
+    let width = (widest as f32 * style.char_width + style.node_padding_x * 2.0)
         .max(style.min_width);
-    let height = (style.char_height + style.node_padding_y * 2.0).max(style.min_height);
+    let height = (lines.len() as f32 * style.char_height + style.node_padding_y * 2.0)
+        .max(style.min_height);
     (width, height)
 }
 
@@ -1034,6 +1737,95 @@ fn dfs_cycle_break(
     state[node] = 2;
 }
 
+// Hinted layers seed assign_layers, which only ever pushes nodes further down.
+fn apply_rank_hints(nodes: &mut [WorkNode], rank_hints: &HashMap<String, usize>) {
+    if rank_hints.is_empty() {
+        return;
+    }
+    for node in nodes {
+        if let Some(&rank) = rank_hints.get(&node.id) {
+            node.layer = node.layer.max(rank);
+        }
+    }
+}
+
+// Members joined by a forward path can never share a layer, so the later one is
+// dropped (with a warning) to keep the constraint satisfiable.
+fn same_rank_sets(
+    graph: &Graph,
+    node_index: &HashMap<String, usize>,
+    edges: &[EdgeMeta],
+    warnings: &mut Vec<String>,
+) -> Vec<Vec<usize>> {
+    if graph.same_rank.is_empty() {
+        return Vec::new();
+    }
+    let mut outgoing = vec![Vec::new(); node_index.len()];
+    for edge in edges.iter().filter(|edge| !edge.reversed) {
+        outgoing[edge.from].push(edge.to);
+    }
+
+    let mut sets = Vec::new();
+    for ids in &graph.same_rank {
+        let mut members: Vec<usize> = ids.iter().filter_map(|id| node_index.get(id).copied()).collect();
+        members.dedup();
+        let mut kept: Vec<usize> = Vec::new();
+        for member in members {
+            let conflict = kept.iter().copied().find(|&other| {
+                reaches(&outgoing, other, member) || reaches(&outgoing, member, other)
+            });
+            match conflict {
+                Some(other) => warnings.push(format!(
+                    "same-rank nodes {} and {} are connected; dropping {} from the constraint",
+                    graph.nodes[other].id, graph.nodes[member].id, graph.nodes[member].id
+                )),
+                None => kept.push(member),
+            }
+        }
+        if kept.len() > 1 {
+            sets.push(kept);
+        }
+    }
+    sets
+}
+
+fn reaches(outgoing: &[Vec<usize>], from: usize, to: usize) -> bool {
+    let mut seen = vec![false; outgoing.len()];
+    let mut stack = vec![from];
+    while let Some(node) = stack.pop() {
+        for &next in &outgoing[node] {
+            if next == to {
+                return true;
+            }
+            if !seen[next] {
+                seen[next] = true;
+                stack.push(next);
+            }
+        }
+    }
+    false
+}
+
+fn align_same_ranks(nodes: &mut [WorkNode], edges: &[EdgeMeta], sets: &[Vec<usize>]) {
+    // Sets can still conflict with each other through chains of edges; cap the passes.
+    for _ in 0..=nodes.len() {
+        let mut changed = false;
+        for set in sets {
+            let layer = set.iter().map(|&idx| nodes[idx].layer).max().unwrap_or(0);
+            for &idx in set {
+                if nodes[idx].layer != layer {
+                    nodes[idx].layer = layer;
+                    changed = true;
+                }
+            }
+        }
+        if !changed {
+            return;
+        }
+        assign_layers(nodes, edges);
+    }
+}
+
 fn assign_layers(nodes: &mut [WorkNode], edges: &[EdgeMeta]) {
     let node_count = nodes.len();
     let mut indegree = vec![0usize; node_count];
@@ -1073,10 +1865,163 @@ fn assign_layers(nodes: &mut [WorkNode], edges: &[EdgeMeta]) {
     }
 }
 
+// Network simplex (Gansner et al.) over the longest-path ranks: grow a tight
+// spanning forest, then swap out tree edges with negative cut values until the
+// total rank span of forward edges is minimal. Each tree is re-anchored at rank 0.
+fn network_simplex_layers(nodes: &mut [WorkNode], edges: &[EdgeMeta]) {
+    let node_count = nodes.len();
+    let constraints: Vec<(usize, usize)> = edges
+        .iter()
+        .filter(|edge| !edge.reversed && edge.from != edge.to)
+        .map(|edge| (edge.from, edge.to))
+        .collect();
+    let mut rank: Vec<i64> = nodes.iter().map(|node| node.layer as i64).collect();
+    let slack = |rank: &[i64], (from, to): (usize, usize)| rank[to] - rank[from] - 1;
+
+    let mut in_tree = vec![false; node_count];
+    let mut tree_edges: Vec<usize> = Vec::new();
+    let mut roots = Vec::new();
+    for root in 0..node_count {
+        if in_tree[root] {
+            continue;
+        }
+        roots.push(root);
+        in_tree[root] = true;
+        let mut members = vec![root];
+        loop {
+            let mut grew = true;
+            while grew {
+                grew = false;
+                for (index, &(from, to)) in constraints.iter().enumerate() {
+                    if in_tree[from] != in_tree[to] && slack(&rank, (from, to)) == 0 {
+                        let outside = if in_tree[from] { to } else { from };
+                        in_tree[outside] = true;
+                        members.push(outside);
+                        tree_edges.push(index);
+                        grew = true;
+                    }
+                }
+            }
+
+            let incident = constraints
+                .iter()
+                .filter(|&&(from, to)| in_tree[from] != in_tree[to])
+                .min_by_key(|&&edge| slack(&rank, edge));
+            let Some(&(from, to)) = incident else {
+                break;
+            };
+            let delta = slack(&rank, (from, to));
+            let shift = if in_tree[from] { delta } else { -delta };
+            for &member in &members {
+                rank[member] += shift;
+            }
+        }
+    }
+
+    let max_iterations = node_count * constraints.len().max(1);
+    for _ in 0..max_iterations {
+        let adjacency = tree_adjacency(node_count, &constraints, &tree_edges);
+        let mut exchange = None;
+        for (position, &tree_edge) in tree_edges.iter().enumerate() {
+            let (from, to) = constraints[tree_edge];
+            let tail = tree_component(&adjacency, from, tree_edge);
+            let head = tree_component(&adjacency, to, tree_edge);
+            let cut_value: i64 = constraints
+                .iter()
+                .map(|&(a, b)| {
+                    if tail[a] && head[b] {
+                        1
+                    } else if head[a] && tail[b] {
+                        -1
+                    } else {
+                        0
+                    }
+                })
+                .sum();
+            if cut_value < 0 {
+                exchange = Some((position, head, tail));
+                break;
+            }
+        }
+        let Some((position, head, tail)) = exchange else {
+            break;
+        };
+        let entering = (0..constraints.len())
+            .filter(|index| !tree_edges.contains(index))
+            .filter(|&index| {
+                let (a, b) = constraints[index];
+                head[a] && tail[b]
+            })
+            .min_by_key(|&index| slack(&rank, constraints[index]));
+        let Some(entering) = entering else {
+            break;
+        };
+        let delta = slack(&rank, constraints[entering]);
+        for (node, value) in rank.iter_mut().enumerate() {
+            if head[node] {
+                *value += delta;
+            }
+        }
+        tree_edges[position] = entering;
+    }
+
+    let adjacency = tree_adjacency(node_count, &constraints, &tree_edges);
+    for root in roots {
+        let members = tree_component(&adjacency, root, usize::MAX);
+        let min_rank = (0..node_count)
+            .filter(|&node| members[node])
+            .map(|node| rank[node])
+            .min()
+            .unwrap_or(0);
+        for node in 0..node_count {
+            if members[node] {
+                nodes[node].layer = (rank[node] - min_rank) as usize;
+            }
+        }
+    }
+}
+
+fn tree_adjacency(
+    node_count: usize,
+    constraints: &[(usize, usize)],
+    tree_edges: &[usize],
+) -> Vec<Vec<(usize, usize)>> {
+    let mut adjacency = vec![Vec::new(); node_count];
+    for &index in tree_edges {
+        let (from, to) = constraints[index];
+        adjacency[from].push((to, index));
+        adjacency[to].push((from, index));
+    }
+    adjacency
+}
+
+// Nodes reachable from `start` over tree edges, not crossing `skip_edge`.
+fn tree_component(adjacency: &[Vec<(usize, usize)>], start: usize, skip_edge: usize) -> Vec<bool> {
+    let mut seen = vec![false; adjacency.len()];
+    seen[start] = true;
+    let mut stack = vec![start];
+    while let Some(node) = stack.pop() {
+        for &(next, edge) in &adjacency[node] {
+            if edge != skip_edge && !seen[next] {
+                seen[next] = true;
+                stack.push(next);
+            }
+        }
+    }
+    seen
+}
+
 fn insert_dummy_nodes(
     nodes: &mut Vec<WorkNode>,
     edges: &[EdgeMeta],
+    style: &LayoutStyle,
+    direction: Direction,
 ) -> (Vec<EdgeChain>, Vec<UnitEdge>) {
+    // Dummies reserve one edge lane across the layer so long edges keep a clear column.
+    let (dummy_width, dummy_height) = match direction {
+        Direction::TB | Direction::BT => (style.dummy_width.max(1.0), 1.0),
+        Direction::LR | Direction::RL => (1.0, style.dummy_width.max(1.0)),
+    };
     let mut chains = Vec::new();
     let mut unit_edges = Vec::new();
 
@@ -1104,8 +2049,8 @@ fn insert_dummy_nodes(
             nodes.push(WorkNode {
                 id: dummy_id,
                 label: None,
-                width: 1.0,
-                height: 1.0,
+                width: dummy_width,
+                height: dummy_height,
                 layer,
                 order: 0,
                 x: 0.0,
@@ -1364,6 +2309,148 @@ fn assign_coordinates_lr(nodes: &mut [WorkNode], layers: &[Vec<usize>], style: &
     }
 }
 
+// Priority-layout balancing of the packed cross-axis coordinates: sweeping down
+// and up, each node moves toward the median of its neighbors in the previous
+// layer, pushing strictly lower-priority nodes aside but never closer than `node_gap`.
+// Dummies outrank real nodes, so long edges come out as straight columns.
+fn align_to_neighbors(
+    nodes: &mut [WorkNode],
+    layers: &[Vec<usize>],
+    unit_edges: &[UnitEdge],
+    style: &LayoutStyle,
+    direction: Direction,
+) {
+    let horizontal = matches!(direction, Direction::TB | Direction::BT);
+    let mut up_neighbors = vec![Vec::new(); nodes.len()];
+    let mut down_neighbors = vec![Vec::new(); nodes.len()];
+    for edge in unit_edges {
+        if nodes[edge.to].layer == nodes[edge.from].layer + 1 {
+            down_neighbors[edge.from].push(edge.to);
+            up_neighbors[edge.to].push(edge.from);
+        }
+    }
+
+    let mut pos: Vec<f32> = nodes.iter().map(|n| if horizontal { n.x } else { n.y }).collect();
+    let size: Vec<f32> = nodes
+        .iter()
+        .map(|n| if horizontal { n.width } else { n.height })
+        .collect();
+
+    for pass in 0..4 {
+        let downward = pass % 2 == 0;
+        let (order, neighbor_lists): (Vec<usize>, _) = if downward {
+            ((1..layers.len()).collect(), &up_neighbors)
+        } else {
+            ((0..layers.len().saturating_sub(1)).rev().collect(), &down_neighbors)
+        };
+        for layer_index in order {
+            let layer = &layers[layer_index];
+            let priority: Vec<usize> = layer
+                .iter()
+                .map(|&node| {
+                    if nodes[node].is_dummy {
+                        usize::MAX
+                    } else {
+                        up_neighbors[node].len() + down_neighbors[node].len()
+                    }
+                })
+                .collect();
+            let mut by_priority: Vec<usize> = (0..layer.len()).collect();
+            by_priority.sort_by(|&a, &b| priority[b].cmp(&priority[a]).then(a.cmp(&b)));
+
+            for slot in by_priority {
+                let mut targets: Vec<f32> = neighbor_lists[layer[slot]]
+                    .iter()
+                    .map(|&neighbor| pos[neighbor])
+                    .collect();
+                if !targets.is_empty() {
+                    targets.sort_by(|a, b| a.total_cmp(b));
+                    let mid = targets.len() / 2;
+                    let target = if targets.len() % 2 == 1 {
+                        targets[mid]
+                    } else {
+                        (targets[mid - 1] + targets[mid]) / 2.0
+                    };
+                    let blocked = |other: usize| priority[other] >= priority[slot];
+                    shift_in_layer(&mut pos, &size, layer, slot, target, style.node_gap, blocked);
+                }
+            }
+        }
+    }
+
+    let min_edge = nodes
+        .iter()
+        .enumerate()
+        .map(|(index, _)| pos[index] - size[index] / 2.0)
+        .fold(f32::INFINITY, f32::min);
+    let offset = if min_edge.is_finite() { min_edge } else { 0.0 };
+    for (index, node) in nodes.iter_mut().enumerate() {
+        if horizontal {
+            node.x = pos[index] - offset;
+        } else {
+            node.y = pos[index] - offset;
+        }
+    }
+}
+
+// Moves layer[slot] toward `target` as far as blocked neighbors allow, pushing the
+// unblocked ones in between along so spacing stays at least `gap`.
+fn shift_in_layer(
+    pos: &mut [f32],
+    size: &[f32],
+    layer: &[usize],
+    slot: usize,
+    target: f32,
+    gap: f32,
+    blocked: impl Fn(usize) -> bool,
+) {
+    let node = layer[slot];
+    let spacing = |a: usize, b: usize| size[layer[a]] / 2.0 + gap + size[layer[b]] / 2.0;
+    if target > pos[node] {
+        let mut limit = target;
+        let mut reach = 0.0;
+        for other in slot + 1..layer.len() {
+            reach += spacing(other - 1, other);
+            if blocked(other) {
+                limit = limit.min(pos[layer[other]] - reach);
+                break;
+            }
+        }
+        if limit <= pos[node] {
+            return;
+        }
+        pos[node] = limit;
+        for other in slot + 1..layer.len() {
+            let min = pos[layer[other - 1]] + spacing(other - 1, other);
+            if pos[layer[other]] >= min {
+                break;
+            }
+            pos[layer[other]] = min;
+        }
+    } else if target < pos[node] {
+        let mut limit = target;
+        let mut reach = 0.0;
+        for other in (0..slot).rev() {
+            reach += spacing(other, other + 1);
+            if blocked(other) {
+                limit = limit.max(pos[layer[other]] + reach);
+                break;
+            }
+        }
+        if limit >= pos[node] {
+            return;
+        }
+        pos[node] = limit;
+        for other in (0..slot).rev() {
+            let max = pos[layer[other + 1]] - spacing(other, other + 1);
+            if pos[layer[other]] <= max {
+                break;
+            }
+            pos[layer[other]] = max;
+        }
+    }
+}
+
 fn compute_graph_extent(nodes: &[WorkNode], direction: Direction) -> (f32, f32) {
     let mut max_x = 0.0f32;
     let mut max_y = 0.0f32;
@@ -1380,6 +2467,42 @@ fn compute_graph_extent(nodes: &[WorkNode], direction: Direction) -> (f32, f32)
     }
 }
 
+fn edge_extent(edges: &[LayoutEdge], width: f32, height: f32) -> (f32, f32) {
+    edges
+        .iter()
+        .flat_map(|edge| edge.points.iter())
+        .fold((width, height), |(width, height), &(x, y)| (width.max(x), height.max(y)))
+}
+
+fn mirror_coordinates(
+    nodes: &mut [WorkNode],
+    edges: &mut [LayoutEdge],
+    direction: Direction,
+    width: f32,
+    height: f32,
+) {
+    let points = edges.iter_mut().flat_map(|edge| edge.points.iter_mut());
+    match direction {
+        Direction::TB | Direction::LR => {}
+        Direction::BT => {
+            for node in nodes {
+                node.y = height - node.y;
+            }
+            for point in points {
+                point.1 = height - point.1;
+            }
+        }
+        Direction::RL => {
+            for node in nodes {
+                node.x = width - node.x;
+            }
+            for point in points {
+                point.0 = width - point.0;
+            }
+        }
+    }
+}
+
 fn route_edges(
     nodes: &[WorkNode],
     edges: &[EdgeMeta],
@@ -1392,11 +2515,9 @@ fn route_edges(
     let (start_offsets, end_offsets) = build_edge_port_offsets(nodes, edges, direction.clone(), style);
     for chain in chains {
         let edge = &edges[chain.edge_index];
-        if edge.orig_from == edge.orig_to {
-            let points = match direction {
-                Direction::TB | Direction::BT => route_self_loop_tb(&nodes[edge.orig_from], style),
-                Direction::LR | Direction::RL => route_self_loop_lr(&nodes[edge.orig_from], style),
-            };
+        // Reversal and dummy insertion only ever touch `from`/`to`, so either pair of
+        // endpoints meeting means the edge is a loop on the original node.
+        if edge.orig_from == edge.orig_to || edge.from == edge.to {
             layout_edges.push(LayoutEdge {
                 from: nodes[edge.orig_from].id.clone(),
                 to: nodes[edge.orig_to].id.clone(),
@@ -1404,25 +2525,10 @@ fn route_edges(
                 label: edge.label.clone(),
                 style: edge.style.clone(),
                 arrow: edge.arrow.clone(),
+                label_position: edge.label_position.clone(),
                 reversed: edge.reversed,
-                points,
-            });
-            continue;
-        }
-        if edge.from == edge.to {
-            let points = match direction {
-                Direction::TB | Direction::BT => route_self_loop_tb(&nodes[edge.from], style),
-                Direction::LR | Direction::RL => route_self_loop_lr(&nodes[edge.from], style),
-            };
-            layout_edges.push(LayoutEdge {
-                from: nodes[edge.orig_from].id.clone(),
-                to: nodes[edge.orig_to].id.clone(),
-                is_cross: false,
-                label: edge.label.clone(),
-                style: edge.style.clone(),
-                arrow: edge.arrow.clone(),
-                reversed: edge.reversed,
-                points,
+                source_group: None,
+                points: route_self_loop(&nodes[edge.orig_from], direction.clone(), style),
             });
             continue;
         }
@@ -1458,6 +2564,13 @@ fn route_edges(
                 edge.orig_to,
             ),
         };
+        // Coincident endpoints collapse to a single point; draw a loop rather than
+        // nothing so the edge stays visible and always has a direction.
+        let points = if points.len() < 2 {
+            route_self_loop(&nodes[edge.orig_from], direction.clone(), style)
+        } else {
+            points
+        };
         layout_edges.push(LayoutEdge {
             from: nodes[edge.orig_from].id.clone(),
             to: nodes[edge.orig_to].id.clone(),
@@ -1465,18 +2578,28 @@ fn route_edges(
             label: edge.label.clone(),
             style: edge.style.clone(),
             arrow: edge.arrow.clone(),
+            label_position: edge.label_position.clone(),
             reversed: edge.reversed,
+            source_group: None,
             points,
         });
     }
     layout_edges
 }
 
+fn route_self_loop(node: &WorkNode, direction: Direction, style: &LayoutStyle) -> Vec<(f32, f32)> {
+    match direction {
+        Direction::TB | Direction::BT => route_self_loop_tb(node, style),
+        Direction::LR | Direction::RL => route_self_loop_lr(node, style),
+    }
+}
+
+// Loop sizes are floored at one unit so a zeroed style cannot fold the loop onto itself.
 fn route_self_loop_tb(node: &WorkNode, style: &LayoutStyle) -> Vec<(f32, f32)> {
     let mut points = Vec::new();
     let right = node.x + node.width / 2.0;
-    let loop_w = style.node_gap.max(style.char_width * 3.0);
-    let loop_h = (style.char_height * 1.5).max(style.node_padding_y * 2.0);
+    let loop_w = style.node_gap.max(style.char_width * 3.0).max(1.0);
+    let loop_h = (style.char_height * 1.5).max(style.node_padding_y * 2.0).max(1.0);
     let start = (right, node.y);
     push_point(&mut points, start);
     push_point(&mut points, (right + loop_w, node.y));
@@ -1489,8 +2612,8 @@ fn route_self_loop_tb(node: &WorkNode, style: &LayoutStyle) -> Vec<(f32, f32)> {
 fn route_self_loop_lr(node: &WorkNode, style: &LayoutStyle) -> Vec<(f32, f32)> {
     let mut points = Vec::new();
     let bottom = node.y + node.height / 2.0;
-    let loop_h = style.node_gap.max(style.char_height * 2.0);
-    let loop_w = (style.char_width * 1.5).max(style.node_padding_x * 2.0);
+    let loop_h = style.node_gap.max(style.char_height * 2.0).max(1.0);
+    let loop_w = (style.char_width * 1.5).max(style.node_padding_x * 2.0).max(1.0);
     let start = (node.x, bottom);
     push_point(&mut points, start);
     push_point(&mut points, (node.x, bottom + loop_h));
@@ -1510,7 +2633,11 @@ fn route_chain_tb(
     orig_to: usize,
 ) -> Vec<(f32, f32)> {
     let mut points = Vec::new();
-    for pair in chain.windows(2) {
+    // Lane offsets are relative to the whole edge's midpoint; only the first hop uses them.
+    let first = &nodes[chain[0]];
+    let last = &nodes[chain[chain.len() - 1]];
+    let lane_y = (first.y + first.height / 2.0 + last.y - last.height / 2.0) / 2.0 + lane_offset;
+    for (hop, pair) in chain.windows(2).enumerate() {
         let from = &nodes[pair[0]];
         let to = &nodes[pair[1]];
         let start_x = if pair[0] == orig_from {
@@ -1525,7 +2652,11 @@ fn route_chain_tb(
         };
         let start = (start_x, from.y + from.height / 2.0);
         let end = (end_x, to.y - to.height / 2.0);
-        let mid_y = (start.1 + end.1) / 2.0 + lane_offset;
+        let mid_y = if hop == 0 {
+            lane_y
+        } else {
+            (start.1 + end.1) / 2.0
+        };
         push_point(&mut points, start);
         if (start.0 - end.0).abs() < 0.01 {
             push_point(&mut points, (start.0, mid_y));
@@ -1548,7 +2679,11 @@ fn route_chain_lr(
     orig_to: usize,
 ) -> Vec<(f32, f32)> {
     let mut points = Vec::new();
-    for pair in chain.windows(2) {
+    // Lane offsets are relative to the whole edge's midpoint; only the first hop uses them.
+    let first = &nodes[chain[0]];
+    let last = &nodes[chain[chain.len() - 1]];
+    let lane_x = (first.x + first.width / 2.0 + last.x - last.width / 2.0) / 2.0 + lane_offset;
+    for (hop, pair) in chain.windows(2).enumerate() {
         let from = &nodes[pair[0]];
         let to = &nodes[pair[1]];
         let start_y = if pair[0] == orig_from {
@@ -1563,7 +2698,11 @@ fn route_chain_lr(
         };
         let start = (from.x + from.width / 2.0, start_y);
         let end = (to.x - to.width / 2.0, end_y);
-        let mid_x = (start.0 + end.0) / 2.0 + lane_offset;
+        let mid_x = if hop == 0 {
+            lane_x
+        } else {
+            (start.0 + end.0) / 2.0
+        };
         push_point(&mut points, start);
         if (start.1 - end.1).abs() < 0.01 {
             push_point(&mut points, (mid_x, start.1));
@@ -1583,7 +2722,7 @@ fn build_edge_lane_offsets(
     style: &LayoutStyle,
 ) -> HashMap<usize, f32> {
     let layer_bounds = compute_layer_bounds(nodes, direction.clone());
-    let mut by_from: HashMap<usize, Vec<usize>> = HashMap::new();
+    let mut by_from: BTreeMap<usize, Vec<usize>> = BTreeMap::new();
     for (idx, edge) in edges.iter().enumerate() {
         by_from.entry(edge.from).or_default().push(idx);
     }
@@ -1757,8 +2896,8 @@ fn build_edge_port_offsets(
     direction: Direction,
     style: &LayoutStyle,
 ) -> (HashMap<usize, f32>, HashMap<usize, f32>) {
-    let mut outgoing: HashMap<usize, Vec<usize>> = HashMap::new();
-    let mut incoming: HashMap<usize, Vec<usize>> = HashMap::new();
+    let mut outgoing: BTreeMap<usize, Vec<usize>> = BTreeMap::new();
+    let mut incoming: BTreeMap<usize, Vec<usize>> = BTreeMap::new();
 
     for (idx, edge) in edges.iter().enumerate() {
         outgoing.entry(edge.from).or_default().push(idx);
@@ -1794,9 +2933,60 @@ fn build_edge_port_offsets(
         );
     }
 
+    let step = match direction {
+        Direction::TB | Direction::BT => style.char_width * 2.0,
+        Direction::LR | Direction::RL => style.char_height,
+    };
+    separate_parallel_ports(edges, &mut start_offsets, &mut end_offsets, step);
     (start_offsets, end_offsets)
 }
 
+// Edges joining the same two nodes in opposite directions land in different fan-out
+// groups and can end up on identical ports; shift such bundles apart as a whole.
+fn separate_parallel_ports(
+    edges: &[EdgeMeta],
+    start_offsets: &mut HashMap<usize, f32>,
+    end_offsets: &mut HashMap<usize, f32>,
+    step: f32,
+) {
+    let mut bundles: BTreeMap<(usize, usize), Vec<usize>> = BTreeMap::new();
+    for (idx, edge) in edges.iter().enumerate() {
+        if edge.from != edge.to {
+            let key = (edge.from.min(edge.to), edge.from.max(edge.to));
+            bundles.entry(key).or_default().push(idx);
+        }
+    }
+
+    for bundle in bundles.values().filter(|bundle| bundle.len() > 1) {
+        // Offsets are absolute along the cross axis, so compare them per physical node.
+        let ports = |idx: usize| {
+            let start = start_offsets.get(&idx).copied().unwrap_or(0.0);
+            let end = end_offsets.get(&idx).copied().unwrap_or(0.0);
+            if edges[idx].from < edges[idx].to {
+                (start, end)
+            } else {
+                (end, start)
+            }
+        };
+        let collides = bundle.iter().enumerate().any(|(i, &a)| {
+            bundle[i + 1..].iter().any(|&b| {
+                let (a0, a1) = ports(a);
+                let (b0, b1) = ports(b);
+                (a0 - b0).abs() < 0.5 && (a1 - b1).abs() < 0.5
+            })
+        });
+        if !collides {
+            continue;
+        }
+        let center = (bundle.len() - 1) as f32 / 2.0;
+        for (k, &idx) in bundle.iter().enumerate() {
+            let shift = (k as f32 - center) * step;
+            *start_offsets.entry(idx).or_insert(0.0) += shift;
+            *end_offsets.entry(idx).or_insert(0.0) += shift;
+        }
+    }
+}
+
 fn assign_port_offsets(
     nodes: &[WorkNode],
     edges: &[EdgeMeta],
@@ -1,5 +1,12 @@
-use merdraw_layout::{layout_flowchart, subgraph_bounds, LayoutStyle};
-use merdraw_parser::parse_flowchart;
+use std::collections::HashMap;
+
+use merdraw_layout::{
+    find_highlight_path, label_anchors, layout_flowchart, layout_flowchart_with_rank_hints,
+    layout_flowchart_with_sizes, straighten_edge_points, subgraph_bounds, to_graphviz_plain,
+    LayerAssignment, LayoutGraph,
+    LayoutStyle,
+};
+use merdraw_parser::{parse_flowchart, Direction};
 
 #[test]
 fn layouts_simple_chain_tb() {
@@ -33,6 +40,41 @@ fn inserts_dummy_nodes_for_long_edges() {
     assert!(dummy_count > 0);
 }
 
+#[test]
+fn rank_hint_pushes_node_down_and_keeps_edges_routed() {
+    let graph = parse_flowchart("flowchart TB\nA-->B-->C\nC-->D\n").expect("parse failed");
+    let hints = HashMap::from([("C".to_string(), 3usize)]);
+    let layout = layout_flowchart_with_rank_hints(&graph, &LayoutStyle::default(), &hints);
+
+    let b = layout.nodes.iter().find(|n| n.id == "B").unwrap();
+    let c = layout.nodes.iter().find(|n| n.id == "C").unwrap();
+    let d = layout.nodes.iter().find(|n| n.id == "D").unwrap();
+    assert!(c.layer >= 3);
+    assert!(d.layer > c.layer);
+    assert!(b.y < c.y);
+    assert!(layout.nodes.iter().any(|n| n.is_dummy));
+
+    assert_eq!(layout.edges.len(), 3);
+    for edge in &layout.edges {
+        assert!(edge.points.len() >= 2, "{} -> {} not routed", edge.from, edge.to);
+    }
+}
+
+#[test]
+fn highlights_only_edges_on_found_path() {
+    let graph = parse_flowchart("flowchart TB\nA-->B-->D\nA-->C\nC-->E\n").expect("parse failed");
+    let layout = layout_flowchart(&graph, &LayoutStyle::default());
+    let highlight = find_highlight_path(&layout, "A", "D").expect("path not found");
+
+    for (idx, edge) in layout.edges.iter().enumerate() {
+        let on_path = matches!((edge.from.as_str(), edge.to.as_str()), ("A", "B") | ("B", "D"));
+        assert_eq!(highlight.contains_edge(idx), on_path, "{} -> {}", edge.from, edge.to);
+    }
+    assert!(highlight.contains_node("B"));
+    assert!(!highlight.contains_node("C"));
+    assert!(find_highlight_path(&layout, "D", "A").is_none());
+}
+
 #[test]
 fn subgraph_bounds_do_not_overlap_for_siblings() {
     let graph = parse_flowchart(
@@ -64,6 +106,520 @@ fn subgraph_bounds_do_not_overlap_for_siblings() {
     }
 }
 
+#[test]
+fn subgraph_content_sits_below_title_baseline() {
+    let graph = parse_flowchart("flowchart TB\nsubgraph api[API]\nA-->B\nend\n")
+        .expect("parse failed");
+    let layout = layout_flowchart(&graph, &LayoutStyle::default());
+    let bounds = subgraph_bounds(&layout, 12.0);
+    assert_eq!(bounds.len(), 1);
+    let api = &bounds[0];
+    assert!(api.top < api.title_baseline);
+    assert!(api.content_top > api.title_baseline);
+    assert!(api.content_bottom <= api.bottom);
+    for node in layout.nodes.iter().filter(|n| !n.is_dummy) {
+        assert!(node.y - node.height / 2.0 >= api.content_top);
+    }
+}
+
+#[test]
+fn reports_overlapping_nodes_after_pinning() {
+    let graph = parse_flowchart("flowchart TB\nA-->B\nA-->C\n").expect("parse failed");
+    let mut layout = layout_flowchart(&graph, &LayoutStyle::default());
+    assert!(layout.overlaps().is_empty());
+
+    let (a_x, a_y) = {
+        let a = layout.nodes.iter().find(|n| n.id == "A").unwrap();
+        (a.x, a.y)
+    };
+    let c = layout.nodes.iter_mut().find(|n| n.id == "C").unwrap();
+    c.x = a_x + 4.0;
+    c.y = a_y + 2.0;
+
+    assert_eq!(layout.overlaps(), vec![("A".to_string(), "C".to_string())]);
+}
+
+#[test]
+fn long_edge_runs_straight_through_dummy_column() {
+    let graph =
+        parse_flowchart("flowchart TB\nA-->B-->C-->D-->E\nA-->E\n").expect("parse failed");
+    let style = LayoutStyle::default();
+    let layout = layout_flowchart(&graph, &style);
+    let edge = layout
+        .edges
+        .iter()
+        .find(|e| e.from == "A" && e.to == "E")
+        .unwrap();
+
+    assert!(edge.points.windows(2).all(|pair| pair[1].1 >= pair[0].1));
+
+    let b = layout.nodes.iter().find(|n| n.id == "B").unwrap();
+    let d = layout.nodes.iter().find(|n| n.id == "D").unwrap();
+    let column: Vec<_> = edge
+        .points
+        .iter()
+        .filter(|p| p.1 >= b.y - b.height / 2.0 && p.1 <= d.y + d.height / 2.0)
+        .collect();
+    assert!(column.len() >= 3);
+    assert!(column.iter().all(|p| (p.0 - column[0].0).abs() < 0.01));
+    assert!(column[0].0 >= b.x + b.width / 2.0 + style.dummy_width / 2.0);
+}
+
 fn rects_overlap(a: &merdraw_layout::LayoutSubgraphBounds, b: &merdraw_layout::LayoutSubgraphBounds) -> bool {
     a.left < b.right && a.right > b.left && a.top < b.bottom && a.bottom > b.top
 }
+
+#[test]
+fn subgraph_of_reports_innermost_group_path() {
+    let graph = parse_flowchart(
+        "flowchart TB\n\
+         subgraph outer[Outer]\n\
+         A\n\
+         subgraph inner[Inner]\n\
+         B-->C\n\
+         end\n\
+         end\n\
+         A-->B\n\
+         C-->D\n",
+    )
+    .expect("parse failed");
+    let layout = layout_flowchart(&graph, &LayoutStyle::default());
+
+    assert_eq!(layout.subgraph_of("A").as_deref(), Some("outer"));
+    assert_eq!(layout.subgraph_of("B").as_deref(), Some("outer/inner"));
+    assert_eq!(layout.subgraph_of("D"), None);
+    assert_eq!(layout.subgraph_of("missing"), None);
+}
+
+#[test]
+fn size_overrides_apply_in_flat_and_grouped_layouts() {
+    let sizes = HashMap::from([("B".to_string(), (123.0, 45.0))]);
+    for input in [
+        "flowchart TB\nA-->B-->C\n",
+        "flowchart TB\nsubgraph g[Group]\nB-->C\nend\nA-->B\n",
+    ] {
+        let graph = parse_flowchart(input).expect("parse failed");
+        let style = LayoutStyle::default();
+        let estimated = layout_flowchart(&graph, &style);
+        let layout = layout_flowchart_with_sizes(&graph, &style, &sizes);
+
+        let b = layout.nodes.iter().find(|n| n.id == "B").unwrap();
+        assert_eq!((b.width, b.height), (123.0, 45.0));
+
+        let c = layout.nodes.iter().find(|n| n.id == "C").unwrap();
+        let c_estimated = estimated.nodes.iter().find(|n| n.id == "C").unwrap();
+        assert_eq!((c.width, c.height), (c_estimated.width, c_estimated.height));
+    }
+}
+
+#[test]
+fn straightening_collapses_nearly_straight_edge() {
+    let points = [(40.0, 20.0), (40.0, 40.0), (42.5, 60.0), (42.5, 80.0)];
+    assert_eq!(straighten_edge_points(&points, 3.0), vec![(40.0, 20.0), (40.0, 80.0)]);
+
+    let elbow = [(0.0, 0.0), (0.0, 30.0), (50.0, 30.0)];
+    assert_eq!(straighten_edge_points(&elbow, 3.0), elbow.to_vec());
+}
+
+#[test]
+fn straighten_tolerance_applies_to_laid_out_edges() {
+    let graph = parse_flowchart("flowchart TB\nA-->B\nA-->C\nB-->D\nC-->D\n")
+        .expect("parse failed");
+    let style = LayoutStyle {
+        straighten_tolerance: 4.0,
+        ..LayoutStyle::default()
+    };
+    let layout = layout_flowchart(&graph, &style);
+    for edge in &layout.edges {
+        for segment in edge.points.windows(2) {
+            let dx = (segment[1].0 - segment[0].0).abs();
+            let dy = (segment[1].1 - segment[0].1).abs();
+            assert!(dx == 0.0 || dx > 4.0 || dy <= dx, "jog left in {:?}", edge.points);
+        }
+    }
+}
+
+fn layers_by_id(input: &str, layer_assignment: LayerAssignment) -> HashMap<String, usize> {
+    let graph = parse_flowchart(input).expect("parse failed");
+    let style = LayoutStyle {
+        layer_assignment,
+        ..LayoutStyle::default()
+    };
+    layout_flowchart(&graph, &style)
+        .nodes
+        .into_iter()
+        .filter(|node| !node.is_dummy)
+        .map(|node| (node.id, node.layer))
+        .collect()
+}
+
+#[test]
+fn tight_tree_layering_agrees_where_longest_path_is_optimal() {
+    for input in [
+        "flowchart TB\nA-->B\nA-->C\nB-->D\nC-->D\n",
+        // D must sit below C, so the shortcut A-->D cannot be shortened.
+        "flowchart TB\nA-->B-->C-->D\nA-->D\n",
+    ] {
+        assert_eq!(
+            layers_by_id(input, LayerAssignment::LongestPath),
+            layers_by_id(input, LayerAssignment::TightTree),
+        );
+    }
+}
+
+#[test]
+fn tight_tree_layering_pulls_short_branch_next_to_its_target() {
+    let input = "flowchart TB\nA-->B-->C-->D\nE-->D\n";
+    let longest = layers_by_id(input, LayerAssignment::LongestPath);
+    let tight = layers_by_id(input, LayerAssignment::TightTree);
+    assert_eq!(longest["E"], 0);
+    assert_eq!(tight["E"], 2);
+    assert_eq!(tight["D"], longest["D"]);
+}
+
+#[test]
+fn dummy_chain_is_collinear() {
+    let graph = parse_flowchart(
+        "flowchart TB\nA-->B[A much wider label than the rest]-->C-->D[Medium width]-->E\nA-->E\n",
+    )
+    .expect("parse failed");
+    let layout = layout_flowchart(&graph, &LayoutStyle::default());
+    let dummies: Vec<f32> = layout.nodes.iter().filter(|n| n.is_dummy).map(|n| n.x).collect();
+    assert_eq!(dummies.len(), 3);
+    let min = dummies.iter().copied().fold(f32::INFINITY, f32::min);
+    let max = dummies.iter().copied().fold(f32::NEG_INFINITY, f32::max);
+    assert!(max - min < 1.0, "dummy x positions: {dummies:?}");
+}
+
+#[test]
+fn label_anchors_sit_on_a_segment_of_each_labeled_edge() {
+    let source = "flowchart TB\nA-->|one|B\nA-->|two|C\nC-->D\nA-->|skip|D\nsubgraph G\nE\nend\nD-->|cross|E\n";
+    let graph = parse_flowchart(source).expect("parse failed");
+    let layout = layout_flowchart(&graph, &LayoutStyle::default());
+    let anchors = label_anchors(&layout);
+
+    let labeled: Vec<_> = layout.edges.iter().filter(|edge| edge.label.is_some()).collect();
+    assert_eq!(anchors.len(), labeled.len());
+    for edge in labeled {
+        let key = (edge.from.clone(), edge.to.clone(), edge.label.clone().unwrap());
+        let &(x, y) = anchors.get(&key).expect("missing anchor");
+        let on_segment = edge.points.windows(2).any(|segment| {
+            let (a, b) = (segment[0], segment[1]);
+            let cross = (b.0 - a.0) * (y - a.1) - (b.1 - a.1) * (x - a.0);
+            let within = x >= a.0.min(b.0) - 0.01
+                && x <= a.0.max(b.0) + 0.01
+                && y >= a.1.min(b.1) - 0.01
+                && y <= a.1.max(b.1) + 0.01;
+            cross.abs() < 0.01 && within
+        });
+        assert!(on_segment, "anchor {:?} not on {:?}", (x, y), edge.points);
+    }
+}
+
+#[test]
+fn same_rank_constraint_aligns_layers() {
+    let graph = parse_flowchart("flowchart TB\nA-->B-->C-->D\nA-->E\nrank same D E\n")
+        .expect("parse failed");
+    let layout = layout_flowchart(&graph, &LayoutStyle::default());
+    let layer = |id: &str| layout.nodes.iter().find(|n| n.id == id).unwrap().layer;
+    assert_eq!(layer("D"), 3);
+    assert_eq!(layer("E"), layer("D"));
+    assert!(layout.warnings.is_empty());
+
+    // Directly connected members cannot share a rank; the constraint drops one instead.
+    let graph = parse_flowchart("flowchart TB\nA-->B\nrank same A B\n").expect("parse failed");
+    let layout = layout_flowchart(&graph, &LayoutStyle::default());
+    let layer = |id: &str| layout.nodes.iter().find(|n| n.id == id).unwrap().layer;
+    assert!(layer("A") < layer("B"));
+    assert_eq!(
+        layout.warnings,
+        vec!["same-rank nodes A and B are connected; dropping B from the constraint"]
+    );
+
+    // Warnings from a subgraph's own layout reach the combined one.
+    let graph = parse_flowchart("flowchart TB\nsubgraph g\nA-->B\nend\nrank same A B\n")
+        .expect("parse failed");
+    let layout = layout_flowchart(&graph, &LayoutStyle::default());
+    assert_eq!(layout.warnings.len(), 1, "{:?}", layout.warnings);
+}
+
+#[test]
+fn bt_and_rl_mirror_the_layout() {
+    let node = |layout: &merdraw_layout::LayoutGraph, id: &str| {
+        let node = layout.nodes.iter().find(|n| n.id == id).unwrap();
+        (node.x, node.y)
+    };
+
+    let graph = parse_flowchart("flowchart BT\nA-->B\n").expect("parse failed");
+    let layout = layout_flowchart(&graph, &LayoutStyle::default());
+    assert!(node(&layout, "A").1 > node(&layout, "B").1);
+    let edge = &layout.edges[0];
+    assert!(edge.points[0].1 > edge.points[edge.points.len() - 1].1);
+
+    let graph = parse_flowchart("flowchart RL\nA-->B\n").expect("parse failed");
+    let layout = layout_flowchart(&graph, &LayoutStyle::default());
+    assert!(node(&layout, "A").0 > node(&layout, "B").0);
+}
+
+#[test]
+fn nested_subgraphs_get_their_own_boxes() {
+    let graph = parse_flowchart(
+        "flowchart TB\n\
+         subgraph outer\n\
+         A-->B\n\
+         subgraph middle\n\
+         C-->D\n\
+         subgraph inner\n\
+         E\n\
+         end\n\
+         end\n\
+         end\n\
+         B-->C\n\
+         D-->E\n",
+    )
+    .expect("parse failed");
+    let layout = layout_flowchart(&graph, &LayoutStyle::default());
+    assert!(layout.overlaps().is_empty());
+    let bounds = subgraph_bounds(&layout, 12.0);
+    let find = |path: &str| bounds.iter().find(|b| b.path == path).expect(path);
+    let outer = find("outer");
+    let middle = find("outer/middle");
+    let inner = find("outer/middle/inner");
+
+    let contains = |a: &merdraw_layout::LayoutSubgraphBounds, b: &merdraw_layout::LayoutSubgraphBounds| {
+        a.left <= b.left && a.right >= b.right && a.top <= b.top && a.bottom >= b.bottom
+    };
+    assert!(contains(outer, middle));
+    assert!(contains(middle, inner));
+
+    for node in layout.nodes.iter().filter(|n| !n.is_dummy) {
+        let (left, right) = (node.x - node.width / 2.0, node.x + node.width / 2.0);
+        let (top, bottom) = (node.y - node.height / 2.0, node.y + node.height / 2.0);
+        let inside = |b: &merdraw_layout::LayoutSubgraphBounds| {
+            left >= b.left && right <= b.right && top >= b.top && bottom <= b.bottom
+        };
+        let touches = |b: &merdraw_layout::LayoutSubgraphBounds| {
+            left < b.right && right > b.left && top < b.bottom && bottom > b.top
+        };
+        match node.id.as_str() {
+            "A" | "B" => assert!(inside(outer) && !touches(middle), "{} misplaced", node.id),
+            "C" | "D" => assert!(inside(middle) && !touches(inner), "{} misplaced", node.id),
+            _ => assert!(inside(inner)),
+        }
+    }
+}
+
+#[test]
+fn graphviz_plain_lists_every_real_node() {
+    let graph = parse_flowchart("flowchart TB\nA[Start here]-->B\nA-->|go|C\nB-->D\nC-->D\nA-->D\n")
+        .expect("parse failed");
+    let layout = layout_flowchart(&graph, &LayoutStyle::default());
+    let plain = to_graphviz_plain(&layout);
+    let lines: Vec<&str> = plain.lines().collect();
+    assert!(lines[0].starts_with("graph 1 "));
+    assert_eq!(lines.last(), Some(&"stop"));
+
+    let node_lines: Vec<&str> = lines.iter().copied().filter(|l| l.starts_with("node ")).collect();
+    let real = layout.nodes.iter().filter(|n| !n.is_dummy).count();
+    assert_eq!(node_lines.len(), real);
+    for line in &node_lines {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        for value in &fields[2..6] {
+            value.parse::<f32>().expect("numeric coordinate");
+        }
+    }
+    assert!(plain.contains("node A ") && plain.contains("\"Start here\""));
+    assert_eq!(lines.iter().filter(|l| l.starts_with("edge ")).count(), layout.edges.len());
+}
+
+#[test]
+fn lr_groups_stack_along_x_and_cross_edges_use_facing_sides() {
+    let graph = parse_flowchart(
+        "flowchart LR\nsubgraph one\nA-->B\nend\nsubgraph two\nC-->D\nend\nB-->C\n",
+    )
+    .expect("parse failed");
+    let layout = layout_flowchart(&graph, &LayoutStyle::default());
+    let bounds = subgraph_bounds(&layout, 12.0);
+    let one = bounds.iter().find(|b| b.path == "one").unwrap();
+    let two = bounds.iter().find(|b| b.path == "two").unwrap();
+    assert!(two.left > one.right);
+
+    let node = |id: &str| layout.nodes.iter().find(|n| n.id == id).unwrap();
+    let (b, c) = (node("B"), node("C"));
+    let edge = layout.edges.iter().find(|e| e.from == "B" && e.to == "C").unwrap();
+    let (start, end) = (edge.points[0], edge.points[edge.points.len() - 1]);
+    assert_eq!(start.0, b.x + b.width / 2.0);
+    assert_eq!(end.0, c.x - c.width / 2.0);
+}
+
+#[test]
+fn opposite_edges_between_a_pair_do_not_share_ports() {
+    let graph = parse_flowchart("flowchart TB\nA-->B\nB-->A\n").expect("parse failed");
+    let layout = layout_flowchart(&graph, &LayoutStyle::default());
+    let xs: Vec<f32> = layout.edges.iter().map(|e| e.points[0].0).collect();
+    assert!((xs[0] - xs[1]).abs() > 1.0, "{xs:?}");
+}
+
+#[test]
+fn direction_override_flips_tb_to_lr() {
+    let graph = parse_flowchart("flowchart TB\nA-->B\n").expect("parse failed");
+    let position = |layout: &LayoutGraph, id: &str| {
+        let node = layout.nodes.iter().find(|node| node.id == id).unwrap();
+        (node.x, node.y)
+    };
+    let tb = layout_flowchart(&graph, &LayoutStyle::default());
+    assert!(position(&tb, "A").1 < position(&tb, "B").1);
+    assert_eq!(position(&tb, "A").0, position(&tb, "B").0);
+
+    let style = LayoutStyle {
+        direction: Some(Direction::LR),
+        ..LayoutStyle::default()
+    };
+    let lr = layout_flowchart(&graph, &style);
+    assert!(position(&lr, "A").0 < position(&lr, "B").0);
+    assert_eq!(position(&lr, "A").1, position(&lr, "B").1);
+    assert_eq!(graph.direction, Direction::TB);
+}
+
+#[test]
+fn self_loop_in_a_cycle_routes_as_a_multi_point_loop() {
+    let graph = parse_flowchart("flowchart TB\nA-->B-->C-->A\nA-->A\n").expect("parse failed");
+    for direction in [Direction::TB, Direction::LR] {
+        let style = LayoutStyle {
+            direction: Some(direction),
+            ..LayoutStyle::default()
+        };
+        let layout = layout_flowchart(&graph, &style);
+        let edge = layout
+            .edges
+            .iter()
+            .find(|edge| edge.from == "A" && edge.to == "A")
+            .unwrap();
+        assert!(edge.points.len() >= 3, "{:?}", edge.points);
+        assert_eq!(edge.points.first(), edge.points.last());
+        for pair in edge.points.windows(2) {
+            assert_ne!(pair[0], pair[1]);
+        }
+        assert!(layout.edges.iter().all(|edge| edge.points.len() >= 2));
+    }
+}
+
+#[test]
+fn extent_can_include_edges_that_reach_past_the_nodes() {
+    let outermost = |layout: &LayoutGraph| {
+        layout
+            .edges
+            .iter()
+            .flat_map(|edge| edge.points.iter())
+            .fold((0.0f32, 0.0f32), |(x, y), point| (x.max(point.0), y.max(point.1)))
+    };
+    // The self-loop on C bulges out to the right of the widest node.
+    let graph = parse_flowchart("flowchart TB\nA-->B\nA-->C\nB-->D\nC-->D\nC-->C\n")
+        .expect("parse failed");
+    let nodes_only = layout_flowchart(&graph, &LayoutStyle::default());
+    assert!(outermost(&nodes_only).0 > nodes_only.width);
+
+    let style = LayoutStyle {
+        extent_includes_edges: true,
+        ..LayoutStyle::default()
+    };
+    let layout = layout_flowchart(&graph, &style);
+    let (max_x, max_y) = outermost(&layout);
+    assert!(layout.width >= max_x && layout.height >= max_y);
+
+    let grouped = parse_flowchart(
+        "flowchart TB\nsubgraph one\nA-->B\nend\nsubgraph two\nC-->D\nend\nB-->C\nD-->A\n",
+    )
+    .expect("parse failed");
+    let layout = layout_flowchart(&grouped, &LayoutStyle::default());
+    let (max_x, max_y) = outermost(&layout);
+    assert!(layout.width >= max_x && layout.height >= max_y);
+}
+
+#[test]
+fn repeated_layouts_are_bit_identical() {
+    let source = "flowchart TB\nsubgraph one\nA-->B\nA-->C\nA-->D\nend\nsubgraph two\nE-->F\nend\n\
+                  B-->E\nC-->E\nD-->F\nA-->F\nF-->A\nB-->C\nC-->B\n";
+    let graph = parse_flowchart(source).expect("parse failed");
+    let bits = |layout: &LayoutGraph| {
+        let nodes: Vec<_> = layout
+            .nodes
+            .iter()
+            .map(|node| (node.id.clone(), node.x.to_bits(), node.y.to_bits()))
+            .collect();
+        let points: Vec<Vec<_>> = layout
+            .edges
+            .iter()
+            .map(|edge| edge.points.iter().map(|p| (p.0.to_bits(), p.1.to_bits())).collect())
+            .collect();
+        (nodes, points)
+    };
+    let first = bits(&layout_flowchart(&graph, &LayoutStyle::default()));
+    for _ in 0..8 {
+        assert_eq!(bits(&layout_flowchart(&graph, &LayoutStyle::default())), first);
+    }
+}
+
+#[test]
+fn disconnected_components_pack_into_a_compact_box() {
+    let style = LayoutStyle::default();
+    let part = |source: &str| layout_flowchart(&parse_flowchart(source).unwrap(), &style);
+    let first = part("flowchart LR\nA-->B\n");
+    let second = part("flowchart LR\nC-->D\n");
+
+    let graph = parse_flowchart("flowchart LR\nA-->B\nC-->D\n").expect("parse failed");
+    let layout = layout_flowchart(&graph, &style);
+    assert!(layout.width < first.width + second.width, "{} wide", layout.width);
+    assert!(layout.height < layout.width * 2.0 && layout.width < layout.height * 4.0);
+
+    let ids: Vec<&str> = layout.nodes.iter().map(|node| node.id.as_str()).collect();
+    assert_eq!(ids, ["A", "B", "C", "D"]);
+    for node in &layout.nodes {
+        assert!(node.x - node.width / 2.0 >= 0.0 && node.x + node.width / 2.0 <= layout.width);
+        assert!(node.y - node.height / 2.0 >= 0.0 && node.y + node.height / 2.0 <= layout.height);
+    }
+    let a = layout.nodes.iter().find(|node| node.id == "A").unwrap();
+    let c = layout.nodes.iter().find(|node| node.id == "C").unwrap();
+    assert!((a.y - c.y).abs() > a.height, "components overlap");
+}
+
+#[test]
+fn virtual_group_margin_keeps_lone_nodes_off_subgraph_frames() {
+    let graph = parse_flowchart("flowchart LR\nsubgraph g\nA-->B\nend\nB-->C\n").expect("parse failed");
+    let frame_gap = |margin: f32| {
+        let style = LayoutStyle {
+            virtual_group_margin: margin,
+            ..LayoutStyle::default()
+        };
+        let layout = layout_flowchart(&graph, &style);
+        let frame = &subgraph_bounds(&layout, 12.0)[0];
+        let lone = layout.nodes.iter().find(|node| node.id == "C").unwrap();
+        lone.x - lone.width / 2.0 - frame.right
+    };
+    let flush = frame_gap(0.0);
+    let spaced = frame_gap(40.0);
+    assert!(spaced >= 40.0, "gap {spaced}");
+    assert!(spaced >= flush + 40.0 - 0.5, "gap {spaced} vs {flush}");
+}
+
+#[test]
+fn lr_gap_overrides_change_lr_layouts_only() {
+    let source = "A-->B\nA-->C\nB-->D\nC-->D\n";
+    let extent = |direction: &str, style: &LayoutStyle| {
+        let graph = parse_flowchart(&format!("flowchart {direction}\n{source}")).unwrap();
+        let layout = layout_flowchart(&graph, style);
+        (layout.width, layout.height)
+    };
+    let base = LayoutStyle::default();
+    let tight = LayoutStyle {
+        node_gap_lr: Some(4.0),
+        layer_gap_lr: Some(80.0),
+        ..LayoutStyle::default()
+    };
+
+    let (base_w, base_h) = extent("LR", &base);
+    let (tight_w, tight_h) = extent("LR", &tight);
+    assert!(tight_h < base_h, "{tight_h} vs {base_h}");
+    assert!(tight_w > base_w, "{tight_w} vs {base_w}");
+    assert_eq!(extent("TB", &tight), extent("TB", &base));
+}
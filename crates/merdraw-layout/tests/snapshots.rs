@@ -0,0 +1,41 @@
+use std::fs;
+use std::path::PathBuf;
+
+use merdraw_layout::{layout_flowchart, layout_snapshot, LayoutStyle};
+use merdraw_parser::parse_flowchart;
+
+// Set UPDATE_SNAPSHOTS=1 to rewrite the committed snapshots after an intended layout change.
+fn assert_snapshot(name: &str, input: &str) {
+    let graph = parse_flowchart(input).expect("parse failed");
+    let layout = layout_flowchart(&graph, &LayoutStyle::default());
+    let actual = layout_snapshot(&layout);
+
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/snapshots")
+        .join(format!("{name}.snap"));
+    if std::env::var_os("UPDATE_SNAPSHOTS").is_some() {
+        fs::write(&path, &actual).expect("failed to write snapshot");
+        return;
+    }
+    let expected = fs::read_to_string(&path)
+        .unwrap_or_else(|_| panic!("missing snapshot {}", path.display()));
+    assert_eq!(actual, expected, "layout snapshot mismatch for {name}");
+}
+
+#[test]
+fn snapshot_chain() {
+    assert_snapshot("chain", "flowchart TB\nA-->B-->C-->D\n");
+}
+
+#[test]
+fn snapshot_diamond() {
+    assert_snapshot("diamond", "flowchart TB\nA-->B\nA-->C\nB-->D\nC-->D\n");
+}
+
+#[test]
+fn snapshot_subgraph() {
+    assert_snapshot(
+        "subgraph",
+        "flowchart TB\nsubgraph one \"One\"\nA-->B\nend\nsubgraph two \"Two\"\nC-->D\nend\nB-->C\n",
+    );
+}
@@ -0,0 +1,66 @@
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Instant;
+
+use merdraw_parser::{parse_flowchart, Direction, FlowchartParser, Graph};
+
+struct CountingAlloc;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        unsafe { System.alloc(layout) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { System.dealloc(ptr, layout) }
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        unsafe { System.realloc(ptr, layout, new_size) }
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAlloc = CountingAlloc;
+
+const ITERATIONS: usize = 20;
+
+fn build_source(lines: usize) -> String {
+    let mut source = String::from("flowchart TB\n");
+    for i in 0..lines {
+        source.push_str(&format!("N{i}[Node {i}] --> N{}\n", i + 1));
+    }
+    source
+}
+
+fn measure(name: &str, mut run: impl FnMut()) {
+    run();
+    let before = ALLOCATIONS.load(Ordering::Relaxed);
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        run();
+    }
+    let elapsed = start.elapsed() / ITERATIONS as u32;
+    let allocations = (ALLOCATIONS.load(Ordering::Relaxed) - before) / ITERATIONS;
+    println!("{name:<8} {elapsed:>10.2?}/parse  {allocations:>7} allocations/parse");
+}
+
+fn main() {
+    let source = build_source(5_000);
+
+    measure("fresh", || {
+        let graph = parse_flowchart(&source).expect("parse failed");
+        std::hint::black_box(graph);
+    });
+
+    let mut parser = FlowchartParser::new();
+    let mut graph = Graph::new(Direction::TB);
+    measure("reused", || {
+        parser.parse_into(&source, &mut graph).expect("parse failed");
+        std::hint::black_box(&graph);
+    });
+}
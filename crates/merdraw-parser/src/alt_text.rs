@@ -0,0 +1,46 @@
+use std::fmt::Write as _;
+
+use crate::ast::{Direction, Graph};
+
+// A one-paragraph description of `graph` for image alt text. The chart's own `accTitle` and
+// `accDescr` win; otherwise it names the direction, the counts, and each edge's endpoints.
+pub fn alt_text(graph: &Graph) -> String {
+    match (graph.acc_title.as_deref(), graph.acc_descr.as_deref()) {
+        (Some(title), Some(descr)) => return format!("{title}. {descr}"),
+        (Some(text), None) | (None, Some(text)) => return text.to_string(),
+        (None, None) => {}
+    }
+
+    let direction = match graph.direction {
+        Direction::TB => "top to bottom",
+        Direction::BT => "bottom to top",
+        Direction::LR => "left to right",
+        Direction::RL => "right to left",
+    };
+    let plural = |count: usize, noun: &str| {
+        format!("{count} {noun}{}", if count == 1 { "" } else { "s" })
+    };
+    let mut out = format!(
+        "Flowchart, {direction}, with {} and {}",
+        plural(graph.nodes.len(), "node"),
+        plural(graph.edges.len(), "edge")
+    );
+    let name = |id: &str| {
+        graph
+            .nodes
+            .iter()
+            .find(|node| node.id == id)
+            .and_then(|node| node.label.as_deref())
+            .unwrap_or(id)
+            .to_string()
+    };
+    for (index, edge) in graph.edges.iter().enumerate() {
+        out.push_str(if index == 0 { ": " } else { ", " });
+        let _ = write!(out, "{} to {}", name(&edge.from), name(&edge.to));
+        if let Some(label) = edge.label.as_deref() {
+            let _ = write!(out, " ({label})");
+        }
+    }
+    out.push('.');
+    out
+}
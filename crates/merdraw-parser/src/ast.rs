@@ -1,4 +1,8 @@
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Direction {
     TB,
     BT,
@@ -6,7 +10,12 @@ pub enum Direction {
     RL,
 }
 
+impl Direction {
+    pub const ALL: [Direction; 4] = [Direction::TB, Direction::BT, Direction::LR, Direction::RL];
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum NodeShape {
     Plain,
     Bracket,
@@ -14,57 +23,369 @@ pub enum NodeShape {
     Circle,
     Diamond,
     Hexagon,
+    Stadium,
+    Subroutine,
+    Cylinder,
+}
+
+impl NodeShape {
+    pub const ALL: [NodeShape; 9] = [
+        NodeShape::Plain,
+        NodeShape::Bracket,
+        NodeShape::Round,
+        NodeShape::Circle,
+        NodeShape::Diamond,
+        NodeShape::Hexagon,
+        NodeShape::Stadium,
+        NodeShape::Subroutine,
+        NodeShape::Cylinder,
+    ];
+
+    pub fn example(&self) -> &'static str {
+        match self {
+            NodeShape::Plain => "A",
+            NodeShape::Bracket => "A[label]",
+            NodeShape::Round => "A(label)",
+            NodeShape::Circle => "A((label))",
+            NodeShape::Diamond => "A{label}",
+            NodeShape::Hexagon => "A{{label}}",
+            NodeShape::Stadium => "A([label])",
+            NodeShape::Subroutine => "A[[label]]",
+            NodeShape::Cylinder => "A[(label)]",
+        }
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Node {
     pub id: String,
     pub label: Option<String>,
     pub shape: NodeShape,
 }
 
+// Splits a label on `<br>`, `<br/>`, and `<br />` (any case); a label without one is a
+// single line.
+pub fn label_lines(label: &str) -> Vec<&str> {
+    let mut lines = Vec::new();
+    let mut start = 0;
+    let mut search = 0;
+    while let Some(rel) = label[search..].find('<') {
+        let open = search + rel;
+        let tag = label[open + 1..]
+            .get(..2)
+            .filter(|name| name.eq_ignore_ascii_case("br"))
+            .and_then(|_| {
+                let rest = label[open + 3..].trim_start();
+                let rest = rest.strip_prefix('/').unwrap_or(rest);
+                rest.strip_prefix('>')
+                    .map(|after| label.len() - after.len())
+            });
+        match tag {
+            Some(end) => {
+                lines.push(&label[start..open]);
+                start = end;
+                search = end;
+            }
+            None => search = open + 1,
+        }
+    }
+    lines.push(&label[start..]);
+    lines
+}
+
+// Splits a leading icon glyph (an emoji or pictograph, with any variation selector or
+// skin-tone modifier) from the rest of a label, dropping the space between.
+pub fn split_label_glyph(label: &str) -> Option<(&str, &str)> {
+    let mut chars = label.char_indices();
+    let (_, first) = chars.next()?;
+    if !is_emoji(first) {
+        return None;
+    }
+    let mut end = first.len_utf8();
+    for (index, ch) in chars {
+        if !matches!(ch, '\u{FE0E}' | '\u{FE0F}' | '\u{1F3FB}'..='\u{1F3FF}') {
+            break;
+        }
+        end = index + ch.len_utf8();
+    }
+    Some((&label[..end], label[end..].trim_start()))
+}
+
+// Terminal columns a character takes. East Asian wide and fullwidth ranges and emoji take
+// two; variation selectors, skin-tone modifiers, and joiners take none.
+pub fn char_width(ch: char) -> usize {
+    if is_emoji(ch) {
+        return 2;
+    }
+    match ch as u32 {
+        0x200D | 0xFE00..=0xFE0F | 0x1F3FB..=0x1F3FF => 0,
+        0x1100..=0x115F
+        | 0x2E80..=0x303E
+        | 0x3041..=0x33FF
+        | 0x3400..=0x4DBF
+        | 0x4E00..=0x9FFF
+        | 0xA000..=0xA4CF
+        | 0xAC00..=0xD7A3
+        | 0xF900..=0xFAFF
+        | 0xFE30..=0xFE4F
+        | 0xFF00..=0xFF60
+        | 0xFFE0..=0xFFE6
+        | 0x20000..=0x3FFFD => 2,
+        _ => 1,
+    }
+}
+
+// Pictographs, emoticons, transport symbols, and the supplemental emoji blocks. Arrows,
+// math, and technical symbols stay ordinary one-column text.
+fn is_emoji(ch: char) -> bool {
+    matches!(
+        ch as u32,
+        0x1F300..=0x1F3FA | 0x1F400..=0x1F64F | 0x1F680..=0x1F6FF | 0x1F900..=0x1FAFF
+    )
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum EdgeStyle {
     Solid,
     Dotted,
     Thick,
+    Invisible,
+}
+
+impl EdgeStyle {
+    pub const ALL: [EdgeStyle; 4] = [
+        EdgeStyle::Solid,
+        EdgeStyle::Dotted,
+        EdgeStyle::Thick,
+        EdgeStyle::Invisible,
+    ];
+
+    pub fn operator(&self, arrow: &EdgeArrow) -> &'static str {
+        match (self, arrow) {
+            (EdgeStyle::Solid, EdgeArrow::Forward) => "-->",
+            (EdgeStyle::Solid, EdgeArrow::None) => "---",
+            (EdgeStyle::Solid, EdgeArrow::Backward) => "<--",
+            (EdgeStyle::Solid, EdgeArrow::Both) => "<-->",
+            (EdgeStyle::Solid, EdgeArrow::Cross) => "--x",
+            (EdgeStyle::Solid, EdgeArrow::Circle) => "--o",
+            (EdgeStyle::Dotted, EdgeArrow::Forward) => "-.->",
+            (EdgeStyle::Dotted, EdgeArrow::None) => "-.-",
+            (EdgeStyle::Dotted, EdgeArrow::Backward) => "<-.-",
+            (EdgeStyle::Dotted, EdgeArrow::Both) => "<-.->",
+            (EdgeStyle::Dotted, EdgeArrow::Cross) => "-.-x",
+            (EdgeStyle::Dotted, EdgeArrow::Circle) => "-.-o",
+            (EdgeStyle::Thick, EdgeArrow::Forward) => "==>",
+            (EdgeStyle::Thick, EdgeArrow::None) => "===",
+            (EdgeStyle::Thick, EdgeArrow::Backward) => "<==",
+            (EdgeStyle::Thick, EdgeArrow::Both) => "<==>",
+            (EdgeStyle::Thick, EdgeArrow::Cross) => "==x",
+            (EdgeStyle::Thick, EdgeArrow::Circle) => "==o",
+            (EdgeStyle::Invisible, _) => "~~~",
+        }
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum EdgeArrow {
     None,
     Forward,
+    Backward,
+    Both,
+    // `--x` and `--o`: the target end gets a cross or a circle instead of a head.
+    Cross,
+    Circle,
+}
+
+impl EdgeArrow {
+    pub const ALL: [EdgeArrow; 6] = [
+        EdgeArrow::None,
+        EdgeArrow::Forward,
+        EdgeArrow::Backward,
+        EdgeArrow::Both,
+        EdgeArrow::Cross,
+        EdgeArrow::Circle,
+    ];
+
+    pub fn has_start_head(&self) -> bool {
+        matches!(self, EdgeArrow::Backward | EdgeArrow::Both)
+    }
+
+    pub fn has_end_head(&self) -> bool {
+        matches!(
+            self,
+            EdgeArrow::Forward | EdgeArrow::Both | EdgeArrow::Cross | EdgeArrow::Circle
+        )
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum EdgeLabelPosition {
+    #[default]
+    Auto,
+    Above,
+    Below,
+    Inline,
+}
+
+impl EdgeLabelPosition {
+    pub fn from_keyword(value: &str) -> Option<Self> {
+        match value {
+            "auto" => Some(EdgeLabelPosition::Auto),
+            "above" => Some(EdgeLabelPosition::Above),
+            "below" => Some(EdgeLabelPosition::Below),
+            "inline" => Some(EdgeLabelPosition::Inline),
+            _ => None,
+        }
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Edge {
     pub from: String,
     pub to: String,
     pub label: Option<String>,
     pub style: EdgeStyle,
     pub arrow: EdgeArrow,
+    pub label_position: EdgeLabelPosition,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Graph {
     pub direction: Direction,
     pub nodes: Vec<Node>,
     pub edges: Vec<Edge>,
     pub subgraphs: Vec<Subgraph>,
+    // Each entry lists node ids from one `rank same ...` statement.
+    pub same_rank: Vec<Vec<String>>,
+    // `classDef` styles by class name, and the classes `class` gave each node id, in order.
+    pub class_defs: BTreeMap<String, NodeStyle>,
+    pub node_classes: BTreeMap<String, Vec<String>>,
+    // `accTitle` / `accDescr` text for screen readers; renderers that can carry it do.
+    pub acc_title: Option<String>,
+    pub acc_descr: Option<String>,
 }
 
 impl Graph {
+    // The graph laid out in `direction` when one is given; borrowed when nothing changes.
+    pub fn redirected(&self, direction: Option<&Direction>) -> Cow<'_, Graph> {
+        match direction {
+            Some(direction) if *direction != self.direction => {
+                Cow::Owned(Graph { direction: direction.clone(), ..self.clone() })
+            }
+            _ => Cow::Borrowed(self),
+        }
+    }
+
     pub fn new(direction: Direction) -> Self {
         Self {
             direction,
             nodes: Vec::new(),
             edges: Vec::new(),
             subgraphs: Vec::new(),
+            same_rank: Vec::new(),
+            class_defs: BTreeMap::new(),
+            node_classes: BTreeMap::new(),
+            acc_title: None,
+            acc_descr: None,
         }
     }
+
+    // The `default` class applies to every node; later classes override earlier ones.
+    pub fn node_style(&self, id: &str) -> NodeStyle {
+        let mut style = self.class_defs.get("default").cloned().unwrap_or_default();
+        for class in self.node_classes.get(id).into_iter().flatten() {
+            if let Some(def) = self.class_defs.get(class) {
+                style.merge(def);
+            }
+        }
+        style
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Rgba(pub u8, pub u8, pub u8, pub u8);
+
+impl Rgba {
+    // Accepts `#rgb`, `#rgba`, `#rrggbb`, `#rrggbbaa`, `rgb(r, g, b)`, `rgba(r, g, b, a)`
+    // with `a` in 0..=1, `transparent`/`none`, and a few common color names.
+    pub fn parse(value: &str) -> Option<Rgba> {
+        let value = value.trim();
+        if let Some(hex) = value.strip_prefix('#') {
+            let digit = |i: usize| u8::from_str_radix(hex.get(i..i + 1)?, 16).ok();
+            let pair = |i: usize| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok();
+            return match hex.len() {
+                3 | 4 => Some(Rgba(
+                    digit(0)? * 17,
+                    digit(1)? * 17,
+                    digit(2)? * 17,
+                    if hex.len() == 4 { digit(3)? * 17 } else { 255 },
+                )),
+                6 | 8 => Some(Rgba(
+                    pair(0)?,
+                    pair(2)?,
+                    pair(4)?,
+                    if hex.len() == 8 { pair(6)? } else { 255 },
+                )),
+                _ => None,
+            };
+        }
+        let lower = value.to_ascii_lowercase();
+        if let Some(args) = lower
+            .strip_prefix("rgba(")
+            .or_else(|| lower.strip_prefix("rgb("))
+            .and_then(|rest| rest.strip_suffix(')'))
+        {
+            let parts: Vec<&str> = args.split(',').map(str::trim).collect();
+            let channel = |i: usize| parts.get(i)?.parse::<u8>().ok();
+            let alpha = match parts.len() {
+                3 => 255,
+                4 => (parts[3].parse::<f32>().ok()?.clamp(0.0, 1.0) * 255.0).round() as u8,
+                _ => return None,
+            };
+            return Some(Rgba(channel(0)?, channel(1)?, channel(2)?, alpha));
+        }
+        match lower.as_str() {
+            "transparent" | "none" => Some(Rgba(0, 0, 0, 0)),
+            "black" => Some(Rgba(0, 0, 0, 255)),
+            "white" => Some(Rgba(255, 255, 255, 255)),
+            "red" => Some(Rgba(255, 0, 0, 255)),
+            "green" => Some(Rgba(0, 128, 0, 255)),
+            "blue" => Some(Rgba(0, 0, 255, 255)),
+            "yellow" => Some(Rgba(255, 255, 0, 255)),
+            "orange" => Some(Rgba(255, 165, 0, 255)),
+            "purple" => Some(Rgba(128, 0, 128, 255)),
+            "gray" | "grey" => Some(Rgba(128, 128, 128, 255)),
+            _ => None,
+        }
+    }
+}
+
+// Colors a `classDef` sets; `None` leaves the renderer's default.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct NodeStyle {
+    pub fill: Option<Rgba>,
+    pub stroke: Option<Rgba>,
+    pub color: Option<Rgba>,
+}
+
+impl NodeStyle {
+    pub fn merge(&mut self, other: &NodeStyle) {
+        self.fill = other.fill.or(self.fill);
+        self.stroke = other.stroke.or(self.stroke);
+        self.color = other.color.or(self.color);
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Subgraph {
     pub id: String,
     pub title: Option<String>,
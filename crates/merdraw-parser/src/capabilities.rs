@@ -0,0 +1,28 @@
+use crate::ast::{Direction, EdgeArrow, EdgeStyle, NodeShape};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Capabilities {
+    pub headers: &'static [&'static str],
+    pub directions: &'static [Direction],
+    pub shapes: &'static [NodeShape],
+    pub edge_styles: &'static [EdgeStyle],
+    pub arrows: &'static [EdgeArrow],
+    pub directives: &'static [&'static str],
+    pub serde: bool,
+}
+
+// What this build of the parser accepts, so hosts can offer only supported syntax.
+pub const fn capabilities() -> Capabilities {
+    Capabilities {
+        headers: &["flowchart", "graph"],
+        directions: &Direction::ALL,
+        shapes: &NodeShape::ALL,
+        edge_styles: &EdgeStyle::ALL,
+        arrows: &EdgeArrow::ALL,
+        directives: &[
+            "subgraph", "end", "linkStyle", "rank same", "classDef", "class", "accTitle",
+            "accDescr",
+        ],
+        serde: cfg!(feature = "serde"),
+    }
+}
@@ -0,0 +1,185 @@
+use std::fmt::Write as _;
+
+use crate::ast::{Direction, EdgeLabelPosition, Graph, NodeShape, Rgba, Subgraph};
+
+// Emits canonical Mermaid for `graph`: the header, `accTitle`/`accDescr`, one declaration
+// per node, the edges (in source order, with a blank line wherever the source node
+// changes), `linkStyle` lines for explicit label positions, subgraph blocks, `rank same`
+// sets, and class definitions and assignments. Parsing the output yields a `Graph` equal
+// to the input.
+pub fn format_flowchart(graph: &Graph) -> String {
+    let direction = match graph.direction {
+        Direction::TB => "TB",
+        Direction::BT => "BT",
+        Direction::LR => "LR",
+        Direction::RL => "RL",
+    };
+    let mut out = format!("flowchart {direction}\n");
+    if let Some(title) = &graph.acc_title {
+        let _ = writeln!(out, "    accTitle: {title}");
+    }
+    match graph.acc_descr.as_deref() {
+        Some(descr) if descr.contains('\n') => {
+            let _ = writeln!(out, "    accDescr {{");
+            for line in descr.lines() {
+                let _ = writeln!(out, "        {line}");
+            }
+            let _ = writeln!(out, "    }}");
+        }
+        Some(descr) => {
+            let _ = writeln!(out, "    accDescr: {descr}");
+        }
+        None => {}
+    }
+
+    for node in &graph.nodes {
+        let id = format_id(&node.id);
+        let label = node.label.as_deref().unwrap_or_default();
+        let _ = match (&node.shape, node.label.as_deref()) {
+            (NodeShape::Plain, None) => writeln!(out, "    {id}"),
+            (shape, _) => {
+                let (open, close) = shape_delimiters(shape);
+                writeln!(out, "    {id}{open}{}{close}", format_label(label, close))
+            }
+        };
+    }
+
+    let mut previous_source: Option<&str> = None;
+    for edge in &graph.edges {
+        if previous_source != Some(edge.from.as_str()) {
+            out.push('\n');
+            previous_source = Some(edge.from.as_str());
+        }
+        let operator = edge.style.operator(&edge.arrow);
+        let label = edge
+            .label
+            .as_deref()
+            .map(|label| format!("|{}|", format_label(label, "|")))
+            .unwrap_or_default();
+        let _ = writeln!(
+            out,
+            "    {} {operator}{label} {}",
+            format_id(&edge.from),
+            format_id(&edge.to)
+        );
+    }
+
+    let mut positions: Vec<(&str, Vec<String>)> = Vec::new();
+    for (index, edge) in graph.edges.iter().enumerate() {
+        let keyword = match edge.label_position {
+            EdgeLabelPosition::Auto => continue,
+            EdgeLabelPosition::Above => "above",
+            EdgeLabelPosition::Below => "below",
+            EdgeLabelPosition::Inline => "inline",
+        };
+        match positions.iter_mut().find(|(existing, _)| *existing == keyword) {
+            Some((_, indices)) => indices.push(index.to_string()),
+            None => positions.push((keyword, vec![index.to_string()])),
+        }
+    }
+    if !positions.is_empty() {
+        out.push('\n');
+    }
+    for (keyword, indices) in positions {
+        let _ = writeln!(out, "    linkStyle {} label-position:{keyword}", indices.join(","));
+    }
+
+    for subgraph in &graph.subgraphs {
+        out.push('\n');
+        write_subgraph(&mut out, subgraph, 1);
+    }
+
+    if !graph.same_rank.is_empty() {
+        out.push('\n');
+    }
+    for set in &graph.same_rank {
+        let _ = writeln!(out, "    rank same {}", set.join(" "));
+    }
+
+    if !graph.class_defs.is_empty() || !graph.node_classes.is_empty() {
+        out.push('\n');
+    }
+    for (name, style) in &graph.class_defs {
+        let properties: Vec<String> = [
+            ("fill", style.fill),
+            ("stroke", style.stroke),
+            ("color", style.color),
+        ]
+        .into_iter()
+        .filter_map(|(key, color)| Some(format!("{key}:{}", format_color(color?))))
+        .collect();
+        let _ = writeln!(out, "    classDef {name} {}", properties.join(","));
+    }
+    for (id, classes) in &graph.node_classes {
+        for class in classes {
+            let _ = writeln!(out, "    class {id} {class}");
+        }
+    }
+    out
+}
+
+fn format_color(Rgba(r, g, b, a): Rgba) -> String {
+    if a == 255 {
+        format!("#{r:02x}{g:02x}{b:02x}")
+    } else {
+        format!("#{r:02x}{g:02x}{b:02x}{a:02x}")
+    }
+}
+
+fn write_subgraph(out: &mut String, subgraph: &Subgraph, depth: usize) {
+    let indent = "    ".repeat(depth);
+    let title = subgraph
+        .title
+        .as_deref()
+        .map(|title| format!(" [{}]", format_label(title, "]")))
+        .unwrap_or_default();
+    let _ = writeln!(out, "{indent}subgraph {}{title}", format_id(&subgraph.id));
+    for id in &subgraph.nodes {
+        let _ = writeln!(out, "{indent}    {}", format_id(id));
+    }
+    for child in &subgraph.subgraphs {
+        write_subgraph(out, child, depth + 1);
+    }
+    let _ = writeln!(out, "{indent}end");
+}
+
+// Plain nodes that somehow carry a label fall back to the bracket form.
+fn shape_delimiters(shape: &NodeShape) -> (&'static str, &'static str) {
+    match shape {
+        NodeShape::Plain | NodeShape::Bracket => ("[", "]"),
+        NodeShape::Round => ("(", ")"),
+        NodeShape::Circle => ("((", "))"),
+        NodeShape::Diamond => ("{", "}"),
+        NodeShape::Hexagon => ("{{", "}}"),
+        NodeShape::Stadium => ("([", "])"),
+        NodeShape::Subroutine => ("[[", "]]"),
+        NodeShape::Cylinder => ("[(", ")]"),
+    }
+}
+
+const KEYWORDS: [&str; 11] = [
+    "flowchart", "graph", "TB", "TD", "BT", "LR", "RL", "subgraph", "end", "linkStyle", "rank",
+];
+
+// Ids the lexer would split or read as a keyword are written as string literals.
+fn format_id(id: &str) -> String {
+    let bare = id.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+        && id.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+        && !KEYWORDS.contains(&id);
+    if bare { id.to_string() } else { format!("\"{}\"", encode_entities(id)) }
+}
+
+// Labels the lexer would cut short at `close`, or read as another shape or a quoted label,
+// are quoted.
+fn format_label(label: &str, close: &str) -> String {
+    let cut_short = format!("{label}{close}").find(close) != Some(label.len());
+    if cut_short || label.starts_with(['"', '(', '[', '{']) {
+        format!("\"{}\"", encode_entities(label))
+    } else {
+        label.to_string()
+    }
+}
+
+fn encode_entities(text: &str) -> String {
+    text.replace('#', "#35;").replace('"', "#quot;")
+}
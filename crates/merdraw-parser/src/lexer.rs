@@ -6,6 +6,7 @@ pub enum TokenKind {
     KwGraph,
     KwSubgraph,
     KwEnd,
+    KwLinkStyle,
     Direction(Direction),
     Ident(String),
     EdgeOp(EdgeStyle, EdgeArrow),
@@ -15,7 +16,12 @@ pub enum TokenKind {
     LabelCircle(String),
     LabelDiamond(String),
     LabelHexagon(String),
+    LabelStadium(String),
+    LabelSubroutine(String),
+    LabelCylinder(String),
     LabelPipe(String),
+    LabelInline(String),
+    Ampersand,
     Newline,
     Eof,
 }
@@ -31,18 +37,28 @@ pub struct Lexer<'a> {
     input: &'a str,
     pos: usize,
     len: usize,
+    pending: Option<Token>,
 }
 
 impl<'a> Lexer<'a> {
     pub fn new(input: &'a str) -> Self {
+        let pos = if input.starts_with('\u{FEFF}') {
+            '\u{FEFF}'.len_utf8()
+        } else {
+            0
+        };
         Self {
             input,
-            pos: 0,
+            pos,
             len: input.len(),
+            pending: None,
         }
     }
 
     pub fn next_token(&mut self) -> Result<Token, ParseError> {
+        if let Some(token) = self.pending.take() {
+            return Ok(token);
+        }
         let bytes = self.input.as_bytes();
         while self.pos < self.len {
             let b = bytes[self.pos];
@@ -59,7 +75,8 @@ impl<'a> Lexer<'a> {
                 continue;
             }
 
-            if b == b'\n' {
+            // `;` ends a statement just like a newline (`end;`, `A-->B;`).
+            if b == b'\n' || b == b';' {
                 let start = self.pos;
                 self.pos += 1;
                 return Ok(Token {
@@ -73,6 +90,16 @@ impl<'a> Lexer<'a> {
                 return Ok(token);
             }
 
+            if b == b'&' {
+                let start = self.pos;
+                self.pos += 1;
+                return Ok(Token {
+                    kind: TokenKind::Ampersand,
+                    start,
+                    end: self.pos,
+                });
+            }
+
             if b == b'[' {
                 return self.read_bracket_label();
             }
@@ -97,8 +124,10 @@ impl<'a> Lexer<'a> {
                 return self.read_ident();
             }
 
+            // Name the whole character; `b` may be the first byte of a multibyte one.
+            let ch = self.input[self.pos..].chars().next().unwrap_or(b as char);
             return Err(ParseError::new(
-                format!("unexpected character '{}'", b as char),
+                format!("unexpected character '{ch}'"),
                 self.pos,
             ));
         }
@@ -128,6 +157,7 @@ impl<'a> Lexer<'a> {
             "RL" => TokenKind::Direction(Direction::RL),
             "subgraph" => TokenKind::KwSubgraph,
             "end" => TokenKind::KwEnd,
+            "linkStyle" => TokenKind::KwLinkStyle,
             _ => TokenKind::Ident(text.to_string()),
         };
         Ok(Token {
@@ -137,8 +167,66 @@ impl<'a> Lexer<'a> {
         })
     }
 
+    pub fn at_directive_text(&self, allow_block: bool) -> bool {
+        let rest = self.input[self.pos..].trim_start_matches([' ', '\t']);
+        rest.starts_with(':') || (allow_block && rest.starts_with('{'))
+    }
+
+    // The text of `accTitle: ...` (to the end of the line) or a multi-line `accDescr { ... }`
+    // block, whose lines are trimmed and rejoined.
+    pub fn read_directive_text(&mut self, allow_block: bool) -> Result<String, ParseError> {
+        let bytes = self.input.as_bytes();
+        while self.pos < self.len && (bytes[self.pos] == b' ' || bytes[self.pos] == b'\t') {
+            self.pos += 1;
+        }
+        if allow_block && self.pos < self.len && bytes[self.pos] == b'{' {
+            let open = self.pos;
+            let Some(close_rel) = self.input[open + 1..].find('}') else {
+                return Err(ParseError::new("unterminated '{' block".to_string(), open));
+            };
+            let close = open + 1 + close_rel;
+            self.pos = close + 1;
+            let lines: Vec<&str> = self.input[open + 1..close]
+                .lines()
+                .map(str::trim)
+                .skip_while(|line| line.is_empty())
+                .collect();
+            let end = lines.iter().rposition(|line| !line.is_empty()).map_or(0, |at| at + 1);
+            return Ok(lines[..end].join("\n"));
+        }
+        self.pos += 1;
+        let start = self.pos;
+        let end = self.input[start..]
+            .find('\n')
+            .map(|rel| start + rel)
+            .unwrap_or(self.len);
+        self.pos = end;
+        Ok(self.input[start..end].trim().to_string())
+    }
+
+    pub fn read_rest_of_statement(&mut self) -> (String, usize) {
+        let start = self.pos;
+        let end = self.input[start..]
+            .find(['\n', ';'])
+            .map(|rel| start + rel)
+            .unwrap_or(self.len);
+        self.pos = end;
+        (self.input[start..end].to_string(), start)
+    }
+
     fn read_bracket_label(&mut self) -> Result<Token, ParseError> {
         let start = self.pos;
+        let bytes = self.input.as_bytes();
+        if self.pos + 1 < self.len && bytes[self.pos + 1] == b'[' {
+            return self.read_delimited_label("]]", TokenKind::LabelSubroutine, "'[[ ]]'");
+        }
+        if self.pos + 1 < self.len && bytes[self.pos + 1] == b'(' {
+            return self.read_delimited_label(")]", TokenKind::LabelCylinder, "'[( )]'");
+        }
+        if let Some(token) = self.read_quoted_label(1, "]", TokenKind::LabelBracket) {
+            return Ok(token);
+        }
+
         let search_start = self.pos + 1;
         if let Some(end_rel) = self.input[search_start..].find(']') {
             let end = search_start + end_rel;
@@ -154,7 +242,60 @@ impl<'a> Lexer<'a> {
         }
     }
 
+    // Reads a label opened by a two-byte delimiter at the current position.
+    fn read_delimited_label(
+        &mut self,
+        close: &str,
+        kind: fn(String) -> TokenKind,
+        name: &str,
+    ) -> Result<Token, ParseError> {
+        if let Some(token) = self.read_quoted_label(2, close, kind) {
+            return Ok(token);
+        }
+        let start = self.pos;
+        let search_start = self.pos + 2;
+        if let Some(end_rel) = self.input[search_start..].find(close) {
+            let end = search_start + end_rel;
+            let label = self.input[search_start..end].to_string();
+            self.pos = end + close.len();
+            Ok(Token {
+                kind: kind(label),
+                start,
+                end: self.pos,
+            })
+        } else {
+            Err(ParseError::new(format!("unterminated {name} label"), start))
+        }
+    }
+
+    // `open"text"close` reads `text` verbatim apart from the `#quot;` and `#35;` codes, so a
+    // label can hold its own closing delimiter. Without a closing quote right before `close`
+    // the label is read bare.
+    fn read_quoted_label(
+        &mut self,
+        open_len: usize,
+        close: &str,
+        kind: fn(String) -> TokenKind,
+    ) -> Option<Token> {
+        let start = self.pos;
+        let body = self.input.get(start + open_len..)?.strip_prefix('"')?;
+        let end_rel = body.find('"')?;
+        if !body[end_rel + 1..].starts_with(close) {
+            return None;
+        }
+        let text = decode_entities(&body[..end_rel]);
+        self.pos = start + open_len + 1 + end_rel + 1 + close.len();
+        Some(Token {
+            kind: kind(text),
+            start,
+            end: self.pos,
+        })
+    }
+
     fn read_pipe_label(&mut self) -> Result<Token, ParseError> {
+        if let Some(token) = self.read_quoted_label(1, "|", TokenKind::LabelPipe) {
+            return Ok(token);
+        }
         let start = self.pos;
         let search_start = self.pos + 1;
         if let Some(end_rel) = self.input[search_start..].find('|') {
@@ -175,37 +316,14 @@ impl<'a> Lexer<'a> {
         let start = self.pos;
         let bytes = self.input.as_bytes();
         if self.pos + 1 < self.len && bytes[self.pos + 1] == b'(' {
-            let search_start = self.pos + 2;
-            if let Some(end_rel) = self.input[search_start..].find("))") {
-                let end = search_start + end_rel;
-                let label = self.input[search_start..end].to_string();
-                self.pos = end + 2;
-                return Ok(Token {
-                    kind: TokenKind::LabelCircle(label),
-                    start,
-                    end: self.pos,
-                });
-            }
-            return Err(ParseError::new("unterminated '(( ))' label".to_string(), start));
+            return self.read_delimited_label("))", TokenKind::LabelCircle, "'(( ))'");
         }
 
-        if self.pos + 1 < self.len && bytes[self.pos + 1] == b'"' {
-            let search_start = self.pos + 2;
-            if let Some(end_rel) = self.input[search_start..].find('"') {
-                let end = search_start + end_rel;
-                let label = self.input[search_start..end].to_string();
-                let close = end + 1;
-                if close < self.len && bytes[close] == b')' {
-                    self.pos = close + 1;
-                    return Ok(Token {
-                        kind: TokenKind::LabelRound(label),
-                        start,
-                        end: self.pos,
-                    });
-                }
-                return Err(ParseError::new("expected ')' after round label".to_string(), close));
-            }
-            return Err(ParseError::new("unterminated round label".to_string(), start));
+        if self.pos + 1 < self.len && bytes[self.pos + 1] == b'[' {
+            return self.read_delimited_label("])", TokenKind::LabelStadium, "'([ ])'");
+        }
+        if let Some(token) = self.read_quoted_label(1, ")", TokenKind::LabelRound) {
+            return Ok(token);
         }
 
         let search_start = self.pos + 1;
@@ -227,7 +345,7 @@ impl<'a> Lexer<'a> {
         let search_start = self.pos + 1;
         if let Some(end_rel) = self.input[search_start..].find('"') {
             let end = search_start + end_rel;
-            let text = self.input[search_start..end].to_string();
+            let text = decode_entities(&self.input[search_start..end]);
             self.pos = end + 1;
             Ok(Token {
                 kind: TokenKind::StringLiteral(text),
@@ -243,18 +361,10 @@ impl<'a> Lexer<'a> {
         let start = self.pos;
         let bytes = self.input.as_bytes();
         if self.pos + 1 < self.len && bytes[self.pos + 1] == b'{' {
-            let search_start = self.pos + 2;
-            if let Some(end_rel) = self.input[search_start..].find("}}") {
-                let end = search_start + end_rel;
-                let label = self.input[search_start..end].to_string();
-                self.pos = end + 2;
-                return Ok(Token {
-                    kind: TokenKind::LabelHexagon(label),
-                    start,
-                    end: self.pos,
-                });
-            }
-            return Err(ParseError::new("unterminated '{{ }}' label".to_string(), start));
+            return self.read_delimited_label("}}", TokenKind::LabelHexagon, "'{{ }}'");
+        }
+        if let Some(token) = self.read_quoted_label(1, "}", TokenKind::LabelDiamond) {
+            return Ok(token);
         }
 
         let search_start = self.pos + 1;
@@ -277,78 +387,103 @@ impl<'a> Lexer<'a> {
             return Ok(None);
         }
         let start = self.pos;
-        match bytes[self.pos] {
-            b'-' => {
-                if self.pos + 3 < self.len
-                    && bytes[self.pos + 1] == b'.'
-                    && bytes[self.pos + 2] == b'-'
-                    && bytes[self.pos + 3] == b'>'
-                {
-                    self.pos += 4;
-                    return Ok(Some(Token {
-                        kind: TokenKind::EdgeOp(EdgeStyle::Dotted, EdgeArrow::Forward),
-                        start,
-                        end: self.pos,
-                    }));
-                }
-                if self.pos + 2 < self.len
-                    && bytes[self.pos + 1] == b'.'
-                    && bytes[self.pos + 2] == b'-'
-                {
-                    self.pos += 3;
-                    return Ok(Some(Token {
-                        kind: TokenKind::EdgeOp(EdgeStyle::Dotted, EdgeArrow::None),
-                        start,
-                        end: self.pos,
-                    }));
-                }
-                if self.pos + 2 < self.len
-                    && bytes[self.pos + 1] == b'-'
-                    && bytes[self.pos + 2] == b'>'
-                {
-                    self.pos += 3;
-                    return Ok(Some(Token {
-                        kind: TokenKind::EdgeOp(EdgeStyle::Solid, EdgeArrow::Forward),
-                        start,
-                        end: self.pos,
-                    }));
-                }
-                if self.pos + 2 < self.len && bytes[self.pos + 1] == b'-' && bytes[self.pos + 2] == b'-' {
-                    self.pos += 3;
-                    return Ok(Some(Token {
-                        kind: TokenKind::EdgeOp(EdgeStyle::Solid, EdgeArrow::None),
-                        start,
-                        end: self.pos,
-                    }));
-                }
-            }
-            b'=' => {
-                if self.pos + 2 < self.len
-                    && bytes[self.pos + 1] == b'='
-                    && bytes[self.pos + 2] == b'>'
-                {
-                    self.pos += 3;
-                    return Ok(Some(Token {
-                        kind: TokenKind::EdgeOp(EdgeStyle::Thick, EdgeArrow::Forward),
-                        start,
-                        end: self.pos,
-                    }));
-                }
-                if self.pos + 2 < self.len
-                    && bytes[self.pos + 1] == b'='
-                    && bytes[self.pos + 2] == b'='
-                {
-                    self.pos += 3;
-                    return Ok(Some(Token {
-                        kind: TokenKind::EdgeOp(EdgeStyle::Thick, EdgeArrow::None),
-                        start,
-                        end: self.pos,
-                    }));
+        let backward = bytes[start] == b'<';
+        let body = if backward { start + 1 } else { start };
+        let matches = |pattern: &[u8]| bytes[body..].starts_with(pattern);
+
+        if !backward && let Some((style, arrow, len)) = marker_edge_op(&bytes[body..]) {
+            self.pos = body + len;
+            return Ok(Some(Token {
+                kind: TokenKind::EdgeOp(style, arrow),
+                start,
+                end: self.pos,
+            }));
+        }
+
+        let (style, head, len) = if matches(b"-.->") {
+            (EdgeStyle::Dotted, true, 4)
+        } else if matches(b"-.-") {
+            (EdgeStyle::Dotted, false, 3)
+        } else if matches(b"-->") {
+            (EdgeStyle::Solid, true, 3)
+        } else if matches(b"---") {
+            (EdgeStyle::Solid, false, 3)
+        } else if backward && matches(b"--") {
+            (EdgeStyle::Solid, false, 2)
+        } else if matches(b"==>") {
+            (EdgeStyle::Thick, true, 3)
+        } else if matches(b"===") {
+            (EdgeStyle::Thick, false, 3)
+        } else if backward && matches(b"==") {
+            (EdgeStyle::Thick, false, 2)
+        } else if !backward && matches(b"~~~") {
+            let run = bytes[body..].iter().take_while(|&&b| b == b'~').count();
+            (EdgeStyle::Invisible, false, run)
+        } else if !backward && let Some(token) = self.read_inline_label_edge(start) {
+            return Ok(Some(token));
+        } else {
+            return Ok(None);
+        };
+
+        self.pos = body + len;
+        Ok(Some(Token {
+            kind: TokenKind::EdgeOp(style, edge_arrow(backward, head)),
+            start,
+            end: self.pos,
+        }))
+    }
+
+    // `A -- text --> B`, `A -. text .-> B`, `A == text ==> B` and their headless
+    // forms. The label is queued and returned as the token after the operator.
+    fn read_inline_label_edge(&mut self, start: usize) -> Option<Token> {
+        let bytes = self.input.as_bytes();
+        let opener = &bytes[start..];
+        let (style, close, none_tail) = if opener.starts_with(b"--") {
+            (EdgeStyle::Solid, "--", Some(b'-'))
+        } else if opener.starts_with(b"-.") {
+            (EdgeStyle::Dotted, ".-", None)
+        } else if opener.starts_with(b"==") {
+            (EdgeStyle::Thick, "==", Some(b'='))
+        } else {
+            return None;
+        };
+
+        let text_start = start + 2;
+        let line_end = self.input[text_start..]
+            .find(['\n', ';'])
+            .map(|rel| text_start + rel)
+            .unwrap_or(self.len);
+        let mut search = text_start;
+        while let Some(rel) = self.input[search..line_end].find(close) {
+            let at = search + rel;
+            let after = at + close.len();
+            let tail = bytes.get(after).copied().filter(|_| after < line_end);
+            let (head, end) = match tail {
+                Some(b'>') => (true, after + 1),
+                Some(b) if Some(b) == none_tail => (false, after + 1),
+                _ if none_tail.is_none() => (false, after),
+                _ => {
+                    search = at + 1;
+                    continue;
                 }
+            };
+            let text = self.input[text_start..at].trim();
+            if text.is_empty() {
+                return None;
             }
-            _ => {}
+            self.pending = Some(Token {
+                kind: TokenKind::LabelInline(text.to_string()),
+                start: text_start,
+                end: at,
+            });
+            self.pos = end;
+            return Some(Token {
+                kind: TokenKind::EdgeOp(style, edge_arrow(false, head)),
+                start,
+                end,
+            });
         }
-        Ok(None)
+        None
     }
 }
 
@@ -359,3 +494,56 @@ fn is_ident_start(b: u8) -> bool {
 fn is_ident_continue(b: u8) -> bool {
     is_ident_start(b) || (b'0'..=b'9').contains(&b)
 }
+
+// `--x`/`--o` and the dotted and thick forms. The marker letter must not run on into a
+// node id, so `A --oB` is left for the other edge forms.
+fn marker_edge_op(bytes: &[u8]) -> Option<(EdgeStyle, EdgeArrow, usize)> {
+    let (style, len) = if bytes.starts_with(b"-.-") {
+        (EdgeStyle::Dotted, 3)
+    } else if bytes.starts_with(b"--") {
+        (EdgeStyle::Solid, 2)
+    } else if bytes.starts_with(b"==") {
+        (EdgeStyle::Thick, 2)
+    } else {
+        return None;
+    };
+    let arrow = match bytes.get(len) {
+        Some(b'x') => EdgeArrow::Cross,
+        Some(b'o') => EdgeArrow::Circle,
+        _ => return None,
+    };
+    if bytes.get(len + 1).is_some_and(|&b| is_ident_continue(b)) {
+        return None;
+    }
+    Some((style, arrow, len + 1))
+}
+
+fn edge_arrow(backward: bool, head: bool) -> EdgeArrow {
+    match (backward, head) {
+        (false, false) => EdgeArrow::None,
+        (false, true) => EdgeArrow::Forward,
+        (true, false) => EdgeArrow::Backward,
+        (true, true) => EdgeArrow::Both,
+    }
+}
+
+// Replaces the `#quot;` and `#35;` entity codes with `"` and `#`.
+fn decode_entities(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(at) = rest.find('#') {
+        out.push_str(&rest[..at]);
+        rest = &rest[at..];
+        let (ch, len) = if rest.starts_with("#quot;") {
+            ('"', "#quot;".len())
+        } else if rest.starts_with("#35;") {
+            ('#', "#35;".len())
+        } else {
+            ('#', 1)
+        };
+        out.push(ch);
+        rest = &rest[len..];
+    }
+    out.push_str(rest);
+    out
+}
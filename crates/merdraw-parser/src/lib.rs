@@ -1,25 +1,66 @@
+mod alt_text;
 mod ast;
+mod capabilities;
+mod format;
 mod lexer;
 mod parser;
+mod transform;
 
-pub use ast::{Direction, Edge, EdgeArrow, EdgeStyle, Graph, Node, NodeShape, Subgraph};
-pub use parser::parse_flowchart;
+pub use alt_text::alt_text;
+pub use ast::{
+    char_width, label_lines, split_label_glyph, Direction, Edge, EdgeArrow, EdgeLabelPosition,
+    EdgeStyle, Graph, Node, NodeShape, NodeStyle, Rgba, Subgraph,
+};
+pub use capabilities::{capabilities, Capabilities};
+pub use format::format_flowchart;
+pub use parser::{parse_flowchart, DuplicateNodes, FlowchartParser};
+pub use transform::{collapse_chains, relabel, truncate_labels};
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct ParseError {
     pub message: String,
     pub offset: usize,
+    // 1-based; both stay 0 until `located` has seen the source.
+    pub line: usize,
+    pub column: usize,
 }
 
 impl ParseError {
     pub fn new(message: String, offset: usize) -> Self {
-        Self { message, offset }
+        Self {
+            message,
+            offset,
+            line: 0,
+            column: 0,
+        }
+    }
+
+    pub fn located(mut self, source: &str) -> Self {
+        (self.line, self.column) = self.line_col(source);
+        self
+    }
+
+    // 1-based line and column; the column counts characters, not bytes.
+    pub fn line_col(&self, source: &str) -> (usize, usize) {
+        let mut offset = self.offset.min(source.len());
+        while !source.is_char_boundary(offset) {
+            offset -= 1;
+        }
+        let before = &source[..offset];
+        let line = before.matches('\n').count() + 1;
+        let line_start = before.rfind('\n').map(|idx| idx + 1).unwrap_or(0);
+        let column = before[line_start..].chars().count() + 1;
+        (line, column)
     }
 }
 
 impl std::fmt::Display for ParseError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{} at byte {}", self.message, self.offset)
+        if self.line == 0 {
+            write!(f, "{} at byte {}", self.message, self.offset)
+        } else {
+            write!(f, "{} at {}:{}", self.message, self.line, self.column)
+        }
     }
 }
 
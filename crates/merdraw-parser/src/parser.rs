@@ -1,34 +1,100 @@
 use std::collections::HashMap;
 
-use crate::ast::{Direction, Edge, EdgeArrow, EdgeStyle, Graph, Node, NodeShape, Subgraph};
+use crate::ast::{
+    Direction, Edge, EdgeArrow, EdgeLabelPosition, EdgeStyle, Graph, Node, NodeShape, NodeStyle,
+    Rgba, Subgraph,
+};
 use crate::lexer::{Lexer, TokenKind};
 use crate::ParseError;
 
 pub fn parse_flowchart(input: &str) -> Result<Graph, ParseError> {
-    let parser = Parser::new(input)?;
-    parser.parse_flowchart()
+    FlowchartParser::new().parse(input)
 }
 
-struct Parser<'a> {
-    lexer: Lexer<'a>,
-    current: crate::lexer::Token,
-    graph: Graph,
+// What to do when a node id is given a second, different label or shape.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DuplicateNodes {
+    #[default]
+    Merge,
+    Error,
+}
+
+#[derive(Debug, Default)]
+pub struct FlowchartParser {
     nodes_by_id: HashMap<String, usize>,
+    duplicates: DuplicateNodes,
 }
 
-impl<'a> Parser<'a> {
-    fn new(input: &'a str) -> Result<Self, ParseError> {
+impl FlowchartParser {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_duplicate_nodes(mut self, duplicates: DuplicateNodes) -> Self {
+        self.duplicates = duplicates;
+        self
+    }
+
+    pub fn parse(&mut self, input: &str) -> Result<Graph, ParseError> {
+        let mut graph = Graph::new(Direction::TB);
+        self.parse_into(input, &mut graph)?;
+        Ok(graph)
+    }
+
+    // Reuses the id index and `graph`'s vectors so hosts that reparse often stop reallocating.
+    // On error `graph` holds whatever was parsed before the failure.
+    pub fn parse_into(&mut self, input: &str, graph: &mut Graph) -> Result<(), ParseError> {
+        graph.direction = Direction::TB;
+        graph.nodes.clear();
+        graph.edges.clear();
+        graph.subgraphs.clear();
+        graph.same_rank.clear();
+        graph.class_defs.clear();
+        graph.node_classes.clear();
+        graph.acc_title = None;
+        graph.acc_descr = None;
+        self.nodes_by_id.clear();
+
+        // Lex the first token before taking the buffers, so an early error leaves them here.
         let mut lexer = Lexer::new(input);
-        let first = lexer.next_token()?;
-        Ok(Self {
+        let first = lexer.next_token().map_err(|err| err.located(input))?;
+        let mut parser = Parser {
             lexer,
             current: first,
-            graph: Graph::new(Direction::TB),
-            nodes_by_id: HashMap::new(),
-        })
+            graph: std::mem::replace(graph, Graph::new(Direction::TB)),
+            nodes_by_id: std::mem::take(&mut self.nodes_by_id),
+            duplicates: self.duplicates,
+            link_styles: Vec::new(),
+        };
+        let result = parser.parse_flowchart();
+        *graph = parser.graph;
+        self.nodes_by_id = parser.nodes_by_id;
+        result.map_err(|err| err.located(input))
     }
+}
 
-    fn parse_flowchart(mut self) -> Result<Graph, ParseError> {
+struct Parser<'a> {
+    lexer: Lexer<'a>,
+    current: crate::lexer::Token,
+    graph: Graph,
+    nodes_by_id: HashMap<String, usize>,
+    duplicates: DuplicateNodes,
+    link_styles: Vec<LinkStyle>,
+}
+
+// A `linkStyle` statement, kept until the whole graph is parsed: it may name edges defined
+// after it. `indices` is `None` for `default`.
+struct LinkStyle {
+    indices: Option<Vec<usize>>,
+    position: Option<EdgeLabelPosition>,
+    offset: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn parse_flowchart(&mut self) -> Result<(), ParseError> {
+        while self.current.kind == TokenKind::Newline {
+            self.advance()?;
+        }
         self.expect_header()?;
         self.advance()?;
 
@@ -49,6 +115,16 @@ impl<'a> Parser<'a> {
                 TokenKind::KwEnd => {
                     return Err(self.error_here("unexpected 'end' outside subgraph"));
                 }
+                TokenKind::KwLinkStyle => {
+                    self.parse_link_style()?;
+                }
+                // Only with a `:` (or `accDescr {`) after them; otherwise they are node ids.
+                TokenKind::Ident(id)
+                    if (id == "accTitle" || id == "accDescr")
+                        && self.lexer.at_directive_text(id == "accDescr") =>
+                {
+                    self.parse_accessibility(&id)?;
+                }
                 TokenKind::Ident(id) | TokenKind::StringLiteral(id) => {
                     self.advance()?;
                     let mut subgraph = None;
@@ -60,10 +136,24 @@ impl<'a> Parser<'a> {
             }
         }
 
-        Ok(self.graph)
+        self.apply_link_styles()
     }
 
     fn parse_statement(&mut self, id: String, subgraph: &mut Option<&mut Subgraph>) -> Result<(), ParseError> {
+        // `rank` stays usable as a node id; only `rank same` starts a directive.
+        if id == "rank" && self.current.kind == TokenKind::Ident("same".to_string()) {
+            return self.parse_same_rank();
+        }
+        // Likewise `classDef`/`class` are only statements when a name follows.
+        if let TokenKind::Ident(name) = self.current.kind.clone() {
+            if id == "classDef" {
+                return self.parse_class_def(name);
+            }
+            if id == "class" {
+                return self.parse_class_assignment(name);
+            }
+        }
+
         if let Some(current) = subgraph.as_deref_mut() {
             current.add_node(&id);
         }
@@ -71,18 +161,25 @@ impl<'a> Parser<'a> {
         match self.current.kind.clone() {
             TokenKind::EdgeOp(style, arrow) => {
                 let subgraph_ref = subgraph.as_deref_mut();
-                self.parse_edge_chain(id, style, arrow, subgraph_ref)
+                self.parse_edge_chain(vec![id], style, arrow, subgraph_ref)
+            }
+            TokenKind::Ampersand => {
+                self.upsert_node(id.clone(), None, NodeShape::Plain);
+                self.parse_edge_after_node(id, subgraph)
             }
             TokenKind::LabelBracket(_)
             | TokenKind::LabelRound(_)
             | TokenKind::LabelCircle(_)
             | TokenKind::LabelDiamond(_)
-            | TokenKind::LabelHexagon(_) => {
+            | TokenKind::LabelHexagon(_)
+            | TokenKind::LabelStadium(_)
+            | TokenKind::LabelSubroutine(_)
+            | TokenKind::LabelCylinder(_) => {
                 self.consume_node_label(&id)?;
                 if let Some(current) = subgraph.as_deref_mut() {
                     current.add_node(&id);
                 }
-                self.parse_edge_after_labeled_node(id, subgraph)
+                self.parse_edge_after_node(id, subgraph)
             }
             TokenKind::Newline | TokenKind::Eof => {
                 self.upsert_node(id, None, NodeShape::Plain);
@@ -92,24 +189,53 @@ impl<'a> Parser<'a> {
         }
     }
 
-    fn parse_edge_after_labeled_node(
+    fn parse_edge_after_node(
         &mut self,
         from: String,
         subgraph: &mut Option<&mut Subgraph>,
     ) -> Result<(), ParseError> {
+        let sources = self.parse_node_group(from, subgraph.as_deref_mut())?;
         match self.current.kind.clone() {
             TokenKind::EdgeOp(style, arrow) => {
                 let subgraph_ref = subgraph.as_deref_mut();
-                self.parse_edge_chain(from, style, arrow, subgraph_ref)
+                self.parse_edge_chain(sources, style, arrow, subgraph_ref)
             }
             TokenKind::Newline | TokenKind::Eof => Ok(()),
             _ => Err(self.error_here("expected edge or end of line")),
         }
     }
 
+    // Reads the `& B & C` tail after `first`, declaring each node (with its label, if any).
+    fn parse_node_group(
+        &mut self,
+        first: String,
+        mut subgraph: Option<&mut Subgraph>,
+    ) -> Result<Vec<String>, ParseError> {
+        let mut ids = vec![first];
+        while self.current.kind == TokenKind::Ampersand {
+            self.advance()?;
+            let id = match self.current.kind.clone() {
+                TokenKind::Ident(id) | TokenKind::StringLiteral(id) => {
+                    self.advance()?;
+                    id
+                }
+                _ => return Err(self.error_here("expected node id after '&'")),
+            };
+            self.upsert_node(id.clone(), None, NodeShape::Plain);
+            self.consume_node_label(&id)?;
+            if let Some(current) = subgraph.as_deref_mut() {
+                current.add_node(&id);
+            }
+            ids.push(id);
+        }
+        Ok(ids)
+    }
+
+    // Each step links every source to every target (`A & B --> C & D` is four edges);
+    // the targets become the sources of the next step.
     fn parse_edge_chain(
         &mut self,
-        mut from: String,
+        mut sources: Vec<String>,
         mut style: EdgeStyle,
         mut arrow: EdgeArrow,
         mut subgraph: Option<&mut Subgraph>,
@@ -117,7 +243,9 @@ impl<'a> Parser<'a> {
         loop {
             self.advance()?;
             let mut label = None;
-            if let TokenKind::LabelPipe(text) = self.current.kind.clone() {
+            if let TokenKind::LabelPipe(text) | TokenKind::LabelInline(text) =
+                self.current.kind.clone()
+            {
                 label = Some(text);
                 self.advance()?;
             }
@@ -130,24 +258,34 @@ impl<'a> Parser<'a> {
                 _ => return Err(self.error_here("expected destination node id")),
             };
 
-            self.upsert_node(from.clone(), None, NodeShape::Plain);
+            for from in &sources {
+                self.upsert_node(from.clone(), None, NodeShape::Plain);
+            }
             self.upsert_node(to.clone(), None, NodeShape::Plain);
             self.consume_node_label(&to)?;
             if let Some(current) = subgraph.as_deref_mut() {
-                current.add_node(&from);
+                for from in &sources {
+                    current.add_node(from);
+                }
                 current.add_node(&to);
             }
-            self.graph.edges.push(Edge {
-                from: from.clone(),
-                to: to.clone(),
-                label,
-                style,
-                arrow,
-            });
+            let targets = self.parse_node_group(to, subgraph.as_deref_mut())?;
+            for from in &sources {
+                for to in &targets {
+                    self.graph.edges.push(Edge {
+                        from: from.clone(),
+                        to: to.clone(),
+                        label: label.clone(),
+                        style: style.clone(),
+                        arrow: arrow.clone(),
+                        label_position: EdgeLabelPosition::Auto,
+                    });
+                }
+            }
 
             match self.current.kind.clone() {
                 TokenKind::EdgeOp(next_style, next_arrow) => {
-                    from = to;
+                    sources = targets;
                     style = next_style;
                     arrow = next_arrow;
                 }
@@ -174,7 +312,10 @@ impl<'a> Parser<'a> {
             | TokenKind::LabelRound(label)
             | TokenKind::LabelCircle(label)
             | TokenKind::LabelDiamond(label)
-            | TokenKind::LabelHexagon(label) => {
+            | TokenKind::LabelHexagon(label)
+            | TokenKind::LabelStadium(label)
+            | TokenKind::LabelSubroutine(label)
+            | TokenKind::LabelCylinder(label) => {
                 title = Some(label);
                 self.advance()?;
             }
@@ -195,6 +336,9 @@ impl<'a> Parser<'a> {
                     self.advance()?;
                     return Ok(subgraph);
                 }
+                TokenKind::KwLinkStyle => {
+                    self.parse_link_style()?;
+                }
                 TokenKind::Ident(id) | TokenKind::StringLiteral(id) => {
                     self.advance()?;
                     let mut current = Some(&mut subgraph);
@@ -209,9 +353,152 @@ impl<'a> Parser<'a> {
         Err(self.error_here("expected 'end' to close subgraph"))
     }
 
+    fn parse_same_rank(&mut self) -> Result<(), ParseError> {
+        let (line, line_start) = self.lexer.read_rest_of_statement();
+        self.advance()?;
+
+        let ids: Vec<String> = line
+            .split(|c: char| c.is_whitespace() || c == ',')
+            .filter(|part| !part.is_empty())
+            .map(str::to_string)
+            .collect();
+        if ids.is_empty() {
+            return Err(ParseError::new(
+                "expected node ids after 'rank same'".to_string(),
+                line_start,
+            ));
+        }
+        for id in &ids {
+            self.upsert_node(id.clone(), None, NodeShape::Plain);
+        }
+        self.graph.same_rank.push(ids);
+        Ok(())
+    }
+
+    fn parse_class_def(&mut self, name: String) -> Result<(), ParseError> {
+        let (line, line_start) = self.lexer.read_rest_of_statement();
+        self.advance()?;
+
+        let mut style = NodeStyle::default();
+        for property in split_properties(&line) {
+            let Some((key, value)) = property.split_once(':') else {
+                continue;
+            };
+            let slot = match key.trim() {
+                "fill" => &mut style.fill,
+                "stroke" => &mut style.stroke,
+                "color" => &mut style.color,
+                _ => continue,
+            };
+            let value = value.trim();
+            *slot = Some(Rgba::parse(value).ok_or_else(|| {
+                ParseError::new(format!("invalid color '{value}'"), line_start)
+            })?);
+        }
+        self.graph.class_defs.insert(name, style);
+        Ok(())
+    }
+
+    // `class A,B important`: the first id was already lexed; the rest of the line holds
+    // any further ids and the class name last.
+    fn parse_class_assignment(&mut self, first: String) -> Result<(), ParseError> {
+        let (line, line_start) = self.lexer.read_rest_of_statement();
+        self.advance()?;
+
+        let text = format!("{first}{line}");
+        let mut words: Vec<&str> = text.split_whitespace().collect();
+        let class = match words.pop() {
+            Some(class) if !words.is_empty() => class.to_string(),
+            _ => {
+                return Err(ParseError::new(
+                    "expected node ids and a class name after 'class'".to_string(),
+                    line_start,
+                ));
+            }
+        };
+        for id in words.join(" ").split(',').map(str::trim).filter(|id| !id.is_empty()) {
+            let classes = self.graph.node_classes.entry(id.to_string()).or_default();
+            if !classes.contains(&class) {
+                classes.push(class.clone());
+            }
+        }
+        Ok(())
+    }
+
+    fn parse_accessibility(&mut self, keyword: &str) -> Result<(), ParseError> {
+        let text = self.lexer.read_directive_text(keyword == "accDescr")?;
+        self.advance()?;
+        if keyword == "accTitle" {
+            self.graph.acc_title = Some(text);
+        } else {
+            self.graph.acc_descr = Some(text);
+        }
+        Ok(())
+    }
+
+    fn parse_link_style(&mut self) -> Result<(), ParseError> {
+        let (line, line_start) = self.lexer.read_rest_of_statement();
+        self.advance()?;
+
+        let trimmed = line.trim_start();
+        let offset = line_start + (line.len() - trimmed.len());
+        let (targets, styles) = trimmed
+            .split_once(char::is_whitespace)
+            .ok_or_else(|| ParseError::new("expected edge index and style".to_string(), offset))?;
+
+        let indices = if targets == "default" {
+            None
+        } else {
+            let mut indices = Vec::new();
+            for part in targets.split(',') {
+                let index = part.trim().parse::<usize>().map_err(|_| {
+                    ParseError::new(format!("invalid linkStyle index '{}'", part.trim()), offset)
+                })?;
+                indices.push(index);
+            }
+            Some(indices)
+        };
+
+        let mut position = None;
+        for property in styles.trim().split(',') {
+            let Some((key, value)) = property.split_once(':') else {
+                continue;
+            };
+            if key.trim() != "label-position" {
+                continue;
+            }
+            let value = value.trim();
+            position = Some(EdgeLabelPosition::from_keyword(value).ok_or_else(|| {
+                ParseError::new(format!("unknown label-position '{value}'"), offset)
+            })?);
+        }
+        self.link_styles.push(LinkStyle { indices, position, offset });
+        Ok(())
+    }
+
+    // Runs once the edge list is complete, in statement order so later lines win.
+    fn apply_link_styles(&mut self) -> Result<(), ParseError> {
+        let edge_count = self.graph.edges.len();
+        for style in std::mem::take(&mut self.link_styles) {
+            let indices = style.indices.unwrap_or_else(|| (0..edge_count).collect());
+            if let Some(&index) = indices.iter().find(|&&index| index >= edge_count) {
+                return Err(ParseError::new(
+                    format!("linkStyle index {index} is out of range"),
+                    style.offset,
+                ));
+            }
+            if let Some(position) = style.position {
+                for index in indices {
+                    self.graph.edges[index].label_position = position.clone();
+                }
+            }
+        }
+        Ok(())
+    }
+
     fn upsert_node(&mut self, id: String, label: Option<String>, shape: NodeShape) {
         if let Some(&idx) = self.nodes_by_id.get(&id) {
-            if label.is_some() {
+            if label.is_some() || shape != NodeShape::Plain {
                 let node = &mut self.graph.nodes[idx];
                 node.label = label;
                 node.shape = shape;
@@ -238,29 +525,33 @@ impl<'a> Parser<'a> {
     }
 
     fn consume_node_label(&mut self, id: &str) -> Result<(), ParseError> {
-        match self.current.kind.clone() {
-            TokenKind::LabelBracket(label) => {
-                self.advance()?;
-                self.upsert_node(id.to_string(), Some(label), NodeShape::Bracket);
-            }
-            TokenKind::LabelRound(label) => {
-                self.advance()?;
-                self.upsert_node(id.to_string(), Some(label), NodeShape::Round);
-            }
-            TokenKind::LabelCircle(label) => {
-                self.advance()?;
-                self.upsert_node(id.to_string(), Some(label), NodeShape::Circle);
-            }
-            TokenKind::LabelDiamond(label) => {
-                self.advance()?;
-                self.upsert_node(id.to_string(), Some(label), NodeShape::Diamond);
-            }
-            TokenKind::LabelHexagon(label) => {
-                self.advance()?;
-                self.upsert_node(id.to_string(), Some(label), NodeShape::Hexagon);
+        let (label, shape) = match self.current.kind.clone() {
+            TokenKind::LabelBracket(label) => (label, NodeShape::Bracket),
+            TokenKind::LabelRound(label) => (label, NodeShape::Round),
+            TokenKind::LabelCircle(label) => (label, NodeShape::Circle),
+            TokenKind::LabelDiamond(label) => (label, NodeShape::Diamond),
+            TokenKind::LabelHexagon(label) => (label, NodeShape::Hexagon),
+            TokenKind::LabelStadium(label) => (label, NodeShape::Stadium),
+            TokenKind::LabelSubroutine(label) => (label, NodeShape::Subroutine),
+            TokenKind::LabelCylinder(label) => (label, NodeShape::Cylinder),
+            _ => return Ok(()),
+        };
+        let start = self.current.start;
+        self.advance()?;
+        let label = shaped_label(label);
+        if self.duplicates == DuplicateNodes::Error
+            && let Some(&idx) = self.nodes_by_id.get(id)
+        {
+            let existing = &self.graph.nodes[idx];
+            let defined = existing.label.is_some() || existing.shape != NodeShape::Plain;
+            if defined && (existing.label != label || existing.shape != shape) {
+                return Err(ParseError::new(
+                    format!("node '{id}' is redefined with a different label or shape"),
+                    start,
+                ));
             }
-            _ => {}
         }
+        self.upsert_node(id.to_string(), label, shape);
         Ok(())
     }
 
@@ -268,3 +559,31 @@ impl<'a> Parser<'a> {
         ParseError::new(message.to_string(), self.current.start)
     }
 }
+
+fn shaped_label(label: String) -> Option<String> {
+    if label.trim().is_empty() {
+        None
+    } else {
+        Some(label)
+    }
+}
+
+// Splits `fill:rgb(1, 2, 3),stroke:#333` on the commas outside parentheses.
+fn split_properties(line: &str) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut depth = 0usize;
+    let mut start = 0;
+    for (idx, c) in line.char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' => depth = depth.saturating_sub(1),
+            ',' if depth == 0 => {
+                parts.push(&line[start..idx]);
+                start = idx + 1;
+            }
+            _ => {}
+        }
+    }
+    parts.push(&line[start..]);
+    parts
+}
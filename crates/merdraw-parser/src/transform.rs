@@ -0,0 +1,198 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::ast::{label_lines, Edge, Graph, Node, NodeShape, Subgraph};
+
+// Replaces every run of at least `min_len` pass-through nodes (exactly one edge in and
+// one out) with a single summary node; the nodes on either side of the run stay. Runs
+// are cut where they cross a subgraph boundary.
+pub fn collapse_chains(graph: &Graph, min_len: usize) -> Graph {
+    let mut indegree: HashMap<&str, usize> = HashMap::new();
+    let mut outgoing: HashMap<&str, Vec<usize>> = HashMap::new();
+    for (index, edge) in graph.edges.iter().enumerate() {
+        *indegree.entry(edge.to.as_str()).or_default() += 1;
+        outgoing.entry(edge.from.as_str()).or_default().push(index);
+    }
+    let mut groups = HashMap::new();
+    collect_group_paths(&graph.subgraphs, "", &mut groups);
+    let group_of = |id: &str| groups.get(id).cloned().unwrap_or_default();
+    let pass_through = |id: &str| {
+        indegree.get(id).copied() == Some(1) && outgoing.get(id).map(Vec::len) == Some(1)
+    };
+
+    let mut taken: HashSet<String> = graph.nodes.iter().map(|node| node.id.clone()).collect();
+    let mut replaced: HashMap<String, String> = HashMap::new();
+    let mut summaries: Vec<(String, Node)> = Vec::new();
+    let mut dropped_edges: HashSet<usize> = HashSet::new();
+    for edge in &graph.edges {
+        let starts_run = !pass_through(&edge.from) || group_of(&edge.from) != group_of(&edge.to);
+        if !starts_run || !pass_through(&edge.to) {
+            continue;
+        }
+        let mut run: Vec<&str> = vec![edge.to.as_str()];
+        let mut inner_edges = Vec::new();
+        loop {
+            let current = run[run.len() - 1];
+            let next_edge = outgoing[current][0];
+            let next = graph.edges[next_edge].to.as_str();
+            if !pass_through(next) || run.contains(&next) || group_of(next) != group_of(run[0]) {
+                break;
+            }
+            inner_edges.push(next_edge);
+            run.push(next);
+        }
+        if run.len() < min_len.max(1) {
+            continue;
+        }
+
+        let (first, last) = (run[0], run[run.len() - 1]);
+        let mut id = format!("{first}__{last}");
+        while taken.contains(&id) {
+            id.push('_');
+        }
+        taken.insert(id.clone());
+        let name = |node_id: &str| {
+            graph
+                .nodes
+                .iter()
+                .find(|node| node.id == node_id)
+                .and_then(|node| node.label.clone())
+                .unwrap_or_else(|| node_id.to_string())
+        };
+        let label = format!("{} → … → {} ({} steps)", name(first), name(last), run.len());
+        for node_id in &run {
+            replaced.insert(node_id.to_string(), id.clone());
+        }
+        dropped_edges.extend(inner_edges);
+        summaries.push((
+            first.to_string(),
+            Node {
+                id: id.clone(),
+                label: Some(label),
+                shape: NodeShape::Subroutine,
+            },
+        ));
+    }
+
+    let mut nodes = Vec::new();
+    for node in &graph.nodes {
+        match summaries.iter().find(|(first, _)| *first == node.id) {
+            Some((_, summary)) => nodes.push(summary.clone()),
+            None if replaced.contains_key(&node.id) => {}
+            None => nodes.push(node.clone()),
+        }
+    }
+    let rename = |id: &String| replaced.get(id).unwrap_or(id).clone();
+    let edges = graph
+        .edges
+        .iter()
+        .enumerate()
+        .filter(|(index, _)| !dropped_edges.contains(index))
+        .map(|(_, edge)| Edge {
+            from: rename(&edge.from),
+            to: rename(&edge.to),
+            ..edge.clone()
+        })
+        .collect();
+    let subgraphs = graph
+        .subgraphs
+        .iter()
+        .map(|subgraph| rename_subgraph_nodes(subgraph, &replaced))
+        .collect();
+    let same_rank = graph
+        .same_rank
+        .iter()
+        .map(|set| {
+            let mut renamed: Vec<String> = Vec::new();
+            for id in set {
+                let id = rename(id);
+                if !renamed.contains(&id) {
+                    renamed.push(id);
+                }
+            }
+            renamed
+        })
+        .filter(|set| set.len() > 1)
+        .collect();
+    let node_classes = graph
+        .node_classes
+        .iter()
+        .filter(|(id, _)| !replaced.contains_key(*id))
+        .map(|(id, classes)| (id.clone(), classes.clone()))
+        .collect();
+    Graph {
+        nodes,
+        edges,
+        subgraphs,
+        same_rank,
+        node_classes,
+        ..graph.clone()
+    }
+}
+
+// Returns a copy of `graph` whose node and edge labels have been passed through `map`;
+// `None` keeps the text. An unlabeled node offers its id, so ids can be renamed for
+// display without touching the edges that refer to them.
+pub fn relabel<F>(graph: &Graph, mut map: F) -> Graph
+where
+    F: FnMut(&str) -> Option<String>,
+{
+    let mut relabeled = graph.clone();
+    for node in &mut relabeled.nodes {
+        let text = node.label.as_deref().unwrap_or(node.id.as_str());
+        if let Some(label) = map(text) {
+            node.label = Some(label);
+        }
+    }
+    for edge in &mut relabeled.edges {
+        if let Some(label) = edge.label.as_deref().and_then(&mut map) {
+            edge.label = Some(label);
+        }
+    }
+    relabeled
+}
+
+// Shortens every node and edge label line longer than `max_chars` characters to
+// `max_chars - 1` of them plus `…`. Done on the graph, before layout, so every backend
+// sizes and draws the same text.
+pub fn truncate_labels(graph: &Graph, max_chars: usize) -> Graph {
+    relabel(graph, |text| {
+        let lines = label_lines(text);
+        if lines.iter().all(|line| line.chars().count() <= max_chars) {
+            return None;
+        }
+        let truncated: Vec<String> = lines
+            .into_iter()
+            .map(|line| {
+                if line.chars().count() <= max_chars {
+                    return line.to_string();
+                }
+                let kept: String = line.chars().take(max_chars.saturating_sub(1)).collect();
+                format!("{}…", kept.trim_end())
+            })
+            .collect();
+        Some(truncated.join("<br>"))
+    })
+}
+
+fn collect_group_paths(subgraphs: &[Subgraph], prefix: &str, out: &mut HashMap<String, String>) {
+    for subgraph in subgraphs {
+        let path = format!("{prefix}/{}", subgraph.id);
+        for id in &subgraph.nodes {
+            out.insert(id.clone(), path.clone());
+        }
+        collect_group_paths(&subgraph.subgraphs, &path, out);
+    }
+}
+
+fn rename_subgraph_nodes(subgraph: &Subgraph, replaced: &HashMap<String, String>) -> Subgraph {
+    let mut renamed = Subgraph::new(subgraph.id.clone(), subgraph.title.clone());
+    for id in &subgraph.nodes {
+        renamed.add_node(replaced.get(id).unwrap_or(id));
+    }
+    renamed.subgraphs = subgraph
+        .subgraphs
+        .iter()
+        .map(|child| rename_subgraph_nodes(child, replaced))
+        .collect();
+    renamed
+}
@@ -1,4 +1,8 @@
-use merdraw_parser::{parse_flowchart, Direction, EdgeArrow, EdgeStyle, NodeShape};
+use merdraw_parser::{
+    alt_text, capabilities, char_width, collapse_chains, format_flowchart, parse_flowchart,
+    split_label_glyph, Direction, DuplicateNodes, EdgeArrow, EdgeLabelPosition, EdgeStyle,
+    FlowchartParser, NodeShape, NodeStyle, Rgba,
+};
 
 #[test]
 fn parses_minimal_flowchart() {
@@ -38,6 +42,43 @@ fn parses_nodes_with_shapes() {
     assert_eq!(f.label.as_deref(), Some("Hex"));
 }
 
+#[test]
+fn parses_stadium_subroutine_and_cylinder_shapes() {
+    let input = "flowchart LR\nA([Stadium])\nB[[Sub]]\nC[(Database)]\nA-->B-->C\n";
+    let graph = parse_flowchart(input).expect("parse failed");
+    assert_eq!(graph.edges.len(), 2);
+
+    let a = graph.nodes.iter().find(|n| n.id == "A").unwrap();
+    assert_eq!(a.shape, NodeShape::Stadium);
+    assert_eq!(a.label.as_deref(), Some("Stadium"));
+
+    let b = graph.nodes.iter().find(|n| n.id == "B").unwrap();
+    assert_eq!(b.shape, NodeShape::Subroutine);
+    assert_eq!(b.label.as_deref(), Some("Sub"));
+
+    let c = graph.nodes.iter().find(|n| n.id == "C").unwrap();
+    assert_eq!(c.shape, NodeShape::Cylinder);
+    assert_eq!(c.label.as_deref(), Some("Database"));
+}
+
+#[test]
+fn empty_shaped_labels_fall_back_to_id() {
+    let input = "flowchart TB\nA[]\nB()\nC-->D\nD{ }\n";
+    let graph = parse_flowchart(input).expect("parse failed");
+
+    let a = graph.nodes.iter().find(|n| n.id == "A").unwrap();
+    assert_eq!(a.shape, NodeShape::Bracket);
+    assert!(a.label.is_none());
+
+    let b = graph.nodes.iter().find(|n| n.id == "B").unwrap();
+    assert_eq!(b.shape, NodeShape::Round);
+    assert!(b.label.is_none());
+
+    let d = graph.nodes.iter().find(|n| n.id == "D").unwrap();
+    assert_eq!(d.shape, NodeShape::Diamond);
+    assert!(d.label.is_none());
+}
+
 #[test]
 fn parses_graph_alias_and_td_direction() {
     let input = "graph TD\nA-->B\n";
@@ -100,6 +141,21 @@ fn ignores_comments_and_blank_lines() {
     assert_eq!(graph.edges.len(), 1);
 }
 
+#[test]
+fn skips_leading_byte_order_mark() {
+    let graph = parse_flowchart("\u{FEFF}flowchart LR\nA-->B\n").expect("parse failed");
+    assert_eq!(graph.direction, Direction::LR);
+    assert_eq!(graph.edges.len(), 1);
+}
+
+#[test]
+fn skips_blank_lines_before_header() {
+    let input = "\n  \n%% leading comment\n\tflowchart TB\nA-->B\n";
+    let graph = parse_flowchart(input).expect("parse failed");
+    assert_eq!(graph.direction, Direction::TB);
+    assert_eq!(graph.edges.len(), 1);
+}
+
 #[test]
 fn errors_on_missing_header() {
     let err = parse_flowchart("A-->B").unwrap_err();
@@ -164,3 +220,398 @@ fn allows_quoted_node_ids() {
     assert!(graph.nodes.iter().any(|n| n.id == "Node A"));
     assert!(graph.nodes.iter().any(|n| n.id == "Node B"));
 }
+
+#[test]
+fn link_style_sets_label_position() {
+    let input = "flowchart LR\nA -->|yes| B\nB -->|no| C\nlinkStyle 1 stroke:#f00,label-position:below\n";
+    let graph = parse_flowchart(input).expect("parse failed");
+    assert_eq!(graph.edges[0].label_position, EdgeLabelPosition::Auto);
+    assert_eq!(graph.edges[1].label_position, EdgeLabelPosition::Below);
+
+    let err = parse_flowchart("flowchart LR\nA-->B\nlinkStyle 3 label-position:below\n")
+        .expect_err("expected out of range index");
+    assert!(err.message.contains("out of range"));
+}
+
+#[test]
+fn link_style_may_name_edges_defined_after_it() {
+    let input = "flowchart LR\nlinkStyle 1 label-position:above\nA -->|yes| B\nB -->|no| C\n";
+    let graph = parse_flowchart(input).expect("parse failed");
+    assert_eq!(graph.edges[0].label_position, EdgeLabelPosition::Auto);
+    assert_eq!(graph.edges[1].label_position, EdgeLabelPosition::Above);
+
+    let input = "flowchart LR\nlinkStyle default label-position:below\nA-->B\nB-->C\n";
+    let graph = parse_flowchart(input).expect("parse failed");
+    assert!(graph.edges.iter().all(|edge| edge.label_position == EdgeLabelPosition::Below));
+
+    let err = parse_flowchart("flowchart LR\nlinkStyle 2 label-position:below\nA-->B\nB-->C\n")
+        .expect_err("expected out of range index");
+    assert!(err.message.contains("linkStyle index 2 is out of range"), "{}", err.message);
+    assert_eq!(err.line, 2);
+}
+
+#[test]
+fn keywords_only_match_whole_identifiers() {
+    let input = "flowchart TB\nsubgraph subgraphs \"Quoted Title\"\nendpoint-->ending\nend\nsubgraph_node-->endpoint\n";
+    let graph = parse_flowchart(input).expect("parse failed");
+    assert_eq!(graph.subgraphs.len(), 1);
+    let group = &graph.subgraphs[0];
+    assert_eq!(group.id, "subgraphs");
+    assert_eq!(group.title.as_deref(), Some("Quoted Title"));
+    assert_eq!(group.nodes, vec!["endpoint".to_string(), "ending".to_string()]);
+    assert!(graph.nodes.iter().any(|n| n.id == "subgraph_node"));
+    assert_eq!(graph.edges.len(), 2);
+}
+
+#[test]
+fn semicolons_terminate_statements() {
+    let input = "flowchart LR;\nsubgraph one \"One\"; A-->B; end;\nB-->C;\n";
+    let graph = parse_flowchart(input).expect("parse failed");
+    assert_eq!(graph.direction, Direction::LR);
+    assert_eq!(graph.subgraphs[0].nodes, vec!["A".to_string(), "B".to_string()]);
+    assert_eq!(graph.edges.len(), 2);
+}
+
+#[test]
+fn reports_line_and_column_for_errors() {
+    let input = "flowchart TB\nA[é]-->B\nB[Größe]-->\n";
+    let err = parse_flowchart(input).expect_err("expected parse error");
+    assert_eq!(err.message, "expected destination node id");
+    assert_eq!(err.line_col(input), (3, 12));
+    assert_eq!((err.line, err.column), (3, 12));
+    assert_eq!(err.to_string(), "expected destination node id at 3:12");
+}
+
+#[test]
+fn reused_parser_matches_fresh_parse() {
+    let inputs = [
+        "flowchart LR\nsubgraph one \"One\"\nA[Start]-->B{Check}\nend\nB-->|yes| C\nB-.->D\n",
+        "flowchart TB\nX-->Y\n",
+        "graph BT\nA((Circle))==>B{{Hex}}\nlinkStyle 0 label-position:below\n",
+    ];
+    let mut parser = FlowchartParser::new();
+    let mut graph = parse_flowchart(inputs[0]).expect("parse failed");
+    for input in inputs.iter().chain(inputs.iter()) {
+        let fresh = parse_flowchart(input).expect("parse failed");
+        assert_eq!(parser.parse(input).expect("parse failed"), fresh);
+        parser.parse_into(input, &mut graph).expect("parse failed");
+        assert_eq!(graph, fresh);
+    }
+    assert!(parser.parse_into("flowchart TB\nA-->\n", &mut graph).is_err());
+    parser.parse_into(inputs[1], &mut graph).expect("parse failed");
+    assert_eq!(graph, parse_flowchart(inputs[1]).unwrap());
+
+    // An error on the very first token still hands the cleared buffers back.
+    let capacity = graph.nodes.capacity();
+    assert!(parser.parse_into("$", &mut graph).is_err());
+    assert!(graph.nodes.is_empty());
+    assert_eq!(graph.nodes.capacity(), capacity);
+}
+
+#[test]
+fn parses_backward_and_bidirectional_arrows() {
+    let input = "flowchart TD\nA <-- B\nC<-.->D\nE<==F\nG <--> H --> I\n";
+    let graph = parse_flowchart(input).expect("parse failed");
+    assert_eq!(graph.edges.len(), 5);
+
+    assert_eq!(graph.edges[0].style, EdgeStyle::Solid);
+    assert_eq!(graph.edges[0].arrow, EdgeArrow::Backward);
+    assert_eq!((graph.edges[0].from.as_str(), graph.edges[0].to.as_str()), ("A", "B"));
+
+    assert_eq!(graph.edges[1].style, EdgeStyle::Dotted);
+    assert_eq!(graph.edges[1].arrow, EdgeArrow::Both);
+
+    assert_eq!(graph.edges[2].style, EdgeStyle::Thick);
+    assert_eq!(graph.edges[2].arrow, EdgeArrow::Backward);
+
+    assert_eq!(graph.edges[3].arrow, EdgeArrow::Both);
+    assert_eq!((graph.edges[3].from.as_str(), graph.edges[3].to.as_str()), ("G", "H"));
+    assert_eq!(graph.edges[4].arrow, EdgeArrow::Forward);
+    assert_eq!((graph.edges[4].from.as_str(), graph.edges[4].to.as_str()), ("H", "I"));
+}
+
+#[test]
+fn parses_inline_edge_labels() {
+    let input = "flowchart TD\nA -- yes --> B\nB -. maybe .-> C\nC == sure ==> D\nD -- plain --- E\nE --- F\nF-- two words -->G==>H==>I\n";
+    let graph = parse_flowchart(input).expect("parse failed");
+    let summary: Vec<_> = graph
+        .edges
+        .iter()
+        .map(|e| (e.from.as_str(), e.to.as_str(), e.label.as_deref(), e.style.clone(), e.arrow.clone()))
+        .collect();
+    assert_eq!(
+        summary,
+        vec![
+            ("A", "B", Some("yes"), EdgeStyle::Solid, EdgeArrow::Forward),
+            ("B", "C", Some("maybe"), EdgeStyle::Dotted, EdgeArrow::Forward),
+            ("C", "D", Some("sure"), EdgeStyle::Thick, EdgeArrow::Forward),
+            ("D", "E", Some("plain"), EdgeStyle::Solid, EdgeArrow::None),
+            ("E", "F", None, EdgeStyle::Solid, EdgeArrow::None),
+            ("F", "G", Some("two words"), EdgeStyle::Solid, EdgeArrow::Forward),
+            ("G", "H", None, EdgeStyle::Thick, EdgeArrow::Forward),
+            ("H", "I", None, EdgeStyle::Thick, EdgeArrow::Forward),
+        ]
+    );
+}
+
+#[test]
+fn inline_label_without_terminator_is_an_error() {
+    assert!(parse_flowchart("flowchart TD\nA -- dangling B\n").is_err());
+}
+
+#[test]
+fn parses_rank_same_directive() {
+    let graph = parse_flowchart("flowchart TB\nA-->B\nrank same B, C D\nrank-->A\n")
+        .expect("parse failed");
+    assert_eq!(graph.same_rank, vec![vec!["B", "C", "D"]]);
+    assert!(graph.nodes.iter().any(|node| node.id == "C"));
+    assert!(graph.nodes.iter().any(|node| node.id == "rank"));
+    assert!(parse_flowchart("flowchart TB\nrank same\n").is_err());
+}
+
+#[test]
+fn strict_mode_rejects_conflicting_redefinitions() {
+    let source = "flowchart TB\nA[X]-->B\nA[Y]\n";
+    let graph = parse_flowchart(source).expect("lenient parse failed");
+    assert_eq!(graph.nodes[0].label.as_deref(), Some("Y"));
+
+    let mut strict = FlowchartParser::new().with_duplicate_nodes(DuplicateNodes::Error);
+    let err = strict.parse(source).unwrap_err();
+    assert_eq!(err.offset, source.find("[Y]").unwrap());
+    assert!(strict.parse("flowchart TB\nA[X]-->B\nA[X]-->C\nA-->D\n").is_ok());
+}
+
+#[test]
+fn collapse_chains_summarizes_pass_through_runs() {
+    let graph = parse_flowchart(
+        "flowchart LR\naccTitle: T\naccDescr: D\nA-->B-->C[Third]-->D-->E\nA-->X\n",
+    )
+    .expect("parse failed");
+    let collapsed = collapse_chains(&graph, 3);
+    assert_eq!(collapsed.acc_title.as_deref(), Some("T"));
+    assert_eq!(collapsed.acc_descr.as_deref(), Some("D"));
+    let ids: Vec<&str> = collapsed.nodes.iter().map(|node| node.id.as_str()).collect();
+    assert_eq!(ids, vec!["A", "B__D", "E", "X"]);
+    assert_eq!(collapsed.nodes[1].label.as_deref(), Some("B → … → D (3 steps)"));
+    let edges: Vec<(&str, &str)> = collapsed
+        .edges
+        .iter()
+        .map(|edge| (edge.from.as_str(), edge.to.as_str()))
+        .collect();
+    assert_eq!(edges, vec![("A", "B__D"), ("B__D", "E"), ("A", "X")]);
+    assert_eq!(collapse_chains(&graph, 4), graph);
+}
+
+#[test]
+fn capabilities_match_what_the_parser_accepts() {
+    let caps = capabilities();
+    assert_eq!(caps.shapes, &NodeShape::ALL);
+    for shape in caps.shapes {
+        let graph = parse_flowchart(&format!("flowchart TB\n{}\n", shape.example())).expect("parse failed");
+        assert_eq!(&graph.nodes[0].shape, shape);
+    }
+    for direction in caps.directions {
+        for header in caps.headers {
+            let graph = parse_flowchart(&format!("{header} {direction:?}\nA\n")).expect("parse failed");
+            assert_eq!(&graph.direction, direction);
+        }
+    }
+    for style in caps.edge_styles {
+        let graph = parse_flowchart(&format!("flowchart TB\nA {} B\n", style.operator(&EdgeArrow::Forward)))
+            .expect("parse failed");
+        assert_eq!(&graph.edges[0].style, style);
+    }
+    assert_eq!(caps.serde, cfg!(feature = "serde"));
+}
+
+#[test]
+fn format_flowchart_round_trips_shapes_labels_and_subgraphs() {
+    let source = "graph LR\n\
+        A[Start] -->|go| B{Check}\n\
+        B -.-> C((Done)) ==> D([Stadium])\n\
+        A --- E[[Sub]]\n\
+        E <--> F[(Store)]\n\
+        F ~~~ G{{Hex}}\n\
+        G --> H(Round)\n\
+        \"end\" --> A\n\
+        linkStyle 0,3 label-position:below\n\
+        subgraph outer [Outer group]\n\
+        B\n\
+        subgraph inner\n\
+        C --> D\n\
+        end\n\
+        end\n\
+        rank same E F\n";
+    let graph = parse_flowchart(source).expect("parse failed");
+    let formatted = format_flowchart(&graph);
+    assert!(formatted.starts_with("flowchart LR\n    A[Start]\n"), "{formatted}");
+    assert!(formatted.contains("        subgraph inner\n            C\n            D\n        end\n    end\n"));
+    assert!(formatted.contains("    linkStyle 0,3 label-position:below\n"));
+    assert!(formatted.contains("    \"end\" --> A\n"));
+    let reparsed = parse_flowchart(&formatted).expect("formatted output did not parse");
+    assert_eq!(reparsed, graph);
+    assert_eq!(format_flowchart(&reparsed), formatted);
+}
+
+#[test]
+fn format_flowchart_quotes_labels_and_ids_holding_delimiters() {
+    let mut graph =
+        parse_flowchart("flowchart TB\nA-->B\nsubgraph g\nC\nend\n").expect("parse failed");
+    graph.nodes[0].label = Some("list[0] | x".to_string());
+    graph.nodes[0].shape = NodeShape::Bracket;
+    graph.nodes[1].label = Some("f(x) \"quoted\" #1".to_string());
+    graph.nodes[1].shape = NodeShape::Round;
+    graph.nodes[2].id = "say \"hi\"".to_string();
+    graph.nodes[2].label = Some("ends in ]".to_string());
+    graph.nodes[2].shape = NodeShape::Subroutine;
+    graph.subgraphs[0].nodes[0] = graph.nodes[2].id.clone();
+    graph.subgraphs[0].title = Some("[tagged]".to_string());
+    graph.edges[0].label = Some("a|b".to_string());
+
+    let formatted = format_flowchart(&graph);
+    assert!(formatted.contains("    A[\"list[0] | x\"]\n"), "{formatted}");
+    assert!(formatted.contains("    B(\"f(x) #quot;quoted#quot; #35;1\")\n"), "{formatted}");
+    assert!(formatted.contains("    \"say #quot;hi#quot;\"[[\"ends in ]\"]]\n"), "{formatted}");
+    assert!(formatted.contains("    A -->|\"a|b\"| B\n"), "{formatted}");
+    let reparsed = parse_flowchart(&formatted).expect("formatted output did not parse");
+    assert_eq!(reparsed, graph);
+    assert_eq!(format_flowchart(&reparsed), formatted);
+}
+
+#[test]
+fn ampersand_groups_expand_to_every_pair() {
+    let graph = parse_flowchart("flowchart TB\nA & B --> C\n").expect("parse failed");
+    let pairs: Vec<(&str, &str)> = graph
+        .edges
+        .iter()
+        .map(|edge| (edge.from.as_str(), edge.to.as_str()))
+        .collect();
+    assert_eq!(pairs, vec![("A", "C"), ("B", "C")]);
+
+    let graph = parse_flowchart(
+        "flowchart TB\nsubgraph g\nA[Start] & B -.->|go| C & D(Round) --> E\nend\n",
+    )
+    .expect("parse failed");
+    let pairs: Vec<(&str, &str)> = graph
+        .edges
+        .iter()
+        .map(|edge| (edge.from.as_str(), edge.to.as_str()))
+        .collect();
+    assert_eq!(
+        pairs,
+        vec![("A", "C"), ("A", "D"), ("B", "C"), ("B", "D"), ("C", "E"), ("D", "E")]
+    );
+    assert!(graph.edges[..4]
+        .iter()
+        .all(|edge| edge.style == EdgeStyle::Dotted && edge.label.as_deref() == Some("go")));
+    assert_eq!(graph.edges[4].style, EdgeStyle::Solid);
+    assert_eq!(graph.nodes[0].label.as_deref(), Some("Start"));
+    assert_eq!(graph.nodes[3].shape, NodeShape::Round);
+    assert_eq!(graph.subgraphs[0].nodes, vec!["A", "B", "C", "D", "E"]);
+
+    assert!(parse_flowchart("flowchart TB\nA & --> B\n").is_err());
+}
+
+#[test]
+fn class_defs_resolve_node_colors() {
+    let source = "flowchart TB\n\
+        classDef important fill:#f00,stroke:#333333,stroke-width:4px\n\
+        classDef faded color:rgba(0, 0, 0, 0.5)\n\
+        A-->B-->C\n\
+        class A,B important\n\
+        class B faded\n";
+    let graph = parse_flowchart(source).expect("parse failed");
+    assert_eq!(
+        graph.node_style("A"),
+        NodeStyle {
+            fill: Some(Rgba(255, 0, 0, 255)),
+            stroke: Some(Rgba(0x33, 0x33, 0x33, 255)),
+            color: None,
+        }
+    );
+    assert_eq!(graph.node_style("B").color, Some(Rgba(0, 0, 0, 128)));
+    assert_eq!(graph.node_style("B").fill, Some(Rgba(255, 0, 0, 255)));
+    assert_eq!(graph.node_style("C"), NodeStyle::default());
+    assert_eq!(graph.nodes.len(), 3);
+    assert_eq!(parse_flowchart(&format_flowchart(&graph)).expect("reparse failed"), graph);
+
+    let err = parse_flowchart("flowchart TB\nclassDef bad fill:#zzz\n").unwrap_err();
+    assert_eq!(err.message, "invalid color '#zzz'");
+}
+
+#[test]
+fn multibyte_labels_are_extracted_whole() {
+    let input = "flowchart LR\nA[café]-->|naïve|B(日本語)\nC{{ü}}-- été -->D((🚀))\n\
+                 E[[Ωmega]]\nF([→])\n";
+    let graph = parse_flowchart(input).expect("parse failed");
+    let label = |id: &str| graph.nodes.iter().find(|n| n.id == id).unwrap().label.clone();
+    assert_eq!(label("A").as_deref(), Some("café"));
+    assert_eq!(label("B").as_deref(), Some("日本語"));
+    assert_eq!(label("C").as_deref(), Some("ü"));
+    assert_eq!(label("D").as_deref(), Some("🚀"));
+    assert_eq!(label("E").as_deref(), Some("Ωmega"));
+    assert_eq!(label("F").as_deref(), Some("→"));
+    let edge_labels: Vec<_> = graph.edges.iter().map(|e| e.label.as_deref()).collect();
+    assert_eq!(edge_labels, [Some("naïve"), Some("été")]);
+
+    for input in [
+        "flowchart LR\nA[日本語\n",
+        "flowchart LR\nA[café] é\n",
+        "flowchart LR\nA[日本語]-->|ü\n",
+        "flowchart LR\nA((🚀)) ?\n",
+    ] {
+        let err = parse_flowchart(input).expect_err("should not parse");
+        assert!(input.is_char_boundary(err.offset), "{input:?}: {err}");
+    }
+}
+
+#[test]
+fn only_emoji_split_off_as_label_icons() {
+    assert_eq!(split_label_glyph("🚀 Launch"), Some(("🚀", "Launch")));
+    assert_eq!(split_label_glyph("👍🏽 Approve"), Some(("👍🏽", "Approve")));
+    assert_eq!(split_label_glyph("🧪"), Some(("🧪", "")));
+    for text in ["→ next", "⌘ K", "∑ total", "■ stop", "Plain"] {
+        assert_eq!(split_label_glyph(text), None, "{text}");
+    }
+
+    // Icons and terminal cells agree on which characters are double-width.
+    assert_eq!(char_width('🚀'), 2);
+    assert_eq!(char_width('日'), 2);
+    assert_eq!(char_width('→'), 1);
+    assert_eq!(char_width('a'), 1);
+    assert_eq!(char_width('\u{FE0F}'), 0);
+    assert_eq!(char_width('\u{1F3FD}'), 0);
+}
+
+#[test]
+fn accessibility_directives_are_captured() {
+    let source = "flowchart LR\naccTitle: Order flow\naccDescr {\n  Orders move from cart\n  to checkout.\n}\nA-->B\n";
+    let graph = parse_flowchart(source).expect("parse failed");
+    assert_eq!(graph.acc_title.as_deref(), Some("Order flow"));
+    assert_eq!(graph.acc_descr.as_deref(), Some("Orders move from cart\nto checkout."));
+    assert_eq!(graph.nodes.len(), 2);
+    assert_eq!(parse_flowchart(&format_flowchart(&graph)).expect("reparse failed"), graph);
+
+    let graph = parse_flowchart("flowchart TB\naccDescr: one line\naccTitle --> B\n")
+        .expect("parse failed");
+    assert_eq!(graph.acc_descr.as_deref(), Some("one line"));
+    assert_eq!(graph.acc_title, None);
+    assert_eq!(graph.nodes[0].id, "accTitle");
+}
+
+#[test]
+fn alt_text_prefers_accessibility_directives() {
+    let graph = parse_flowchart("flowchart TB\nA[Cart] -->|pay| B[Checkout]\nB --> C\n")
+        .expect("parse failed");
+    assert_eq!(
+        alt_text(&graph),
+        "Flowchart, top to bottom, with 3 nodes and 2 edges: Cart to Checkout (pay), Checkout to C."
+    );
+
+    let graph = parse_flowchart("flowchart TB\naccDescr: Orders go to checkout\nA-->B\n")
+        .expect("parse failed");
+    assert_eq!(alt_text(&graph), "Orders go to checkout");
+    let source = "flowchart TB\naccTitle: Orders\naccDescr: Cart to checkout\nA-->B\n";
+    let graph = parse_flowchart(source).expect("parse failed");
+    assert_eq!(alt_text(&graph), "Orders. Cart to checkout");
+}
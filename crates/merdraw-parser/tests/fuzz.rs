@@ -0,0 +1,83 @@
+use merdraw_parser::{format_flowchart, label_lines, parse_flowchart, split_label_glyph};
+
+// Flowchart syntax fragments plus multibyte text, so random inputs reach the label scanners
+// and the edge operators instead of failing on the header.
+const FRAGMENTS: &[&str] = &[
+    "flowchart TB\n", "graph LR\n", "subgraph ", "end", "style ", "classDef ", "class ",
+    "linkStyle ", "click ", "-->", "---", "-.->", "==>", "<-->", "--x", "--o", "-- ", "|",
+    "[", "]", "(", ")", "((", "))", "{", "}", "{{", "}}", "[(", ")]", "[[", "]]", ">", "\"",
+    ":::", ";", "&", "%%", "\n", " ", "A", "B", "id_1", "<br>", "é", "ü", "日本", "🚀", "👍🏽",
+    "\u{200d}", "\t", "\r\n", "#", ",", "=", "0", "fill:#f00",
+];
+
+struct XorShift(u64);
+
+impl XorShift {
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    fn below(&mut self, bound: usize) -> usize {
+        (self.next() % bound as u64) as usize
+    }
+}
+
+fn random_source(rng: &mut XorShift) -> String {
+    let mut source = String::new();
+    if rng.below(4) != 0 {
+        source.push_str("flowchart TD\n");
+    }
+    for _ in 0..rng.below(24) {
+        if rng.below(5) == 0 {
+            let ch = char::from_u32(rng.below(0x1_0000) as u32).unwrap_or('?');
+            source.push(ch);
+        } else {
+            source.push_str(FRAGMENTS[rng.below(FRAGMENTS.len())]);
+        }
+    }
+    source
+}
+
+#[test]
+fn random_sources_never_panic() {
+    let mut rng = XorShift(0x9e37_79b9_7f4a_7c15);
+    for _ in 0..20_000 {
+        let source = random_source(&mut rng);
+        let result = std::panic::catch_unwind(|| {
+            label_lines(&source);
+            split_label_glyph(&source);
+            match parse_flowchart(&source) {
+                Ok(graph) => {
+                    format_flowchart(&graph);
+                }
+                Err(err) => {
+                    err.located(&source).to_string();
+                }
+            }
+        });
+        assert!(result.is_ok(), "panicked on {source:?}");
+    }
+}
+
+#[test]
+fn random_bytes_never_panic() {
+    let mut rng = XorShift(0x2545_f491_4f6c_dd1d);
+    for _ in 0..20_000 {
+        let bytes: Vec<u8> = (0..rng.below(48)).map(|_| rng.next() as u8).collect();
+        let source = String::from_utf8_lossy(&bytes).into_owned();
+        let result = std::panic::catch_unwind(|| parse_flowchart(&source).map(|_| ()));
+        assert!(result.is_ok(), "panicked on {source:?}");
+    }
+}
+
+#[test]
+fn unexpected_multibyte_characters_are_named_whole() {
+    for (source, ch) in [("flowchart TD\nA --> é\n", 'é'), ("flowchart TD\n日本\n", '日')] {
+        let err = parse_flowchart(source).expect_err("should not parse");
+        assert_eq!(err.message, format!("unexpected character '{ch}'"));
+        assert_eq!(&source[err.offset..err.offset + ch.len_utf8()], ch.to_string());
+    }
+}
@@ -0,0 +1,203 @@
+use std::collections::{HashMap, VecDeque};
+
+use merdraw_core::ParseError;
+
+// Only the first `max_source_lines` lines of the source are shown; the rest are
+// summarized so huge generated charts don't bloat the page.
+pub fn render_page(image_id: &str, label: &str, source: &str, max_source_lines: usize) -> String {
+    let line_count = source.lines().count();
+    let shown_source = if line_count > max_source_lines {
+        let shown: Vec<&str> = source.lines().take(max_source_lines).collect();
+        format!(
+            "{}\n<span class=\"more\">({} more lines)</span>",
+            escape_html(&shown.join("\n")),
+            line_count - max_source_lines
+        )
+    } else {
+        escape_html(source)
+    };
+    page(&format!(
+        r#"  <div class="toolbar">
+    <form action="/next" method="get">
+      <button type="submit">Next</button>
+    </form>
+    <div>Example: {label}</div>
+  </div>
+  <div class="preview">
+    <img src="/image?id={image_id}" alt="flowchart preview" />
+  </div>
+  <pre>{shown_source}</pre>
+{editor}"#,
+        editor = editor_form(source)
+    ))
+}
+
+// Shown instead of a 500 when user source fails to parse: the message with its line and
+// column, the source with the offending line marked and a caret under the column, and the
+// editor so the text can be fixed and resubmitted.
+pub fn render_error_page(source: &str, err: &ParseError) -> String {
+    let (line, column) = if err.line == 0 {
+        err.line_col(source)
+    } else {
+        (err.line, err.column)
+    };
+    let width = source.lines().count().max(1).to_string().len();
+    let mut listing = String::new();
+    for (index, text) in source.lines().enumerate() {
+        let number = index + 1;
+        if number == line {
+            listing.push_str(&format!(
+                "<mark>{number:>width$} | {}</mark>\n",
+                escape_html(text)
+            ));
+            let caret = " ".repeat(width + 3 + column.saturating_sub(1));
+            listing.push_str(&format!("<span class=\"caret\">{caret}^</span>\n"));
+        } else {
+            listing.push_str(&format!("{number:>width$} | {}\n", escape_html(text)));
+        }
+    }
+    page(&format!(
+        r#"  <div class="toolbar">
+    <form action="/next" method="get">
+      <button type="submit">Random</button>
+    </form>
+  </div>
+  <h1>Parse error</h1>
+  <p class="error">{message} at line {line}, column {column}</p>
+  <pre>{listing}</pre>
+{editor}"#,
+        message = escape_html(&err.message),
+        editor = editor_form(source)
+    ))
+}
+
+fn editor_form(source: &str) -> String {
+    format!(
+        r#"  <form action="/render" method="post">
+    <textarea name="src" rows="16" spellcheck="false">{}</textarea>
+    <button type="submit">Render</button>
+  </form>"#,
+        escape_html(source)
+    )
+}
+
+fn page(body: &str) -> String {
+    format!(
+        r#"<!doctype html>
+<html lang="en">
+<head>
+  <meta charset="utf-8" />
+  <title>merdraw preview</title>
+  <style>
+    body {{ font-family: ui-sans-serif, system-ui, sans-serif; margin: 24px; }}
+    .toolbar {{ display: flex; gap: 12px; align-items: center; margin-bottom: 16px; }}
+    .preview img {{ max-width: 100%; height: auto; border: 1px solid #ccc; }}
+    pre {{ background: #f6f6f6; padding: 12px; border: 1px solid #ddd; overflow-x: auto; }}
+    pre .more {{ color: #777; font-style: italic; }}
+    pre mark {{ background: #fdd; }}
+    pre .caret, .error {{ color: #c00; font-weight: bold; }}
+    textarea {{ width: 100%; font-family: ui-monospace, monospace; font-size: 13px; }}
+    button {{ padding: 8px 14px; font-size: 14px; }}
+  </style>
+</head>
+<body>
+{body}
+</body>
+</html>"#
+    )
+}
+
+// Byte offset where the request body starts, just past the blank line ending the headers.
+pub fn body_start(request: &[u8]) -> Option<usize> {
+    request
+        .windows(4)
+        .position(|window| window == b"\r\n\r\n")
+        .map(|at| at + 4)
+}
+
+pub fn header_value<'a>(headers: &'a str, name: &str) -> Option<&'a str> {
+    headers.lines().skip(1).find_map(|line| {
+        let (key, value) = line.split_once(':')?;
+        key.trim().eq_ignore_ascii_case(name).then(|| value.trim())
+    })
+}
+
+// The `key` field of an `application/x-www-form-urlencoded` body or query string.
+pub fn form_value(encoded: &str, key: &str) -> Option<String> {
+    encoded.split('&').find_map(|pair| {
+        let (k, v) = pair.split_once('=').unwrap_or((pair, ""));
+        (percent_decode(k) == key).then(|| percent_decode(v))
+    })
+}
+
+pub fn percent_decode(input: &str) -> String {
+    let bytes = input.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'+' => out.push(b' '),
+            b'%' if i + 2 < bytes.len() => {
+                let hex = std::str::from_utf8(&bytes[i + 1..i + 3]).ok();
+                match hex.and_then(|hex| u8::from_str_radix(hex, 16).ok()) {
+                    Some(byte) => {
+                        out.push(byte);
+                        i += 2;
+                    }
+                    None => out.push(b'%'),
+                }
+            }
+            byte => out.push(byte),
+        }
+        i += 1;
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+pub fn escape_html(input: &str) -> String {
+    input
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+// Rendered PNGs keyed by id; once more than `capacity` are held the oldest is dropped.
+#[derive(Debug)]
+pub struct ImageCache {
+    images: HashMap<String, Vec<u8>>,
+    order: VecDeque<String>,
+    capacity: usize,
+}
+
+impl ImageCache {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            images: HashMap::new(),
+            order: VecDeque::new(),
+            capacity,
+        }
+    }
+
+    pub fn insert(&mut self, id: String, bytes: Vec<u8>) {
+        if self.images.insert(id.clone(), bytes).is_none() {
+            self.order.push_back(id);
+        }
+        while self.order.len() > self.capacity {
+            if let Some(old) = self.order.pop_front() {
+                self.images.remove(&old);
+            }
+        }
+    }
+
+    pub fn get(&self, id: &str) -> Option<&[u8]> {
+        self.images.get(id).map(Vec::as_slice)
+    }
+
+    pub fn len(&self) -> usize {
+        self.images.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.images.is_empty()
+    }
+}
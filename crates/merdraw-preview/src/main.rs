@@ -1,33 +1,37 @@
-use std::collections::{HashMap, VecDeque};
-use std::fs;
+use std::collections::HashMap;
 use std::io::{Read, Write};
 use std::net::{TcpListener, TcpStream};
-use std::path::PathBuf;
-use std::process::{Command, Stdio};
 use std::sync::{Arc, Mutex};
 use std::thread;
 use std::time::{SystemTime, UNIX_EPOCH};
 
+use merdraw_core::{render_mermaid, Error, ImageFormat, SkiaRenderOptions};
+use merdraw_preview::{
+    body_start, escape_html, form_value, header_value, render_error_page, render_page, ImageCache,
+};
+
 const HOST: &str = "127.0.0.1:7878";
 const MAX_IMAGES: usize = 32;
+const DEFAULT_SOURCE_LINES: usize = 200;
+const MAX_REQUEST_BYTES: usize = 1 << 20;
 
 #[derive(Debug)]
 struct AppState {
-    images: HashMap<String, PathBuf>,
-    order: VecDeque<String>,
+    images: ImageCache,
     counter: u64,
     rng: u64,
-    workspace_root: PathBuf,
+    max_source_lines: usize,
 }
 
 fn main() {
-    let workspace_root = workspace_root();
     let state = Arc::new(Mutex::new(AppState {
-        images: HashMap::new(),
-        order: VecDeque::new(),
+        images: ImageCache::new(MAX_IMAGES),
         counter: 0,
         rng: seed_from_time(),
-        workspace_root,
+        max_source_lines: std::env::var("MERDRAW_PREVIEW_SOURCE_LINES")
+            .ok()
+            .and_then(|value| value.parse().ok())
+            .unwrap_or(DEFAULT_SOURCE_LINES),
     }));
 
     let listener = TcpListener::bind(HOST).expect("failed to bind preview server");
@@ -44,23 +48,13 @@ fn main() {
     }
 }
 
-fn workspace_root() -> PathBuf {
-    let manifest_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
-    manifest_dir
-        .parent()
-        .and_then(|p| p.parent())
-        .unwrap_or(&manifest_dir)
-        .to_path_buf()
-}
-
 fn handle_connection(mut stream: TcpStream, state: Arc<Mutex<AppState>>) {
-    let mut buffer = [0u8; 8192];
-    let read = match stream.read(&mut buffer) {
-        Ok(0) | Err(_) => return,
-        Ok(n) => n,
+    let Some(request) = read_request(&mut stream) else {
+        return;
     };
-    let request = String::from_utf8_lossy(&buffer[..read]);
-    let (method, path) = match parse_request_line(&request) {
+    let head_len = body_start(&request).unwrap_or(request.len());
+    let head = String::from_utf8_lossy(&request[..head_len]);
+    let (method, path) = match parse_request_line(&head) {
         Some(values) => values,
         None => {
             let _ = respond_text(&mut stream, 400, "Bad Request");
@@ -68,19 +62,46 @@ fn handle_connection(mut stream: TcpStream, state: Arc<Mutex<AppState>>) {
         }
     };
 
+    let query = path.split_once('?').map_or("", |(_, query)| query);
+    if path.starts_with("/render") {
+        let source = match method {
+            "GET" => form_value(query, "src"),
+            "POST" => {
+                let body = String::from_utf8_lossy(&request[head_len..]);
+                // The editor posts a form; anything else (or a form without `src`, as
+                // `curl --data-binary` sends) is taken as the Mermaid text itself.
+                let form = header_value(&head, "Content-Type")
+                    .is_some_and(|value| value.starts_with("application/x-www-form-urlencoded"));
+                form.then(|| form_value(&body, "src"))
+                    .flatten()
+                    .or_else(|| Some(body.into_owned()))
+            }
+            _ => {
+                let _ = respond_text(&mut stream, 405, "Method Not Allowed");
+                return;
+            }
+        };
+        let Some(source) = source.filter(|source| !source.trim().is_empty()) else {
+            let _ = respond_text(&mut stream, 400, "Missing Mermaid source (`src`)");
+            return;
+        };
+        let mut guard = state.lock().unwrap();
+        let result = render_source(&mut guard, source.clone(), "your flowchart".to_string());
+        respond_rendered(&mut stream, &guard, result, &source);
+        return;
+    }
+
     if method != "GET" {
         let _ = respond_text(&mut stream, 405, "Method Not Allowed");
         return;
     }
 
     if path.starts_with("/image") {
-        let id = query_param(&path, "id").unwrap_or_default();
+        let id = form_value(query, "id").unwrap_or_default();
         let guard = state.lock().unwrap();
-        if let Some(image_path) = guard.images.get(&id) {
-            if let Ok(bytes) = fs::read(image_path) {
-                let _ = respond_bytes(&mut stream, 200, "image/png", &bytes);
-                return;
-            }
+        if let Some(bytes) = guard.images.get(&id) {
+            let _ = respond_bytes(&mut stream, 200, "image/png", bytes);
+            return;
         }
         let _ = respond_text(&mut stream, 404, "Not Found");
         return;
@@ -88,23 +109,9 @@ fn handle_connection(mut stream: TcpStream, state: Arc<Mutex<AppState>>) {
 
     if path == "/" || path.starts_with("/next") {
         let mut guard = state.lock().unwrap();
-        match render_random(&mut guard) {
-            Ok(Rendered {
-                id,
-                label,
-                source,
-            }) => {
-                let body = render_page(&id, &label, &source);
-                let _ = respond_html(&mut stream, 200, &body);
-            }
-            Err(err) => {
-                let body = format!(
-                    "<html><body><h1>Render failed</h1><pre>{}</pre></body></html>",
-                    escape_html(&err)
-                );
-                let _ = respond_html(&mut stream, 500, &body);
-            }
-        }
+        let (flowchart, label) = generate_flowchart(&mut guard);
+        let result = render_source(&mut guard, flowchart.clone(), label);
+        respond_rendered(&mut stream, &guard, result, &flowchart);
         return;
     }
 
@@ -117,61 +124,74 @@ struct Rendered {
     source: String,
 }
 
-fn render_random(state: &mut AppState) -> Result<Rendered, String> {
-    let (flowchart, label) = generate_flowchart(state);
-
-    let id = next_id(state);
-    let output_dir = state.workspace_root.join("tmp");
-    let _ = fs::create_dir_all(&output_dir);
-    let output_path = output_dir.join(format!("preview_{id}.png"));
-
-    let mut child = Command::new("cargo")
-        .args([
-            "run",
-            "-q",
-            "-p",
-            "merdraw",
-            "--",
-            "--out",
-            output_path.to_string_lossy().as_ref(),
-        ])
-        .current_dir(&state.workspace_root)
-        .stdin(Stdio::piped())
-        .stdout(Stdio::null())
-        .stderr(Stdio::piped())
-        .spawn()
-        .map_err(|err| format!("failed to invoke merdraw: {err}"))?;
-
-    if let Some(mut stdin) = child.stdin.take() {
-        stdin
-            .write_all(flowchart.as_bytes())
-            .map_err(|err| format!("failed to write to merdraw stdin: {err}"))?;
-    }
-
-    let output = child
-        .wait_with_output()
-        .map_err(|err| format!("failed to wait for merdraw: {err}"))?;
-
-    if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        return Err(format!("merdraw failed: {stderr}"));
+// Reads the request head plus as much body as `Content-Length` announces, up to
+// `MAX_REQUEST_BYTES`.
+fn read_request(stream: &mut TcpStream) -> Option<Vec<u8>> {
+    let mut request = Vec::new();
+    let mut buffer = [0u8; 8192];
+    loop {
+        let read = match stream.read(&mut buffer) {
+            Ok(0) | Err(_) => break,
+            Ok(n) => n,
+        };
+        request.extend_from_slice(&buffer[..read]);
+        if request.len() >= MAX_REQUEST_BYTES {
+            break;
+        }
+        if let Some(start) = body_start(&request) {
+            let head = String::from_utf8_lossy(&request[..start]);
+            let length = header_value(&head, "Content-Length")
+                .and_then(|value| value.parse::<usize>().ok())
+                .unwrap_or(0);
+            if request.len() >= start + length {
+                break;
+            }
+        }
     }
+    (!request.is_empty()).then_some(request)
+}
 
-    state.images.insert(id.clone(), output_path);
-    state.order.push_back(id.clone());
-    while state.order.len() > MAX_IMAGES {
-        if let Some(old) = state.order.pop_front() {
-            if let Some(path) = state.images.remove(&old) {
-                let _ = fs::remove_file(path);
-            }
+fn respond_rendered(
+    stream: &mut TcpStream,
+    state: &AppState,
+    result: Result<Rendered, Error>,
+    source: &str,
+) {
+    match result {
+        Ok(Rendered {
+            id,
+            label,
+            source,
+        }) => {
+            let body = render_page(&id, &label, &source, state.max_source_lines);
+            let _ = respond_html(stream, 200, &body);
+        }
+        Err(Error::Parse(err)) => {
+            let _ = respond_html(stream, 400, &render_error_page(source, &err));
+        }
+        Err(err) => {
+            let body = format!(
+                "<html><body><h1>Render failed</h1><pre>{}</pre></body></html>",
+                escape_html(&err.to_string())
+            );
+            let _ = respond_html(stream, 500, &body);
         }
     }
+}
+
+fn render_source(state: &mut AppState, source: String, label: String) -> Result<Rendered, Error> {
+    // A zero size fits the canvas to the layout, like the CLI without `--width`/`--height`.
+    let options = SkiaRenderOptions {
+        width: 0,
+        height: 0,
+        ..SkiaRenderOptions::default()
+    };
+    let bytes = render_mermaid(&source, ImageFormat::Png, &options)?;
 
-    Ok(Rendered {
-        id,
-        label,
-        source: flowchart,
-    })
+    let id = next_id(state);
+    state.images.insert(id.clone(), bytes);
+
+    Ok(Rendered { id, label, source })
 }
 
 fn next_id(state: &mut AppState) -> String {
@@ -282,38 +302,6 @@ fn random_label(state: &mut AppState) -> Option<&'static str> {
     }
 }
 
-fn render_page(image_id: &str, label: &str, source: &str) -> String {
-    let source = escape_html(source);
-    format!(
-        r#"<!doctype html>
-<html lang="en">
-<head>
-  <meta charset="utf-8" />
-  <title>merdraw preview</title>
-  <style>
-    body {{ font-family: ui-sans-serif, system-ui, sans-serif; margin: 24px; }}
-    .toolbar {{ display: flex; gap: 12px; align-items: center; margin-bottom: 16px; }}
-    .preview img {{ max-width: 100%; height: auto; border: 1px solid #ccc; }}
-    pre {{ background: #f6f6f6; padding: 12px; border: 1px solid #ddd; overflow-x: auto; }}
-    button {{ padding: 8px 14px; font-size: 14px; }}
-  </style>
-</head>
-<body>
-  <div class="toolbar">
-    <form action="/next" method="get">
-      <button type="submit">Next</button>
-    </form>
-    <div>Example: {label}</div>
-  </div>
-  <div class="preview">
-    <img src="/image?id={image_id}" alt="flowchart preview" />
-  </div>
-  <pre>{source}</pre>
-</body>
-</html>"#
-    )
-}
-
 fn parse_request_line(request: &str) -> Option<(&str, &str)> {
     let mut lines = request.lines();
     let line = lines.next()?;
@@ -323,21 +311,6 @@ fn parse_request_line(request: &str) -> Option<(&str, &str)> {
     Some((method, path))
 }
 
-fn query_param(path: &str, key: &str) -> Option<String> {
-    let mut split = path.splitn(2, '?');
-    split.next()?;
-    let query = split.next()?;
-    for pair in query.split('&') {
-        let mut kv = pair.splitn(2, '=');
-        if let Some(k) = kv.next() {
-            if k == key {
-                return kv.next().map(|v| v.to_string());
-            }
-        }
-    }
-    None
-}
-
 fn respond_html(stream: &mut TcpStream, status: u16, body: &str) -> std::io::Result<()> {
     respond_bytes(stream, status, "text/html; charset=utf-8", body.as_bytes())
 }
@@ -369,10 +342,3 @@ fn respond_bytes(
     stream.write_all(body)?;
     Ok(())
 }
-
-fn escape_html(input: &str) -> String {
-    input
-        .replace('&', "&amp;")
-        .replace('<', "&lt;")
-        .replace('>', "&gt;")
-}
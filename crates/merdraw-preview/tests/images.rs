@@ -0,0 +1,17 @@
+use merdraw_preview::ImageCache;
+
+#[test]
+fn image_cache_evicts_the_oldest_past_capacity() {
+    let mut cache = ImageCache::new(2);
+    cache.insert("a".to_string(), vec![1]);
+    cache.insert("b".to_string(), vec![2]);
+    cache.insert("a".to_string(), vec![3]);
+    assert_eq!(cache.len(), 2);
+    assert_eq!(cache.get("a"), Some(&[3][..]));
+
+    cache.insert("c".to_string(), vec![4]);
+    assert_eq!(cache.len(), 2);
+    assert_eq!(cache.get("a"), None);
+    assert_eq!(cache.get("b"), Some(&[2][..]));
+    assert_eq!(cache.get("c"), Some(&[4][..]));
+}
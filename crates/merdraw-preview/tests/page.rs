@@ -0,0 +1,43 @@
+use merdraw_core::render_mermaid_ascii;
+use merdraw_preview::{body_start, form_value, header_value, render_error_page, render_page};
+
+#[test]
+fn long_sources_are_truncated_in_the_page() {
+    let source: String = (0..500).map(|i| format!("N{i} --> N{}\n", i + 1)).collect();
+    let page = render_page("1", "big", &source, 200);
+    let pre = &page[page.find("<pre>").unwrap()..page.find("</pre>").unwrap()];
+    assert!(pre.contains("N199 --&gt; N200"));
+    assert!(!pre.contains("N200 --&gt; N201"));
+    assert!(pre.contains("(300 more lines)"));
+
+    let page = render_page("1", "small", "flowchart TB\nA-->B\n", 200);
+    assert!(!page.contains("more lines"));
+}
+
+#[test]
+fn parse_errors_mark_the_offending_line() {
+    let source = "flowchart TB\nA --> B\nB --> <C>\n";
+    let err = match render_mermaid_ascii(source, &Default::default()) {
+        Err(merdraw_core::Error::Parse(err)) => err,
+        other => panic!("expected a parse error, got {other:?}"),
+    };
+    let page = render_error_page(source, &err);
+    assert!(page.contains(&format!("at line {}, column {}", err.line, err.column)));
+    assert!(page.contains("<mark>3 | B --&gt; &lt;C&gt;</mark>"));
+    let caret = " ".repeat(4 + err.column - 1) + "^";
+    assert!(page.contains(&format!("<span class=\"caret\">{caret}</span>")));
+    assert!(page.contains("<textarea name=\"src\""));
+}
+
+#[test]
+fn posted_forms_decode_to_mermaid_source() {
+    let request = b"POST /render HTTP/1.1\r\nHost: x\r\ncontent-length: 34\r\n\r\nsrc=flowchart+TB%0AA+--%3E+B%C3%A9";
+    let start = body_start(request).expect("no body");
+    let head = String::from_utf8_lossy(&request[..start]);
+    assert_eq!(header_value(&head, "Content-Length"), Some("34"));
+    let body = std::str::from_utf8(&request[start..]).unwrap();
+    assert_eq!(form_value(body, "src").as_deref(), Some("flowchart TB\nA --> Bé"));
+    assert_eq!(form_value("a=1&src=%zz%", "src").as_deref(), Some("%zz%"));
+    // Decoded exactly once, as GET `/render?src=...` relies on.
+    assert_eq!(form_value("src=A%2541", "src").as_deref(), Some("A%41"));
+}
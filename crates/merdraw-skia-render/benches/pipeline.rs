@@ -0,0 +1,110 @@
+use std::hint::black_box;
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use merdraw_layout::{layout_flowchart, LayoutStyle};
+use merdraw_parser::parse_flowchart;
+use merdraw_skia_render::{
+    layout_flowchart_skia, render_to_bytes, ImageFormat, SkiaLayoutOptions, SkiaRenderOptions,
+};
+
+const SIZES: [(&str, usize); 3] = [("small", 10), ("medium", 100), ("large", 400)];
+
+// Same shape as the preview's random flowcharts: every node gets one or two
+// incoming edges from earlier nodes, plus a few extra forward edges, some labeled.
+fn generate_flowchart(node_count: usize, seed: u64) -> String {
+    let mut state = seed;
+    let mut next = |lo: usize, hi: usize| {
+        state = state.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+        lo + ((state >> 33) as usize) % (hi - lo + 1)
+    };
+
+    let mut output = String::from("flowchart TB\n");
+    for i in 0..node_count {
+        output.push_str(&format!("N{i}[Node {}]\n", i + 1));
+    }
+    for to in 1..node_count {
+        for _ in 0..next(1, 2) {
+            let from = next(0, to - 1);
+            if next(0, 3) == 0 {
+                output.push_str(&format!("N{from} -->|step {to}| N{to}\n"));
+            } else {
+                output.push_str(&format!("N{from} --> N{to}\n"));
+            }
+        }
+    }
+    for _ in 0..node_count / 4 {
+        let from = next(0, node_count - 2);
+        let to = next(from + 1, node_count - 1);
+        output.push_str(&format!("N{from} --> N{to}\n"));
+    }
+    output
+}
+
+fn inputs() -> Vec<(&'static str, usize, String)> {
+    SIZES
+        .iter()
+        .map(|&(name, size)| (name, size, generate_flowchart(size, 0x5eed + size as u64)))
+        .collect()
+}
+
+fn bench_parse(c: &mut Criterion) {
+    let mut group = c.benchmark_group("parse");
+    for (name, size, input) in inputs() {
+        let graph = parse_flowchart(&input).expect("parse failed");
+        assert_eq!(graph.nodes.len(), size);
+        group.bench_with_input(BenchmarkId::from_parameter(name), &input, |b, input| {
+            b.iter(|| parse_flowchart(black_box(input)).unwrap())
+        });
+    }
+    group.finish();
+}
+
+fn bench_layout(c: &mut Criterion) {
+    let mut group = c.benchmark_group("layout_flowchart");
+    let style = LayoutStyle::default();
+    for (name, size, input) in inputs() {
+        let graph = parse_flowchart(&input).expect("parse failed");
+        let layout = layout_flowchart(&graph, &style);
+        assert!(layout.nodes.iter().filter(|n| !n.is_dummy).count() == size);
+        group.bench_with_input(BenchmarkId::from_parameter(name), &graph, |b, graph| {
+            b.iter(|| layout_flowchart(black_box(graph), &style))
+        });
+    }
+    group.finish();
+}
+
+fn bench_skia_layout(c: &mut Criterion) {
+    let mut group = c.benchmark_group("layout_flowchart_skia");
+    let options = SkiaRenderOptions::default();
+    let layout_options = SkiaLayoutOptions::default();
+    for (name, size, input) in inputs() {
+        let graph = parse_flowchart(&input).expect("parse failed");
+        let layout =
+            layout_flowchart_skia(&graph, &options, &layout_options).expect("layout failed");
+        assert!(layout.nodes.iter().filter(|n| !n.is_dummy).count() == size);
+        group.bench_with_input(BenchmarkId::from_parameter(name), &graph, |b, graph| {
+            b.iter(|| layout_flowchart_skia(black_box(graph), &options, &layout_options).unwrap())
+        });
+    }
+    group.finish();
+}
+
+fn bench_render(c: &mut Criterion) {
+    let mut group = c.benchmark_group("render_to_bytes");
+    group.sample_size(10);
+    let options = SkiaRenderOptions::default();
+    for (name, _, input) in inputs() {
+        let graph = parse_flowchart(&input).expect("parse failed");
+        let layout = layout_flowchart_skia(&graph, &options, &SkiaLayoutOptions::default())
+            .expect("layout failed");
+        let bytes = render_to_bytes(&layout, ImageFormat::Png, &options).expect("render failed");
+        assert!(bytes.starts_with(b"\x89PNG"));
+        group.bench_with_input(BenchmarkId::from_parameter(name), &layout, |b, layout| {
+            b.iter(|| render_to_bytes(black_box(layout), ImageFormat::Png, &options).unwrap())
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_parse, bench_layout, bench_skia_layout, bench_render);
+criterion_main!(benches);
@@ -1,10 +1,18 @@
-use std::collections::{HashMap, VecDeque};
-
-use merdraw_layout::{LayoutEdge, LayoutGraph, LayoutNode, LayoutSubgraph};
-use merdraw_parser::{Direction, EdgeArrow, EdgeStyle, Graph, NodeShape, Subgraph};
+use std::collections::{BTreeMap, HashMap, VecDeque};
+
+use merdraw_layout::{
+    build_subgraph_graph, pack_components, straighten_edge_points, weak_components, LayoutEdge,
+    LayoutGraph, LayoutNode, LayoutSubgraph,
+};
+use merdraw_parser::{
+    Direction, EdgeArrow, EdgeLabelPosition, EdgeStyle, Graph, NodeShape, Subgraph,
+};
 use skia_safe::{Font, Paint};
 
-use crate::{build_text_paint, configure_font, load_font, SkiaRenderError, SkiaRenderOptions};
+use crate::{
+    build_text_paint, configure_font, load_font, node_label_block, wrap_label, SkiaRenderError,
+    SkiaRenderOptions,
+};
 
 #[derive(Debug, Clone)]
 pub struct SkiaLayoutOptions {
@@ -14,6 +22,22 @@ pub struct SkiaLayoutOptions {
     pub layer_gap: f32,
     pub min_node_width: f32,
     pub min_node_height: f32,
+    pub straighten_tolerance: f32,
+    pub routing: EdgeRouting,
+    pub direction: Option<Direction>,
+    pub max_node_width: Option<f32>,
+    // Gives every node this width, or the widest node's measured width if that is larger,
+    // so nodes line up in tidy columns.
+    pub uniform_node_width: Option<f32>,
+    // Grows the reported size to cover routed edge points, not just node boxes.
+    pub extent_includes_edges: bool,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum EdgeRouting {
+    #[default]
+    Straight,
+    Orthogonal,
 }
 
 impl Default for SkiaLayoutOptions {
@@ -25,6 +49,12 @@ impl Default for SkiaLayoutOptions {
             layer_gap: 64.0,
             min_node_width: 40.0,
             min_node_height: 24.0,
+            straighten_tolerance: 0.0,
+            routing: EdgeRouting::default(),
+            direction: None,
+            max_node_width: None,
+            uniform_node_width: None,
+            extent_includes_edges: false,
         }
     }
 }
@@ -41,6 +71,7 @@ struct WorkNode {
     y: f32,
     is_dummy: bool,
     shape: NodeShape,
+    group_key: Vec<usize>,
 }
 
 #[derive(Debug, Clone)]
@@ -50,6 +81,7 @@ struct EdgeMeta {
     label: Option<String>,
     style: EdgeStyle,
     arrow: EdgeArrow,
+    label_position: EdgeLabelPosition,
     reversed: bool,
 }
 
@@ -64,6 +96,8 @@ pub fn layout_flowchart_skia(
     render_options: &SkiaRenderOptions,
     layout_options: &SkiaLayoutOptions,
 ) -> Result<LayoutGraph, SkiaRenderError> {
+    let redirected = graph.redirected(layout_options.direction.as_ref());
+    let graph: &Graph = &redirected;
     if graph.nodes.is_empty() {
         return Ok(LayoutGraph {
             nodes: Vec::new(),
@@ -71,40 +105,55 @@ pub fn layout_flowchart_skia(
             subgraphs: graph
                 .subgraphs
                 .iter()
-                .map(build_layout_subgraph)
+                .map(|subgraph| build_layout_subgraph(subgraph, render_options, layout_options))
                 .collect(),
             width: 0.0,
             height: 0.0,
+            warnings: Vec::new(),
         });
     }
+    let components = if graph.subgraphs.is_empty() {
+        weak_components(graph)
+    } else {
+        Vec::new()
+    };
+    if components.len() > 1 {
+        return layout_components(graph, &components, render_options, layout_options);
+    }
 
     let mut font = load_font(render_options)?;
-    configure_font(&mut font);
+    configure_font(&mut font, render_options.font_edging);
     let text_paint = build_text_paint();
 
-    let min_width = layout_options
-        .min_node_width
-        .max(render_options.font_size * 2.5);
-    let min_height = layout_options
-        .min_node_height
-        .max(render_options.font_size * 1.6);
+    let min_size = MinNodeSize {
+        width: layout_options.min_node_width.max(render_options.font_size * 2.5),
+        height: layout_options.min_node_height.max(render_options.font_size * 1.6),
+    };
 
     let mut nodes = Vec::new();
     let mut node_index = HashMap::new();
+    let mut group_paths = HashMap::new();
+    collect_group_paths(&graph.subgraphs, &mut Vec::new(), &mut group_paths);
     for node in &graph.nodes {
         let label = node.label.as_deref().unwrap_or(node.id.as_str());
+        // Wrapped labels are stored with `<br>` breaks so drawing stacks the same lines.
+        let wrapped = layout_options.max_node_width.and_then(|max_width| {
+            let text_width = (max_width - layout_options.node_padding_x * 2.0).max(1.0);
+            wrap_label(label, &font, &text_paint, text_width)
+        });
         let (width, height) = measure_node(
-            label,
+            wrapped.as_deref().unwrap_or(label),
+            &node.shape,
             &font,
             &text_paint,
+            render_options.line_spacing,
             layout_options,
-            min_width,
-            min_height,
+            min_size,
         );
         let idx = nodes.len();
         nodes.push(WorkNode {
             id: node.id.clone(),
-            label: node.label.clone(),
+            label: wrapped.or_else(|| node.label.clone()),
             width,
             height,
             layer: 0,
@@ -113,9 +162,16 @@ pub fn layout_flowchart_skia(
             y: 0.0,
             is_dummy: false,
             shape: node.shape.clone(),
+            group_key: group_paths.get(&node.id).cloned().unwrap_or_default(),
         });
         node_index.insert(node.id.clone(), idx);
     }
+    if let Some(uniform) = layout_options.uniform_node_width {
+        let width = nodes.iter().map(|node| node.width).fold(uniform, f32::max);
+        for node in &mut nodes {
+            node.width = width;
+        }
+    }
 
     let mut edges = Vec::new();
     for edge in &graph.edges {
@@ -131,6 +187,7 @@ pub fn layout_flowchart_skia(
             label: edge.label.clone(),
             style: edge.style.clone(),
             arrow: edge.arrow.clone(),
+            label_position: edge.label_position.clone(),
             reversed: false,
         });
     }
@@ -148,6 +205,7 @@ pub fn layout_flowchart_skia(
     let mut layout_nodes = Vec::with_capacity(nodes.len());
     for node in nodes {
         layout_nodes.push(LayoutNode {
+            style: graph.node_style(&node.id),
             id: node.id,
             label: node.label,
             width: node.width,
@@ -167,6 +225,14 @@ pub fn layout_flowchart_skia(
         let to = &layout_nodes[edge.to];
         let points = if edge.from == edge.to {
             route_self_loop(from, layout_options, graph.direction.clone())
+        } else if layout_options.routing == EdgeRouting::Orthogonal {
+            route_edge_orthogonal(
+                from,
+                to,
+                &layout_nodes,
+                layout_options,
+                graph.direction.clone(),
+            )
         } else {
             route_edge_with_avoidance(
                 from,
@@ -175,45 +241,145 @@ pub fn layout_flowchart_skia(
                 layout_options,
             )
         };
+        let points = if points.len() < 2 {
+            route_self_loop(from, layout_options, graph.direction.clone())
+        } else {
+            points
+        };
+        // An edge crosses a frame when its ends sit under different top-level subgraphs.
+        let from_group = group_paths.get(&from.id).and_then(|path| path.first().copied());
+        let to_group = group_paths.get(&to.id).and_then(|path| path.first().copied());
+        let is_cross = edge.from != edge.to && from_group != to_group;
+        let source_group = from_group
+            .filter(|_| is_cross)
+            .map(|index| graph.subgraphs[index].id.clone());
         layout_edges.push(LayoutEdge {
             from: from.id.clone(),
             to: to.id.clone(),
-            is_cross: false,
+            is_cross,
             label: edge.label.clone(),
             style: edge.style.clone(),
             arrow: edge.arrow.clone(),
+            label_position: edge.label_position.clone(),
             reversed: edge.reversed,
+            source_group,
             points,
         });
     }
 
+    spread_parallel_edges(&mut layout_edges, &layout_nodes, layout_options);
+
+    if layout_options.straighten_tolerance > 0.0 {
+        for edge in &mut layout_edges {
+            edge.points = straighten_edge_points(&edge.points, layout_options.straighten_tolerance);
+        }
+    }
+
     let layout_subgraphs = graph
         .subgraphs
         .iter()
-        .map(build_layout_subgraph)
+        .map(|subgraph| build_layout_subgraph(subgraph, render_options, layout_options))
         .collect();
 
-    Ok(LayoutGraph {
+    let mut layout = LayoutGraph {
         nodes: layout_nodes,
         edges: layout_edges,
         subgraphs: layout_subgraphs,
         width,
         height,
-    })
+        warnings: Vec::new(),
+    };
+    if layout_options.extent_includes_edges {
+        layout.extend_to_edges();
+    }
+    Ok(layout)
+}
+
+// Lays out each disconnected component on its own and packs them, as the core layout does.
+fn layout_components(
+    graph: &Graph,
+    components: &[Vec<String>],
+    render_options: &SkiaRenderOptions,
+    layout_options: &SkiaLayoutOptions,
+) -> Result<LayoutGraph, SkiaRenderError> {
+    let layout_parts = |options: &SkiaLayoutOptions| {
+        components
+            .iter()
+            .map(|ids| {
+                let component = build_subgraph_graph(graph, ids);
+                layout_flowchart_skia(&component, render_options, options)
+            })
+            .collect::<Result<Vec<_>, _>>()
+    };
+    let mut parts = layout_parts(layout_options)?;
+    // A uniform width spans every component, so re-run with the widest node of any of them.
+    if layout_options.uniform_node_width.is_some() {
+        let widest = parts
+            .iter()
+            .flat_map(|part| &part.nodes)
+            .filter(|node| !node.is_dummy)
+            .map(|node| node.width)
+            .fold(0.0, f32::max);
+        parts = layout_parts(&SkiaLayoutOptions {
+            uniform_node_width: Some(widest),
+            ..layout_options.clone()
+        })?;
+    }
+    let mut layout = pack_components(graph, parts, layout_options.node_gap);
+    for node in &mut layout.nodes {
+        node.style = graph.node_style(&node.id);
+    }
+    Ok(layout)
+}
+
+#[derive(Debug, Clone, Copy)]
+struct MinNodeSize {
+    width: f32,
+    height: f32,
 }
 
 fn measure_node(
     label: &str,
+    shape: &NodeShape,
     font: &Font,
     text_paint: &Paint,
+    line_spacing: f32,
     options: &SkiaLayoutOptions,
-    min_width: f32,
-    min_height: f32,
+    min_size: MinNodeSize,
 ) -> (f32, f32) {
-    let (text_width, text_bounds) = font.measure_str(label, Some(text_paint));
-    let width = (text_width + options.node_padding_x * 2.0).max(min_width);
-    let height = (text_bounds.height() + options.node_padding_y * 2.0).max(min_height);
-    (width, height)
+    let text = node_label_block(label, font, text_paint, line_spacing);
+    let width = (text.width + options.node_padding_x * 2.0).max(min_size.width);
+    let height = (text.bottom - text.top + options.node_padding_y * 2.0).max(min_size.height);
+    let (inset_x, inset_y) = shape_text_insets(shape, width, height);
+    (width + inset_x, height + inset_y)
+}
+
+pub(crate) const SUBROUTINE_BAR_INSET: f32 = 8.0;
+// Half the height of a cylinder's rim ellipses, as a fraction of the node height.
+pub(crate) const CYLINDER_RIM_FRACTION: f32 = 1.0 / 8.0;
+
+// Extra room a shape's decoration takes from the text box, as (total x, total y).
+pub fn shape_text_insets(shape: &NodeShape, width: f32, height: f32) -> (f32, f32) {
+    match shape {
+        // The text box's corners touch the diamond's sides at double its size.
+        NodeShape::Diamond => (width, height),
+        // An ellipse inscribes a box scaled down by sqrt(2) on each axis.
+        NodeShape::Circle | NodeShape::Round => (
+            width * (std::f32::consts::SQRT_2 - 1.0),
+            height * (std::f32::consts::SQRT_2 - 1.0),
+        ),
+        // The angled sides each cut in by a quarter of the height.
+        NodeShape::Hexagon => (height / 2.0, 0.0),
+        // Rounded ends are half-circles of the node height.
+        NodeShape::Stadium => (height, 0.0),
+        NodeShape::Subroutine => (SUBROUTINE_BAR_INSET * 2.0, 0.0),
+        // Room for the top rim ellipse, which is a quarter of the grown height.
+        NodeShape::Cylinder => {
+            let ellipse = CYLINDER_RIM_FRACTION * 2.0;
+            (0.0, height * ellipse / (1.0 - ellipse))
+        }
+        _ => (0.0, 0.0),
+    }
 }
 
 fn make_acyclic(edges: &mut [EdgeMeta], node_count: usize) {
@@ -322,6 +488,7 @@ fn insert_dummy_nodes(nodes: &mut Vec<WorkNode>, edges: &[EdgeMeta]) -> Vec<Unit
                 y: 0.0,
                 is_dummy: true,
                 shape: NodeShape::Plain,
+                group_key: nodes[edge.from].group_key.clone(),
             });
             unit_edges.push(UnitEdge {
                 from: prev,
@@ -345,7 +512,9 @@ fn build_layers(nodes: &mut [WorkNode]) -> Vec<Vec<usize>> {
         layers[node.layer].push(idx);
     }
     for layer in &mut layers {
-        layer.sort();
+        layer.sort_by(|&a, &b| {
+            cmp_group_key(&nodes[a], &nodes[b]).then_with(|| a.cmp(&b))
+        });
         for (order, &node_idx) in layer.iter().enumerate() {
             nodes[node_idx].order = order;
         }
@@ -430,8 +599,12 @@ fn reorder_layer(
         .collect();
 
     scored.sort_by(|a, b| {
-        a.1.partial_cmp(&b.1)
-            .unwrap_or(std::cmp::Ordering::Equal)
+        cmp_group_key(&nodes[a.0], &nodes[b.0])
+            .then_with(|| {
+                a.1
+                    .partial_cmp(&b.1)
+                    .unwrap_or(std::cmp::Ordering::Equal)
+            })
             .then_with(|| position[a.0].cmp(&position[b.0]))
     });
 
@@ -441,6 +614,15 @@ fn reorder_layer(
     }
 }
 
+fn cmp_group_key(a: &WorkNode, b: &WorkNode) -> std::cmp::Ordering {
+    match (a.group_key.is_empty(), b.group_key.is_empty()) {
+        (true, true) => std::cmp::Ordering::Equal,
+        (true, false) => std::cmp::Ordering::Greater,
+        (false, true) => std::cmp::Ordering::Less,
+        (false, false) => a.group_key.cmp(&b.group_key),
+    }
+}
+
 fn update_positions_for_layer(positions: &mut [usize], layer: &[usize]) {
     for (pos, &node_idx) in layer.iter().enumerate() {
         positions[node_idx] = pos;
@@ -485,9 +667,12 @@ fn optimize_layer(
     while improved && passes < 8 {
         improved = false;
         for i in 0..(layer_len - 1) {
-            let before = crossings_for_layer(layer_index, edges_per_layer, positions);
             let a = layers[layer_index][i];
             let b = layers[layer_index][i + 1];
+            if nodes[a].group_key != nodes[b].group_key {
+                continue;
+            }
+            let before = crossings_for_layer(layer_index, edges_per_layer, positions);
             layers[layer_index].swap(i, i + 1);
             positions[a] = i + 1;
             positions[b] = i;
@@ -659,19 +844,7 @@ fn route_edge_with_avoidance(
 ) -> Vec<(f32, f32)> {
     let start = edge_boundary_point(from, to);
     let end = edge_boundary_point(to, from);
-
-    let mut obstacles = Vec::new();
-    for node in nodes {
-        if node.is_dummy || node.id == from.id || node.id == to.id {
-            continue;
-        }
-        obstacles.push(NodeRect {
-            left: node.x - node.width / 2.0,
-            right: node.x + node.width / 2.0,
-            top: node.y - node.height / 2.0,
-            bottom: node.y + node.height / 2.0,
-        });
-    }
+    let obstacles = edge_obstacles(from, to, nodes);
 
     if obstacles.is_empty() || !path_hits_obstacles(&[start, end], &obstacles) {
         return vec![start, end];
@@ -740,6 +913,205 @@ fn route_edge_with_avoidance(
     best
 }
 
+// Edges between the same two nodes route identically; offset each bundle sideways so the
+// lines (and their labels) stay apart. The offset never exceeds the smaller node's side.
+// Shifted endpoints slide along their end segments back onto the node boundary.
+fn spread_parallel_edges(
+    edges: &mut [LayoutEdge],
+    nodes: &[LayoutNode],
+    options: &SkiaLayoutOptions,
+) {
+    let mut bundles: BTreeMap<(&str, &str), Vec<usize>> = BTreeMap::new();
+    for (idx, edge) in edges.iter().enumerate() {
+        if edge.from != edge.to {
+            let key = if edge.from < edge.to {
+                (edge.from.as_str(), edge.to.as_str())
+            } else {
+                (edge.to.as_str(), edge.from.as_str())
+            };
+            bundles.entry(key).or_default().push(idx);
+        }
+    }
+
+    let find = |id: &str| nodes.iter().find(|node| node.id == id);
+    let mut shifts = Vec::new();
+    for ((first, second), bundle) in bundles {
+        if bundle.len() < 2 {
+            continue;
+        }
+        let (Some(a), Some(b)) = (find(first), find(second)) else {
+            continue;
+        };
+        let (dx, dy) = (b.x - a.x, b.y - a.y);
+        let len = (dx * dx + dy * dy).sqrt().max(1.0);
+        let mut normal = (-dy / len, dx / len);
+        if options.routing == EdgeRouting::Orthogonal {
+            // Keep segments axis-aligned by sliding along a single axis.
+            normal = if normal.0.abs() >= normal.1.abs() {
+                (normal.0.signum(), 0.0)
+            } else {
+                (0.0, normal.1.signum())
+            };
+        }
+        let side = if normal.0.abs() > normal.1.abs() {
+            a.width.min(b.width)
+        } else {
+            a.height.min(b.height)
+        };
+        let count = bundle.len() as f32;
+        let step = options.node_padding_x.min(side * 0.6 / (count - 1.0));
+        let center = (count - 1.0) / 2.0;
+        for (k, idx) in bundle.into_iter().enumerate() {
+            let shift = (k as f32 - center) * step;
+            shifts.push((idx, (normal.0 * shift, normal.1 * shift)));
+        }
+    }
+    for (idx, (sx, sy)) in shifts {
+        let edge = &mut edges[idx];
+        let (Some(from), Some(to)) = (find(&edge.from), find(&edge.to)) else {
+            continue;
+        };
+        let shifted: Vec<(f32, f32)> = edge.points.iter().map(|p| (p.0 + sx, p.1 + sy)).collect();
+        let last = shifted.len() - 1;
+        let start = clip_to_node(from, shifted[1], shifted[0]).unwrap_or(edge.points[0]);
+        let end = clip_to_node(to, shifted[last - 1], shifted[last]).unwrap_or(edge.points[last]);
+        edge.points = shifted;
+        edge.points[0] = start;
+        edge.points[last] = end;
+    }
+}
+
+// Where the line from `outside` toward `inside` first meets the node's box, if it does.
+fn clip_to_node(node: &LayoutNode, outside: (f32, f32), inside: (f32, f32)) -> Option<(f32, f32)> {
+    let (dx, dy) = (inside.0 - outside.0, inside.1 - outside.1);
+    let (half_w, half_h) = (node.width / 2.0, node.height / 2.0);
+    let (mut enter, mut exit) = (0.0f32, f32::INFINITY);
+    for (start, delta, low, high) in [
+        (outside.0, dx, node.x - half_w, node.x + half_w),
+        (outside.1, dy, node.y - half_h, node.y + half_h),
+    ] {
+        if delta.abs() < 1e-6 {
+            if start < low || start > high {
+                return None;
+            }
+            continue;
+        }
+        let (t0, t1) = ((low - start) / delta, (high - start) / delta);
+        enter = enter.max(t0.min(t1));
+        exit = exit.min(t0.max(t1));
+    }
+    (enter <= exit).then_some((outside.0 + dx * enter, outside.1 + dy * enter))
+}
+
+fn edge_obstacles(from: &LayoutNode, to: &LayoutNode, nodes: &[LayoutNode]) -> Vec<NodeRect> {
+    nodes
+        .iter()
+        .filter(|node| !node.is_dummy && node.id != from.id && node.id != to.id)
+        .map(|node| NodeRect {
+            left: node.x - node.width / 2.0,
+            right: node.x + node.width / 2.0,
+            top: node.y - node.height / 2.0,
+            bottom: node.y + node.height / 2.0,
+        })
+        .collect()
+}
+
+// Works in a frame where the main axis is y (x and y swap for LR/RL): edges leave and
+// enter through the faces along the main axis, and the cheapest Z or detour shape by
+// `path_score` wins.
+fn route_edge_orthogonal(
+    from: &LayoutNode,
+    to: &LayoutNode,
+    nodes: &[LayoutNode],
+    options: &SkiaLayoutOptions,
+    direction: Direction,
+) -> Vec<(f32, f32)> {
+    let vertical = matches!(direction, Direction::TB | Direction::BT);
+    let flip = |(x, y): (f32, f32)| if vertical { (x, y) } else { (y, x) };
+    let obstacles: Vec<NodeRect> = edge_obstacles(from, to, nodes)
+        .into_iter()
+        .map(|rect| {
+            let (left, top) = flip((rect.left, rect.top));
+            let (right, bottom) = flip((rect.right, rect.bottom));
+            NodeRect {
+                left,
+                right,
+                top,
+                bottom,
+            }
+        })
+        .collect();
+    let (fx, fy) = flip((from.x, from.y));
+    let (fw, fh) = flip((from.width / 2.0, from.height / 2.0));
+    let (tx, ty) = flip((to.x, to.y));
+    let (tw, th) = flip((to.width / 2.0, to.height / 2.0));
+
+    let mut candidates = Vec::new();
+    let sign = if ty >= fy { 1.0 } else { -1.0 };
+    let start = (fx, fy + sign * fh);
+    let end = (tx, ty - sign * th);
+    if (end.1 - start.1) * sign > 0.0 {
+        if (start.0 - end.0).abs() < 0.5 {
+            candidates.push(vec![start, (start.0, end.1)]);
+        }
+        for t in [0.5, 0.25, 0.75] {
+            let mid = start.1 + (end.1 - start.1) * t;
+            candidates.push(vec![start, (start.0, mid), (end.0, mid), end]);
+        }
+        let lead = ((end.1 - start.1).abs() / 3.0).min(options.layer_gap / 2.0);
+        let (y1, y2) = (start.1 + sign * lead, end.1 - sign * lead);
+        let mut lanes = vec![
+            fx.min(tx) - fw.max(tw) - options.node_gap,
+            fx.max(tx) + fw.max(tw) + options.node_gap,
+        ];
+        for rect in &obstacles {
+            lanes.push(rect.left - options.node_gap / 2.0);
+            lanes.push(rect.right + options.node_gap / 2.0);
+        }
+        for x in lanes {
+            candidates.push(vec![
+                start,
+                (start.0, y1),
+                (x, y1),
+                (x, y2),
+                (end.0, y2),
+                end,
+            ]);
+        }
+    } else {
+        // Overlapping along the main axis (same layer, or a back edge hugging it): loop
+        // out past both nodes on the far side and come back in.
+        let start = (fx, fy + fh);
+        let end = (tx, ty + th);
+        let out = start.1.max(end.1) + options.layer_gap / 2.0;
+        candidates.push(vec![start, (start.0, out), (end.0, out), end]);
+        let start = (fx, fy - fh);
+        let end = (tx, ty - th);
+        let out = start.1.min(end.1) - options.layer_gap / 2.0;
+        candidates.push(vec![start, (start.0, out), (end.0, out), end]);
+    }
+
+    let best = candidates
+        .into_iter()
+        .map(|path| {
+            let score = path_score(&path, &obstacles);
+            (score, path)
+        })
+        .min_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(std::cmp::Ordering::Equal))
+        .map(|(_, path)| path)
+        .unwrap_or_default();
+    let mut points: Vec<(f32, f32)> = Vec::with_capacity(best.len());
+    for point in best.into_iter().map(flip) {
+        let same = points.last().is_some_and(|last: &(f32, f32)| {
+            (last.0 - point.0).abs() < 0.01 && (last.1 - point.1).abs() < 0.01
+        });
+        if !same {
+            points.push(point);
+        }
+    }
+    points
+}
+
 fn path_score(points: &[(f32, f32)], obstacles: &[NodeRect]) -> f32 {
     let intersections = path_intersections(points, obstacles) as f32;
     let length = path_length(points);
@@ -869,7 +1241,26 @@ fn route_self_loop(
     }
 }
 
-fn build_layout_subgraph(subgraph: &Subgraph) -> LayoutSubgraph {
+fn collect_group_paths(
+    subgraphs: &[Subgraph],
+    prefix: &mut Vec<usize>,
+    map: &mut HashMap<String, Vec<usize>>,
+) {
+    for (idx, subgraph) in subgraphs.iter().enumerate() {
+        prefix.push(idx);
+        for node_id in &subgraph.nodes {
+            map.entry(node_id.clone()).or_insert_with(|| prefix.clone());
+        }
+        collect_group_paths(&subgraph.subgraphs, prefix, map);
+        prefix.pop();
+    }
+}
+
+fn build_layout_subgraph(
+    subgraph: &Subgraph,
+    render_options: &SkiaRenderOptions,
+    layout_options: &SkiaLayoutOptions,
+) -> LayoutSubgraph {
     LayoutSubgraph {
         id: subgraph.id.clone(),
         title: subgraph.title.clone(),
@@ -877,7 +1268,9 @@ fn build_layout_subgraph(subgraph: &Subgraph) -> LayoutSubgraph {
         subgraphs: subgraph
             .subgraphs
             .iter()
-            .map(build_layout_subgraph)
+            .map(|child| build_layout_subgraph(child, render_options, layout_options))
             .collect(),
+        title_height: render_options.font_size + layout_options.node_padding_y * 2.0,
+        title_baseline_offset: layout_options.node_padding_y + render_options.font_size,
     }
 }
@@ -1,31 +1,151 @@
+use std::collections::HashMap;
 use std::fs;
 use std::path::{Path, PathBuf};
 
-use merdraw_layout::{LayoutEdge, LayoutGraph, LayoutNode, LayoutSubgraph};
+use merdraw_layout::{
+    label_segment, LayoutEdge, LayoutGraph, LayoutNode, LayoutSubgraph, PathHighlight,
+};
+use merdraw_parser::{
+    label_lines, split_label_glyph, Direction, EdgeArrow, EdgeLabelPosition, EdgeStyle, Graph,
+    Node, NodeShape, Rgba,
+};
 use skia_safe::{
-    surfaces, Canvas, Color, EncodedImageFormat, Font, FontMgr, FontStyle, Paint, PaintStyle,
-    PathBuilder, Point, FontHinting, font::Edging,
+    gradient_shader, surfaces, Canvas, Color, EncodedImageFormat, Font, FontMgr, FontStyle, Image,
+    Paint, PaintStyle, PathBuilder, PathEffect, Point, TileMode, FontHinting, font::Edging,
 };
 
 mod layout;
 
-pub use layout::{layout_flowchart_skia, SkiaLayoutOptions};
+use layout::{CYLINDER_RIM_FRACTION, SUBROUTINE_BAR_INSET};
 
-#[derive(Debug, Clone, Copy)]
+pub use layout::{layout_flowchart_skia, shape_text_insets, EdgeRouting, SkiaLayoutOptions};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct SkiaColor(pub u8, pub u8, pub u8, pub u8);
 
 #[derive(Debug, Clone)]
 pub struct SkiaRenderOptions {
     pub width: u32,
     pub height: u32,
-    pub background: SkiaColor,
+    pub theme: Theme,
     pub jpeg_quality: u8,
     pub padding: f32,
     pub stroke_width: f32,
+    pub subgraph_stroke_width: f32,
+    pub subgraph_frame_style: FrameStyle,
+    pub subgraph_frame_styles: HashMap<String, FrameStyle>,
+    // Radius of the circular arcs at `Straight` edge bends. `Rounded` curves ignore it and
+    // use `edge_curve_radius`.
+    pub corner_rounding: f32,
+    pub edge_curve: EdgeCurve,
+    // Distance from each bend at which a `Rounded` curve leaves the straight segments.
+    pub edge_curve_radius: f32,
+    pub edge_join: EdgeJoin,
+    // Shades each edge from the first color at its start to the second at its end; heads
+    // take the color of the end they sit on. Ignored while a path is highlighted.
+    pub edge_gradient: Option<(SkiaColor, SkiaColor)>,
+    // Space between an arrow tip and the node it points at, in layout units.
+    pub arrow_gap: f32,
+    pub show_arrows: bool,
+    pub junction_dots: bool,
+    pub color_cross_edges_by_source: bool,
     pub font_size: f32,
+    // Distance between `<br>` label lines, in multiples of the font's line height.
+    pub line_spacing: f32,
     pub font_path: Option<PathBuf>,
+    pub font_edging: FontEdging,
     pub device_pixel_ratio: f32,
+    pub highlight: Option<PathHighlight>,
+    pub highlight_color: SkiaColor,
+    pub footer: Option<String>,
+    pub footer_corner: FooterCorner,
     pub debug: bool,
+    // Writes each node's id, center, layer, and order in small text above it.
+    pub debug_labels: bool,
+}
+
+// Aliased or plain AntiAlias text gives platform-independent pixels for golden tests.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum FontEdging {
+    Aliased,
+    AntiAlias,
+    #[default]
+    SubpixelAntiAlias,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum FrameStyle {
+    Solid,
+    #[default]
+    Dashed,
+}
+
+// Rounded swaps every interior bend for a quadratic bezier; end segments stay straight
+// so arrowheads keep the routed direction.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum EdgeCurve {
+    #[default]
+    Straight,
+    Rounded,
+}
+
+// How edge strokes meet at bends; Miter keeps orthogonal corners square.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum EdgeJoin {
+    #[default]
+    Round,
+    Miter,
+    Bevel,
+}
+
+// Base colors for every drawn element; class styles and path highlighting paint over
+// the node and edge entries.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Theme {
+    pub background: SkiaColor,
+    pub node_fill: SkiaColor,
+    pub node_stroke: SkiaColor,
+    pub node_text: SkiaColor,
+    pub edge: SkiaColor,
+    pub subgraph_border: SkiaColor,
+    pub subgraph_label: SkiaColor,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self {
+            background: SkiaColor(255, 255, 255, 255),
+            node_fill: SkiaColor(255, 255, 255, 255),
+            node_stroke: SkiaColor(0, 0, 0, 255),
+            node_text: SkiaColor(0, 0, 0, 255),
+            edge: SkiaColor(0, 0, 0, 255),
+            subgraph_border: SkiaColor(90, 90, 90, 255),
+            subgraph_label: SkiaColor(0, 0, 0, 255),
+        }
+    }
+}
+
+impl Theme {
+    pub fn dark() -> Self {
+        Self {
+            background: SkiaColor(30, 30, 34, 255),
+            node_fill: SkiaColor(48, 48, 54, 255),
+            node_stroke: SkiaColor(200, 200, 210, 255),
+            node_text: SkiaColor(235, 235, 240, 255),
+            edge: SkiaColor(180, 180, 190, 255),
+            subgraph_border: SkiaColor(130, 130, 140, 255),
+            subgraph_label: SkiaColor(220, 220, 228, 255),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum FooterCorner {
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    #[default]
+    BottomRight,
 }
 
 impl Default for SkiaRenderOptions {
@@ -33,14 +153,33 @@ impl Default for SkiaRenderOptions {
         Self {
             width: 1024,
             height: 768,
-            background: SkiaColor(255, 255, 255, 255),
+            theme: Theme::default(),
             jpeg_quality: 85,
             padding: 24.0,
             stroke_width: 2.0,
+            subgraph_stroke_width: 1.5,
+            subgraph_frame_style: FrameStyle::default(),
+            subgraph_frame_styles: HashMap::new(),
+            corner_rounding: 0.0,
+            edge_curve: EdgeCurve::default(),
+            edge_curve_radius: 12.0,
+            edge_join: EdgeJoin::default(),
+            edge_gradient: None,
+            arrow_gap: 0.0,
+            show_arrows: true,
+            junction_dots: false,
+            color_cross_edges_by_source: false,
             font_size: 16.0,
+            line_spacing: 1.2,
             font_path: None,
+            font_edging: FontEdging::default(),
             device_pixel_ratio: 1.0,
+            highlight: None,
+            highlight_color: SkiaColor(214, 69, 24, 255),
+            footer: None,
+            footer_corner: FooterCorner::default(),
             debug: false,
+            debug_labels: false,
         }
     }
 }
@@ -51,41 +190,174 @@ pub enum ImageFormat {
     Jpeg { quality: u8 },
 }
 
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum EdgePathCommand {
+    MoveTo((f32, f32)),
+    LineTo((f32, f32)),
+    ConicTo {
+        control: (f32, f32),
+        end: (f32, f32),
+        weight: f32,
+    },
+    QuadTo {
+        control: (f32, f32),
+        end: (f32, f32),
+    },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct EdgeJunction {
+    pub point: (f32, f32),
+    pub edges: Vec<usize>,
+}
+
 #[derive(Debug)]
 pub enum SkiaRenderError {
     EncodeUnsupported(&'static str),
-    EncodeFailed(String),
+    Encode(&'static str),
+    SurfaceCreationFailed { width: i32, height: i32 },
+    ImageSnapshotFailed,
     Io(std::io::Error),
     FontLoadFailed(String),
 }
 
+impl std::fmt::Display for SkiaRenderError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SkiaRenderError::EncodeUnsupported(format) => {
+                write!(f, "encoding {format} is not supported by this Skia build")
+            }
+            SkiaRenderError::Encode(format) => {
+                write!(f, "could not encode the rendered image as {format}")
+            }
+            SkiaRenderError::SurfaceCreationFailed { width, height } => {
+                write!(f, "could not create a {width}x{height} drawing surface")
+            }
+            SkiaRenderError::ImageSnapshotFailed => write!(f, "could not snapshot the rendered image"),
+            SkiaRenderError::Io(err) => write!(f, "{err}"),
+            SkiaRenderError::FontLoadFailed(message) => write!(f, "{message}"),
+        }
+    }
+}
+
+impl std::error::Error for SkiaRenderError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            SkiaRenderError::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
 impl From<std::io::Error> for SkiaRenderError {
     fn from(err: std::io::Error) -> Self {
         SkiaRenderError::Io(err)
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PageTile {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
 pub fn render_to_bytes(
     layout: &LayoutGraph,
     format: ImageFormat,
     options: &SkiaRenderOptions,
+) -> Result<Vec<u8>, SkiaRenderError> {
+    let region = PageTile {
+        x: 0,
+        y: 0,
+        width: options.width,
+        height: options.height,
+    };
+    render_region(layout, format, options, region, false)
+}
+
+pub fn plan_page_tiles(full: (u32, u32), page: (u32, u32), overlap: u32) -> Vec<PageTile> {
+    let axis = |full: u32, page: u32| -> Vec<(u32, u32)> {
+        let page = page.max(1);
+        let stride = page - overlap.min(page / 2);
+        let mut spans = Vec::new();
+        let mut start = 0;
+        loop {
+            let end = (start + page).min(full.max(1));
+            spans.push((start, end - start));
+            if end >= full {
+                break;
+            }
+            start += stride;
+        }
+        spans
+    };
+    let columns = axis(full.0, page.0);
+    let rows = axis(full.1, page.1);
+    let mut tiles = Vec::with_capacity(columns.len() * rows.len());
+    for &(y, height) in &rows {
+        for &(x, width) in &columns {
+            tiles.push(PageTile {
+                x,
+                y,
+                width,
+                height,
+            });
+        }
+    }
+    tiles
+}
+
+pub fn render_page_tiles(
+    layout: &LayoutGraph,
+    format: ImageFormat,
+    options: &SkiaRenderOptions,
+    page: (u32, u32),
+    overlap: u32,
+) -> Result<Vec<Vec<u8>>, SkiaRenderError> {
+    plan_page_tiles((options.width, options.height), page, overlap)
+        .into_iter()
+        .map(|tile| render_region(layout, format.clone(), options, tile, true))
+        .collect()
+}
+
+fn render_region(
+    layout: &LayoutGraph,
+    format: ImageFormat,
+    options: &SkiaRenderOptions,
+    region: PageTile,
+    crop_marks: bool,
 ) -> Result<Vec<u8>, SkiaRenderError> {
     let dpr = options.device_pixel_ratio.max(1.0);
-    let surface_width = (options.width as f32 * dpr).ceil().max(1.0) as i32;
-    let surface_height = (options.height as f32 * dpr).ceil().max(1.0) as i32;
-    let mut surface = surfaces::raster_n32_premul((surface_width, surface_height))
-        .ok_or_else(|| SkiaRenderError::EncodeFailed("failed to create surface".to_string()))?;
+    let surface_width = (region.width as f32 * dpr).ceil().max(1.0) as i32;
+    let surface_height = (region.height as f32 * dpr).ceil().max(1.0) as i32;
+    let surface_failed = SkiaRenderError::SurfaceCreationFailed {
+        width: surface_width,
+        height: surface_height,
+    };
+    // Skia refuses raster surfaces over i32::MAX bytes; check up front so the error
+    // doesn't depend on the allocator.
+    let byte_size = surface_width as u64 * surface_height as u64 * 4;
+    if byte_size > i32::MAX as u64 {
+        return Err(surface_failed);
+    }
+    let mut surface =
+        surfaces::raster_n32_premul((surface_width, surface_height)).ok_or(surface_failed)?;
 
     let canvas = surface.canvas();
-    clear_canvas(canvas, options.background);
+    clear_canvas(canvas, options.theme.background);
     if dpr != 1.0 {
         canvas.scale((dpr, dpr));
     }
+    if region.x != 0 || region.y != 0 {
+        canvas.translate((-(region.x as f32), -(region.y as f32)));
+    }
 
     let transform = compute_transform(layout, options);
 
     let mut font = load_font(options)?;
-    configure_font(&mut font);
+    configure_font(&mut font, options.font_edging);
     if options.debug {
         let family = font.typeface().family_name();
         eprintln!(
@@ -93,10 +365,14 @@ pub fn render_to_bytes(
             family, options.font_size, dpr
         );
     }
-    let text_paint = build_text_paint();
+    let mut text_paint = build_text_paint();
+    text_paint.set_color(to_color(options.theme.node_text));
 
+    let (behind_frames, over_frames) = edge_draw_passes(layout);
+    draw_edge_paths(canvas, layout, &behind_frames, &transform, options);
     let subgraph_rects = draw_subgraphs(canvas, layout, &transform, options, &font, &text_paint);
-    draw_edges(
+    draw_edge_paths(canvas, layout, &over_frames, &transform, options);
+    draw_edge_annotations(
         canvas,
         layout,
         &transform,
@@ -106,19 +382,149 @@ pub fn render_to_bytes(
         &subgraph_rects,
     );
     draw_nodes(canvas, layout, &transform, options, &font, &text_paint)?;
+    if options.debug_labels {
+        draw_debug_labels(canvas, layout, &transform, options, &font);
+    }
+    draw_footer(canvas, options, &font);
+    if crop_marks {
+        draw_crop_marks(canvas, region);
+    }
 
     let image = surface.image_snapshot();
-    let (encoded, label) = match format {
-        ImageFormat::Png => (image.encode(None, EncodedImageFormat::PNG, 100), "PNG"),
+    if image.width() != surface_width || image.height() != surface_height {
+        return Err(SkiaRenderError::ImageSnapshotFailed);
+    }
+    encode_image(&image, format)
+}
+
+// Skia returns nothing both when the build lacks an encoder and when encoding this image
+// fails; a 1x1 probe tells the two apart.
+fn encode_image(image: &Image, format: ImageFormat) -> Result<Vec<u8>, SkiaRenderError> {
+    let (encoded_format, quality, label) = match format {
+        ImageFormat::Png => (EncodedImageFormat::PNG, 100, "PNG"),
         ImageFormat::Jpeg { quality } => {
-            let q = quality.clamp(0, 100) as u32;
-            (image.encode(None, EncodedImageFormat::JPEG, q), "JPEG")
+            (EncodedImageFormat::JPEG, quality.clamp(0, 100) as u32, "JPEG")
         }
     };
+    if let Some(data) = image.encode(None, encoded_format, quality) {
+        return Ok(data.as_bytes().to_vec());
+    }
+    let supported = surfaces::raster_n32_premul((1, 1)).is_some_and(|mut probe| {
+        probe.image_snapshot().encode(None, encoded_format, quality).is_some()
+    });
+    Err(if supported {
+        SkiaRenderError::Encode(label)
+    } else {
+        SkiaRenderError::EncodeUnsupported(label)
+    })
+}
 
-    let data = encoded.ok_or(SkiaRenderError::EncodeUnsupported(label))?;
-    let bytes = data.as_bytes();
-    Ok(bytes.to_vec())
+pub fn render_node_icon(
+    shape: NodeShape,
+    label: &str,
+    options: &SkiaRenderOptions,
+) -> Result<Vec<u8>, SkiaRenderError> {
+    let mut graph = Graph::new(Direction::TB);
+    graph.nodes.push(Node {
+        id: "icon".to_string(),
+        label: Some(label.to_string()),
+        shape,
+    });
+    let layout = layout_flowchart_skia(&graph, options, &SkiaLayoutOptions::default())?;
+    render_to_bytes(&layout, ImageFormat::Png, options)
+}
+
+const OVERVIEW_PADDING: f32 = 2.0;
+
+// Fits the layout's aspect ratio into a `max_px` square.
+pub fn overview_size(layout: &LayoutGraph, max_px: u32) -> (u32, u32) {
+    let max_px = max_px.max(1) as f32;
+    let width = layout.width.max(1.0);
+    let height = layout.height.max(1.0);
+    let scale = max_px / width.max(height);
+    (
+        (width * scale).round().clamp(1.0, max_px) as u32,
+        (height * scale).round().clamp(1.0, max_px) as u32,
+    )
+}
+
+// A thumbnail of the whole graph for a viewer's corner: edges as hairlines, nodes as
+// filled boxes, no text.
+pub fn render_overview(
+    layout: &LayoutGraph,
+    max_px: u32,
+    options: &SkiaRenderOptions,
+) -> Result<Vec<u8>, SkiaRenderError> {
+    let (width, height) = overview_size(layout, max_px);
+    let overview_options = SkiaRenderOptions {
+        width,
+        height,
+        padding: OVERVIEW_PADDING,
+        footer: None,
+        device_pixel_ratio: 1.0,
+        ..options.clone()
+    };
+    let mut surface = surfaces::raster_n32_premul((width as i32, height as i32)).ok_or(
+        SkiaRenderError::SurfaceCreationFailed {
+            width: width as i32,
+            height: height as i32,
+        },
+    )?;
+    let canvas = surface.canvas();
+    clear_canvas(canvas, options.theme.background);
+    let transform = compute_transform(layout, &overview_options);
+
+    let mut line = Paint::default();
+    line.set_style(PaintStyle::Stroke);
+    line.set_color(Color::from_argb(255, 120, 120, 120));
+    line.set_stroke_width(1.0);
+    line.set_anti_alias(true);
+    for edge in layout.edges.iter().filter(|edge| edge.style != EdgeStyle::Invisible) {
+        for pair in edge.points.windows(2) {
+            let a = transform_point(pair[0], &transform);
+            let b = transform_point(pair[1], &transform);
+            canvas.draw_line(a, b, &line);
+        }
+    }
+
+    let mut fill = Paint::default();
+    fill.set_style(PaintStyle::Fill);
+    fill.set_color(Color::from_argb(255, 60, 60, 60));
+    for node in layout.nodes.iter().filter(|node| !node.is_dummy) {
+        let center = transform_point((node.x, node.y), &transform);
+        let half_w = (node.width * transform.scale / 2.0).max(0.5);
+        let half_h = (node.height * transform.scale / 2.0).max(0.5);
+        let rect = skia_safe::Rect::from_xywh(
+            center.x - half_w,
+            center.y - half_h,
+            half_w * 2.0,
+            half_h * 2.0,
+        );
+        canvas.draw_rect(rect, &fill);
+    }
+
+    encode_image(&surface.image_snapshot(), ImageFormat::Png)
+}
+
+fn draw_crop_marks(canvas: &Canvas, region: PageTile) {
+    let mut paint = Paint::default();
+    paint.set_style(PaintStyle::Stroke);
+    paint.set_color(Color::from_argb(255, 160, 160, 160));
+    paint.set_stroke_width(1.0);
+    let left = region.x as f32 + 0.5;
+    let top = region.y as f32 + 0.5;
+    let right = (region.x + region.width) as f32 - 0.5;
+    let bottom = (region.y + region.height) as f32 - 0.5;
+    let len = 8.0;
+    for (x, y, dx, dy) in [
+        (left, top, len, len),
+        (right, top, -len, len),
+        (left, bottom, len, -len),
+        (right, bottom, -len, -len),
+    ] {
+        canvas.draw_line((x, y), (x + dx, y), &paint);
+        canvas.draw_line((x, y), (x, y + dy), &paint);
+    }
 }
 
 pub fn render_to_file(
@@ -142,11 +548,16 @@ fn compute_transform(layout: &LayoutGraph, options: &SkiaRenderOptions) -> Trans
     let layout_width = layout.width.max(1.0);
     let layout_height = layout.height.max(1.0);
     let available_w = options.width as f32 - options.padding * 2.0;
-    let available_h = options.height as f32 - options.padding * 2.0;
+    let band = footer_band_height(options);
+    let available_h = options.height as f32 - band - options.padding * 2.0;
 
     let scale = (available_w / layout_width).min(available_h / layout_height).max(0.1);
     let offset_x = (options.width as f32 - layout_width * scale) / 2.0;
-    let offset_y = (options.height as f32 - layout_height * scale) / 2.0;
+    let band_top = match options.footer_corner {
+        FooterCorner::TopLeft | FooterCorner::TopRight => band,
+        FooterCorner::BottomLeft | FooterCorner::BottomRight => 0.0,
+    };
+    let offset_y = band_top + (options.height as f32 - band - layout_height * scale) / 2.0;
 
     Transform {
         scale,
@@ -155,6 +566,106 @@ fn compute_transform(layout: &LayoutGraph, options: &SkiaRenderOptions) -> Trans
     }
 }
 
+pub fn diagram_viewport(layout: &LayoutGraph, options: &SkiaRenderOptions) -> (f32, f32, f32, f32) {
+    let transform = compute_transform(layout, options);
+    let right = transform.offset_x + layout.width.max(1.0) * transform.scale;
+    let bottom = transform.offset_y + layout.height.max(1.0) * transform.scale;
+    (transform.offset_x, transform.offset_y, right, bottom)
+}
+
+const FOOTER_MARGIN: f32 = 6.0;
+
+fn footer_font_size(options: &SkiaRenderOptions) -> f32 {
+    (options.font_size * 0.75).max(8.0)
+}
+
+// Height kept free of the diagram along the footer's edge of the canvas.
+pub fn footer_band_height(options: &SkiaRenderOptions) -> f32 {
+    match options.footer.as_deref() {
+        Some(text) if !text.trim().is_empty() => footer_font_size(options) + FOOTER_MARGIN * 2.0,
+        _ => 0.0,
+    }
+}
+
+// Baseline origin for footer text of the given width.
+pub fn footer_origin(options: &SkiaRenderOptions, text_width: f32) -> (f32, f32) {
+    let width = options.width as f32;
+    let height = options.height as f32;
+    let x = match options.footer_corner {
+        FooterCorner::TopLeft | FooterCorner::BottomLeft => FOOTER_MARGIN,
+        FooterCorner::TopRight | FooterCorner::BottomRight => width - FOOTER_MARGIN - text_width,
+    };
+    let y = match options.footer_corner {
+        FooterCorner::TopLeft | FooterCorner::TopRight => FOOTER_MARGIN + footer_font_size(options),
+        FooterCorner::BottomLeft | FooterCorner::BottomRight => height - FOOTER_MARGIN,
+    };
+    (x.max(FOOTER_MARGIN), y)
+}
+
+fn draw_footer(canvas: &Canvas, options: &SkiaRenderOptions, font: &Font) {
+    let Some(text) = options.footer.as_deref().filter(|text| !text.trim().is_empty()) else {
+        return;
+    };
+    let mut footer_font = font.clone();
+    footer_font.set_size(footer_font_size(options));
+    let mut paint = build_text_paint();
+    paint.set_color(Color::from_argb(255, 128, 128, 128));
+    let (text_width, _) = footer_font.measure_str(text, Some(&paint));
+    let (x, y) = footer_origin(options, text_width);
+    canvas.draw_str(text, (snap_point(x), snap_point(y)), &footer_font, &paint);
+}
+
+fn draw_debug_labels(
+    canvas: &Canvas,
+    layout: &LayoutGraph,
+    transform: &Transform,
+    options: &SkiaRenderOptions,
+    font: &Font,
+) {
+    let mut debug_font = font.clone();
+    debug_font.set_size((options.font_size * 0.6).max(7.0));
+    let mut paint = build_text_paint();
+    paint.set_color(Color::from_argb(255, 200, 0, 160));
+    for node in layout.nodes.iter().filter(|node| !node.is_dummy) {
+        let text = format!(
+            "{} ({:.0},{:.0},{},{})",
+            node.id, node.x, node.y, node.layer, node.order
+        );
+        let center = transform_point((node.x, node.y), transform);
+        let left = center.x - node.width * transform.scale / 2.0;
+        let top = center.y - node.height * transform.scale / 2.0;
+        let (_, metrics) = debug_font.metrics();
+        let baseline = top - metrics.descent - 2.0;
+        canvas.draw_str(&text, (snap_point(left), snap_point(baseline)), &debug_font, &paint);
+    }
+}
+
+// Subgraph frames are specified in output pixels, so undo the canvas dpr scale.
+fn subgraph_frame_stroke_width(options: &SkiaRenderOptions) -> f32 {
+    let dpr = options.device_pixel_ratio.max(1.0);
+    options.subgraph_stroke_width / dpr
+}
+
+// Per-subgraph styles are keyed by the slash-joined subgraph path, e.g. "outer/inner".
+pub fn subgraph_frame_paint(options: &SkiaRenderOptions, path: &str) -> Paint {
+    let width = subgraph_frame_stroke_width(options);
+    let mut stroke = Paint::default();
+    stroke.set_style(PaintStyle::Stroke);
+    stroke.set_color(to_color(options.theme.subgraph_border));
+    stroke.set_stroke_width(width);
+    stroke.set_anti_alias(true);
+    let style = options
+        .subgraph_frame_styles
+        .get(path)
+        .copied()
+        .unwrap_or(options.subgraph_frame_style);
+    if style == FrameStyle::Dashed {
+        let dash = (width * 4.0).max(4.0);
+        stroke.set_path_effect(PathEffect::dash(&[dash, dash * 0.75], 0.0));
+    }
+    stroke
+}
+
 fn transform_point(point: (f32, f32), transform: &Transform) -> Point {
     Point::new(
         point.0 * transform.scale + transform.offset_x,
@@ -162,8 +673,52 @@ fn transform_point(point: (f32, f32), transform: &Transform) -> Point {
     )
 }
 
+fn to_color(color: SkiaColor) -> Color {
+    Color::from_argb(color.3, color.0, color.1, color.2)
+}
+
+fn dimmed_color() -> Color {
+    Color::from_argb(255, 190, 190, 190)
+}
+
+fn node_ink_color(options: &SkiaRenderOptions, id: &str, base: SkiaColor) -> Color {
+    match options.highlight.as_ref() {
+        Some(highlight) if highlight.contains_node(id) => to_color(options.highlight_color),
+        Some(_) => dimmed_color(),
+        None => to_color(base),
+    }
+}
+
+fn edge_ink_color(options: &SkiaRenderOptions, index: usize) -> Color {
+    match options.highlight.as_ref() {
+        Some(highlight) if highlight.contains_edge(index) => to_color(options.highlight_color),
+        Some(_) => dimmed_color(),
+        None => to_color(options.theme.edge),
+    }
+}
+
+const GROUP_EDGE_PALETTE: [SkiaColor; 6] = [
+    SkiaColor(31, 119, 180, 255),
+    SkiaColor(214, 39, 40, 255),
+    SkiaColor(44, 160, 44, 255),
+    SkiaColor(148, 103, 189, 255),
+    SkiaColor(255, 127, 14, 255),
+    SkiaColor(23, 190, 207, 255),
+];
+
+pub fn source_group_colors(layout: &LayoutGraph) -> HashMap<String, SkiaColor> {
+    let mut colors = HashMap::new();
+    for edge in layout.edges.iter().filter(|edge| edge.is_cross) {
+        if let Some(group) = edge.source_group.as_ref() {
+            let next = GROUP_EDGE_PALETTE[colors.len() % GROUP_EDGE_PALETTE.len()];
+            colors.entry(group.clone()).or_insert(next);
+        }
+    }
+    colors
+}
+
 fn clear_canvas(canvas: &Canvas, background: SkiaColor) {
-    canvas.clear(Color::from_argb(background.3, background.0, background.1, background.2));
+    canvas.clear(to_color(background));
 }
 
 pub(crate) fn load_font(options: &SkiaRenderOptions) -> Result<Font, SkiaRenderError> {
@@ -192,14 +747,142 @@ pub(crate) fn load_font(options: &SkiaRenderOptions) -> Result<Font, SkiaRenderE
     }
 }
 
-pub(crate) fn configure_font(font: &mut Font) {
-    font.set_edging(Edging::SubpixelAntiAlias);
+pub(crate) fn configure_font(font: &mut Font, edging: FontEdging) {
+    let (edging, subpixel) = match edging {
+        FontEdging::Aliased => (Edging::Alias, false),
+        FontEdging::AntiAlias => (Edging::AntiAlias, false),
+        FontEdging::SubpixelAntiAlias => (Edging::SubpixelAntiAlias, true),
+    };
+    font.set_edging(edging);
     font.set_hinting(FontHinting::Full);
-    font.set_subpixel(true);
+    font.set_subpixel(subpixel);
     font.set_baseline_snap(true);
     font.set_force_auto_hinting(true);
 }
 
+const LABEL_GLYPH_SCALE: f32 = 1.25;
+
+pub(crate) struct LabelRuns<'a> {
+    pub runs: Vec<(&'a str, Font, f32)>,
+    pub width: f32,
+    pub bounds: skia_safe::Rect,
+}
+
+// A leading icon glyph is drawn larger than the text after it; each run carries its
+// font and x offset from the label start, and `bounds` covers the ink of both.
+pub(crate) fn node_label_runs<'a>(label: &'a str, font: &Font, paint: &Paint) -> LabelRuns<'a> {
+    let Some((glyph, rest)) = split_label_glyph(label) else {
+        let (width, bounds) = font.measure_str(label, Some(paint));
+        return LabelRuns {
+            runs: vec![(label, font.clone(), 0.0)],
+            width,
+            bounds,
+        };
+    };
+    let mut glyph_font = font.clone();
+    glyph_font.set_size(font.size() * LABEL_GLYPH_SCALE);
+    let (glyph_width, glyph_bounds) = glyph_font.measure_str(glyph, Some(paint));
+    let mut runs = vec![(glyph, glyph_font, 0.0)];
+    if rest.is_empty() {
+        return LabelRuns {
+            runs,
+            width: glyph_width,
+            bounds: glyph_bounds,
+        };
+    }
+    let (space_width, _) = font.measure_str(" ", Some(paint));
+    let offset = glyph_width + space_width;
+    let (rest_width, rest_bounds) = font.measure_str(rest, Some(paint));
+    runs.push((rest, font.clone(), offset));
+    let width = offset + rest_width;
+    LabelRuns {
+        runs,
+        width,
+        bounds: skia_safe::Rect::from_ltrb(
+            glyph_bounds.left().min(offset + rest_bounds.left()),
+            glyph_bounds.top().min(rest_bounds.top()),
+            width.max(offset + rest_bounds.right()),
+            glyph_bounds.bottom().max(rest_bounds.bottom()),
+        ),
+    }
+}
+
+pub(crate) struct LabelBlock<'a> {
+    pub lines: Vec<LabelRuns<'a>>,
+    pub width: f32,
+    pub line_advance: f32,
+    // Ink extent relative to the first line's baseline.
+    pub top: f32,
+    pub bottom: f32,
+}
+
+// `<br>`-separated label lines, stacked `line_spacing` font line heights apart.
+pub(crate) fn node_label_block<'a>(
+    label: &'a str,
+    font: &Font,
+    paint: &Paint,
+    line_spacing: f32,
+) -> LabelBlock<'a> {
+    let lines: Vec<LabelRuns<'a>> = label_lines(label)
+        .into_iter()
+        .map(|line| node_label_runs(line, font, paint))
+        .collect();
+    let line_advance = font.spacing() * line_spacing;
+    let width = lines.iter().map(|line| line.width).fold(0.0, f32::max);
+    let top = lines[0].bounds.top();
+    let bottom = (lines.len() - 1) as f32 * line_advance + lines[lines.len() - 1].bounds.bottom();
+    LabelBlock {
+        lines,
+        width,
+        line_advance,
+        top,
+        bottom,
+    }
+}
+
+// Word-wraps each label line to `max_width`, re-joined with `<br>` so the result flows
+// through `node_label_block` like hand-broken text. Words wider than the limit are
+// hard-broken. `None` when every line already fits.
+pub(crate) fn wrap_label(label: &str, font: &Font, paint: &Paint, max_width: f32) -> Option<String> {
+    let fits = |text: &str| node_label_runs(text, font, paint).width <= max_width;
+    if label_lines(label).into_iter().all(fits) {
+        return None;
+    }
+    let mut wrapped: Vec<String> = Vec::new();
+    for line in label_lines(label) {
+        let mut current = String::new();
+        for word in line.split_whitespace() {
+            let candidate = if current.is_empty() {
+                word.to_string()
+            } else {
+                format!("{current} {word}")
+            };
+            if fits(&candidate) {
+                current = candidate;
+                continue;
+            }
+            if !current.is_empty() {
+                wrapped.push(std::mem::take(&mut current));
+            }
+            current = word.to_string();
+            while !fits(&current) && current.chars().count() > 1 {
+                // Keep at least one character per line so an oversized glyph still moves on.
+                let mut split = current.char_indices().nth(1).map_or(current.len(), |(at, _)| at);
+                for (at, _) in current.char_indices().skip(2) {
+                    if !fits(&current[..at]) {
+                        break;
+                    }
+                    split = at;
+                }
+                let rest = current.split_off(split);
+                wrapped.push(std::mem::replace(&mut current, rest));
+            }
+        }
+        wrapped.push(current);
+    }
+    Some(wrapped.join("<br>"))
+}
+
 pub(crate) fn build_text_paint() -> Paint {
     let mut paint = Paint::default();
     paint.set_color(Color::BLACK);
@@ -230,14 +913,6 @@ fn collect_node_rects(layout: &LayoutGraph, transform: &Transform) -> Vec<skia_s
     rects
 }
 
-fn normalize_point(point: Point) -> Point {
-    let len = (point.x * point.x + point.y * point.y).sqrt();
-    if len <= f32::EPSILON {
-        return Point::new(0.0, -1.0);
-    }
-    Point::new(point.x / len, point.y / len)
-}
-
 fn centered_rect(center: Point, width: f32, height: f32) -> skia_safe::Rect {
     skia_safe::Rect::from_xywh(
         center.x - width / 2.0,
@@ -263,16 +938,11 @@ fn draw_subgraphs(
         return Vec::new();
     }
 
-    let mut node_map = std::collections::HashMap::new();
+    let mut node_map = HashMap::new();
     for node in &layout.nodes {
         node_map.insert(node.id.as_str(), node);
     }
 
-    let mut stroke = Paint::default();
-    stroke.set_style(PaintStyle::Stroke);
-    stroke.set_color(Color::from_argb(255, 90, 90, 90));
-    stroke.set_stroke_width(1.5);
-
     let mut rects = Vec::new();
     let mut path = Vec::new();
     for subgraph in &layout.subgraphs {
@@ -284,7 +954,6 @@ fn draw_subgraphs(
             options,
             font,
             text_paint,
-            &stroke,
             &mut path,
             &mut rects,
         );
@@ -314,12 +983,11 @@ fn draw_subgraphs(
 fn draw_subgraph(
     canvas: &Canvas,
     subgraph: &LayoutSubgraph,
-    node_map: &std::collections::HashMap<&str, &LayoutNode>,
+    node_map: &HashMap<&str, &LayoutNode>,
     transform: &Transform,
     options: &SkiaRenderOptions,
     font: &Font,
     text_paint: &Paint,
-    stroke: &Paint,
     path: &mut Vec<String>,
     rects: &mut Vec<SubgraphRect>,
 ) -> Option<skia_safe::Rect> {
@@ -355,7 +1023,6 @@ fn draw_subgraph(
             options,
             font,
             text_paint,
-            stroke,
             path,
             rects,
         ) {
@@ -381,14 +1048,17 @@ fn draw_subgraph(
         rect.height() + padding * 2.0,
     );
 
-    canvas.draw_rect(rect, stroke);
+    let stroke = subgraph_frame_paint(options, &path.join("/"));
+    canvas.draw_rect(rect, &stroke);
 
     let label = subgraph.title.as_deref().unwrap_or(subgraph.id.as_str());
     if !label.is_empty() {
-        let (_text_width, text_bounds) = font.measure_str(label, Some(text_paint));
+        let mut label_paint = text_paint.clone();
+        label_paint.set_color(to_color(options.theme.subgraph_label));
+        let (_text_width, text_bounds) = font.measure_str(label, Some(&label_paint));
         let text_x = snap_point(rect.left() + padding);
         let text_y = snap_point(rect.top() + padding + text_bounds.height());
-        canvas.draw_str(label, (text_x, text_y), font, text_paint);
+        canvas.draw_str(label, (text_x, text_y), font, &label_paint);
     }
 
     rects.push(SubgraphRect {
@@ -461,14 +1131,13 @@ fn draw_nodes(
     font: &Font,
     text_paint: &Paint,
 ) -> Result<(), SkiaRenderError> {
+    let theme = &options.theme;
     let mut stroke = Paint::default();
     stroke.set_style(PaintStyle::Stroke);
-    stroke.set_color(Color::BLACK);
     stroke.set_stroke_width(options.stroke_width);
 
     let mut fill = Paint::default();
     fill.set_style(PaintStyle::Fill);
-    fill.set_color(Color::WHITE);
 
     for node in &layout.nodes {
         if node.is_dummy {
@@ -483,43 +1152,216 @@ fn draw_nodes(
             half_w * 2.0,
             half_h * 2.0,
         );
-        canvas.draw_rect(rect, &fill);
-        canvas.draw_rect(rect, &stroke);
-
+        let ink = |base| node_ink_color(options, &node.id, base);
+        // Class colors give way to path highlighting, which recolors by emphasis.
+        let class_color = |color: Option<Rgba>| {
+            color
+                .filter(|_| options.highlight.is_none())
+                .map(|Rgba(r, g, b, a)| Color::from_argb(a, r, g, b))
+        };
+        stroke.set_color(class_color(node.style.stroke).unwrap_or(ink(theme.node_stroke)));
+        fill.set_color(class_color(node.style.fill).unwrap_or(to_color(theme.node_fill)));
+        draw_node_outline(canvas, &node.shape, rect, transform.scale, &fill, &stroke);
+
+        let mut node_text_paint = text_paint.clone();
+        node_text_paint.set_color(class_color(node.style.color).unwrap_or(ink(theme.node_text)));
         let text = node.label.as_deref().unwrap_or(node.id.as_str());
-        let (text_width, text_bounds) = font.measure_str(text, Some(text_paint));
-        let text_x = snap_point(center.x - text_width / 2.0);
-        let text_y = snap_point(center.y + text_bounds.height() / 2.0);
-        canvas.draw_str(text, (text_x, text_y), font, text_paint);
+        let block = node_label_block(text, font, &node_text_paint, options.line_spacing);
+        // Center the ink box rather than the baseline so text sits mid-shape
+        // inside the narrower diamond, ellipse, and hexagon interiors.
+        let baseline = center.y + label_shift(&node.shape, rect.height())
+            - (block.top + block.bottom) / 2.0;
+        for (index, line) in block.lines.iter().enumerate() {
+            let text_x = snap_point(center.x - line.width / 2.0);
+            let text_y = snap_point(baseline + index as f32 * block.line_advance);
+            for (run, run_font, offset) in &line.runs {
+                canvas.draw_str(run, (text_x + offset, text_y), run_font, &node_text_paint);
+            }
+        }
     }
 
     Ok(())
 }
 
-fn draw_edges(
+// Moves a label down past decoration drawn only at the top of a shape.
+fn label_shift(shape: &NodeShape, height: f32) -> f32 {
+    match shape {
+        // Centers the text between the top rim ellipse and the bottom edge.
+        NodeShape::Cylinder => height * CYLINDER_RIM_FRACTION,
+        _ => 0.0,
+    }
+}
+
+fn draw_node_outline(
+    canvas: &Canvas,
+    shape: &NodeShape,
+    rect: skia_safe::Rect,
+    scale: f32,
+    fill: &Paint,
+    stroke: &Paint,
+) {
+    match shape {
+        NodeShape::Stadium => {
+            let radius = rect.height() / 2.0;
+            canvas.draw_round_rect(rect, radius, radius, fill);
+            canvas.draw_round_rect(rect, radius, radius, stroke);
+        }
+        NodeShape::Subroutine => {
+            canvas.draw_rect(rect, fill);
+            canvas.draw_rect(rect, stroke);
+            let inset = SUBROUTINE_BAR_INSET * scale;
+            for x in [rect.left() + inset, rect.right() - inset] {
+                canvas.draw_line((x, rect.top()), (x, rect.bottom()), stroke);
+            }
+        }
+        NodeShape::Cylinder => {
+            let rim = rect.height() * CYLINDER_RIM_FRACTION;
+            let top = skia_safe::Rect::from_ltrb(
+                rect.left(),
+                rect.top(),
+                rect.right(),
+                rect.top() + rim * 2.0,
+            );
+            let bottom = skia_safe::Rect::from_ltrb(
+                rect.left(),
+                rect.bottom() - rim * 2.0,
+                rect.right(),
+                rect.bottom(),
+            );
+
+            let mut builder = PathBuilder::new();
+            builder.move_to((rect.left(), rect.top() + rim));
+            builder.line_to((rect.left(), rect.bottom() - rim));
+            builder.arc_to(bottom, 180.0, -180.0, false);
+            builder.line_to((rect.right(), rect.top() + rim));
+            builder.arc_to(top, 0.0, -180.0, false);
+            builder.close();
+            let body = builder.detach();
+            canvas.draw_path(&body, fill);
+            canvas.draw_path(&body, stroke);
+            canvas.draw_oval(top, stroke);
+        }
+        NodeShape::Circle | NodeShape::Round => {
+            canvas.draw_oval(rect, fill);
+            canvas.draw_oval(rect, stroke);
+        }
+        NodeShape::Diamond => {
+            let (cx, cy) = (rect.center_x(), rect.center_y());
+            let outline = polygon_path(&[
+                (cx, rect.top()),
+                (rect.right(), cy),
+                (cx, rect.bottom()),
+                (rect.left(), cy),
+            ]);
+            canvas.draw_path(&outline, fill);
+            canvas.draw_path(&outline, stroke);
+        }
+        NodeShape::Hexagon => {
+            let cut = rect.height() / 4.0;
+            let cy = rect.center_y();
+            let outline = polygon_path(&[
+                (rect.left() + cut, rect.top()),
+                (rect.right() - cut, rect.top()),
+                (rect.right(), cy),
+                (rect.right() - cut, rect.bottom()),
+                (rect.left() + cut, rect.bottom()),
+                (rect.left(), cy),
+            ]);
+            canvas.draw_path(&outline, fill);
+            canvas.draw_path(&outline, stroke);
+        }
+        NodeShape::Plain | NodeShape::Bracket => {
+            canvas.draw_rect(rect, fill);
+            canvas.draw_rect(rect, stroke);
+        }
+    }
+}
+
+fn polygon_path(points: &[(f32, f32)]) -> skia_safe::Path {
+    let mut builder = PathBuilder::new();
+    builder.move_to(points[0]);
+    for &point in &points[1..] {
+        builder.line_to(point);
+    }
+    builder.close();
+    builder.detach()
+}
+
+// Edge indices in draw order: intra-group edges go under the subgraph frames so a
+// frame covers its own edges' border crossings; cross-group edges go on top.
+// Invisible edges only shape the layout and are in neither pass.
+pub fn edge_draw_passes(layout: &LayoutGraph) -> (Vec<usize>, Vec<usize>) {
+    (0..layout.edges.len())
+        .filter(|&index| layout.edges[index].style != EdgeStyle::Invisible)
+        .partition(|&index| !layout.edges[index].is_cross)
+}
+
+fn draw_edge_paths(
     canvas: &Canvas,
     layout: &LayoutGraph,
+    indices: &[usize],
     transform: &Transform,
     options: &SkiaRenderOptions,
-    font: &Font,
-    text_paint: &Paint,
-    subgraph_rects: &[SubgraphRect],
 ) {
+    let mut paint = edge_stroke_paint(options);
+    let group_colors = if options.color_cross_edges_by_source && options.highlight.is_none() {
+        source_group_colors(layout)
+    } else {
+        HashMap::new()
+    };
+    for &index in indices {
+        let edge = &layout.edges[index];
+        let group_color = edge
+            .source_group
+            .as_ref()
+            .filter(|_| edge.is_cross)
+            .and_then(|group| group_colors.get(group));
+        match group_color {
+            Some(&color) => paint.set_color(to_color(color)),
+            None => paint.set_color(edge_ink_color(options, index)),
+        };
+        draw_edge_path(canvas, edge, transform, &paint, options);
+    }
+}
+
+pub fn edge_stroke_paint(options: &SkiaRenderOptions) -> Paint {
     let mut paint = Paint::default();
     paint.set_style(PaintStyle::Stroke);
-    paint.set_color(Color::BLACK);
+    paint.set_color(to_color(options.theme.edge));
     paint.set_stroke_width(options.stroke_width);
     paint.set_anti_alias(true);
     paint.set_stroke_cap(skia_safe::paint::Cap::Round);
-    paint.set_stroke_join(skia_safe::paint::Join::Round);
+    paint.set_stroke_join(match options.edge_join {
+        EdgeJoin::Round => skia_safe::paint::Join::Round,
+        EdgeJoin::Miter => skia_safe::paint::Join::Miter,
+        EdgeJoin::Bevel => skia_safe::paint::Join::Bevel,
+    });
+    paint
+}
 
-    for edge in &layout.edges {
-        draw_edge_path(canvas, edge, transform, &paint, options);
+fn draw_edge_annotations(
+    canvas: &Canvas,
+    layout: &LayoutGraph,
+    transform: &Transform,
+    options: &SkiaRenderOptions,
+    font: &Font,
+    text_paint: &Paint,
+    subgraph_rects: &[SubgraphRect],
+) {
+    if options.junction_dots {
+        let mut dot = Paint::default();
+        dot.set_style(PaintStyle::Fill);
+        dot.set_anti_alias(true);
+        for junction in edge_junctions(layout, JUNCTION_TOLERANCE) {
+            dot.set_color(edge_ink_color(options, junction.edges[0]));
+            let center = transform_point(junction.point, transform);
+            canvas.draw_circle(center, options.stroke_width * 1.75, &dot);
+        }
     }
 
     let base_avoid = collect_node_rects(layout, transform);
     let mut placed = Vec::new();
-    for edge in &layout.edges {
+    for edge in layout.edges.iter().filter(|edge| edge.style != EdgeStyle::Invisible) {
         let mut avoid_rects = base_avoid.clone();
         if edge.is_cross {
             for rect in subgraph_rects {
@@ -539,27 +1381,260 @@ fn draw_edges(
     }
 }
 
+const JUNCTION_TOLERANCE: f32 = 1.0;
+
+pub fn edge_junctions(layout: &LayoutGraph, tolerance: f32) -> Vec<EdgeJunction> {
+    let mut junctions: Vec<EdgeJunction> = Vec::new();
+    for (index, edge) in layout.edges.iter().enumerate() {
+        if edge.style == EdgeStyle::Invisible {
+            continue;
+        }
+        let (Some(&first), Some(&last)) = (edge.points.first(), edge.points.last()) else {
+            continue;
+        };
+        for point in [first, last] {
+            let existing = junctions
+                .iter_mut()
+                .find(|junction| distance(junction.point, point) <= tolerance);
+            match existing {
+                Some(junction) if !junction.edges.contains(&index) => junction.edges.push(index),
+                Some(_) => {}
+                None => junctions.push(EdgeJunction {
+                    point,
+                    edges: vec![index],
+                }),
+            }
+        }
+    }
+    junctions.retain(|junction| junction.edges.len() >= 2);
+    junctions
+}
+
 fn draw_edge_path(
     canvas: &Canvas,
     edge: &LayoutEdge,
     transform: &Transform,
     paint: &Paint,
-    _options: &SkiaRenderOptions,
+    options: &SkiaRenderOptions,
 ) {
     if edge.points.is_empty() {
         return;
     }
+    let points: Vec<(f32, f32)> = edge
+        .points
+        .iter()
+        .map(|&point| {
+            let p = transform_point(point, transform);
+            (p.x, p.y)
+        })
+        .collect();
+    let (start_head, end_head) = arrowhead_ends(&edge.arrow, options);
+    // Zoom with the diagram like the nodes do; the canvas scale then applies the DPR.
+    let gap = options.arrow_gap * transform.scale;
+    let start_gap = if start_head { gap } else { 0.0 };
+    let end_gap = if end_head { gap } else { 0.0 };
+    let points = pull_back_endpoints(&points, start_gap, end_gap);
+    let stroke_width = edge_stroke_width(&edge.style, options);
+    let mut paint = paint.clone();
+    paint.set_stroke_width(stroke_width);
+    if edge.style == EdgeStyle::Dotted {
+        let dash = stroke_width * 2.0;
+        paint.set_path_effect(PathEffect::dash(&[dash, dash * 1.5], 0.0));
+    }
+    let commands = match options.edge_curve {
+        EdgeCurve::Straight => edge_path_commands(&points, options.corner_rounding),
+        EdgeCurve::Rounded => curved_edge_path_commands(&points, options.edge_curve_radius),
+    };
     let mut builder = PathBuilder::new();
-    let start = transform_point(edge.points[0], transform);
-    builder.move_to(start);
-    for point in &edge.points[1..] {
-        let p = transform_point(*point, transform);
-        builder.line_to(p);
+    for command in commands {
+        match command {
+            EdgePathCommand::MoveTo(p) => {
+                builder.move_to(p);
+            }
+            EdgePathCommand::LineTo(p) => {
+                builder.line_to(p);
+            }
+            EdgePathCommand::ConicTo {
+                control,
+                end,
+                weight,
+            } => {
+                builder.conic_to(control, end, weight);
+            }
+            EdgePathCommand::QuadTo { control, end } => {
+                builder.quad_to(control, end);
+            }
+        }
     }
     let path = builder.detach();
-    canvas.draw_path(&path, paint);
+    let (mut start_color, mut end_color) = (paint.color(), paint.color());
+    if let Some((from, to)) = options.edge_gradient.filter(|_| options.highlight.is_none())
+        && let (Some(&first), Some(&last)) = (points.first(), points.last())
+    {
+        (start_color, end_color) = (to_color(from), to_color(to));
+        let colors = [start_color, end_color];
+        paint.set_shader(gradient_shader::linear(
+            (first, last),
+            colors.as_slice(),
+            None,
+            TileMode::Clamp,
+            None,
+            None,
+        ));
+    }
+    canvas.draw_path(&path, &paint);
+
+    let background = to_color(options.theme.background);
+    if end_head {
+        let end = points.len().saturating_sub(1);
+        let mark = match edge.arrow {
+            EdgeArrow::Cross => EndMark::Cross,
+            EdgeArrow::Circle => EndMark::Circle,
+            _ => EndMark::Head,
+        };
+        draw_arrowhead(canvas, &points, end, mark, stroke_width, (end_color, background));
+    }
+    if start_head {
+        let colors = (start_color, background);
+        draw_arrowhead(canvas, &points, 0, EndMark::Head, stroke_width, colors);
+    }
+}
+
+pub fn edge_stroke_width(style: &EdgeStyle, options: &SkiaRenderOptions) -> f32 {
+    match style {
+        EdgeStyle::Thick => options.stroke_width * 2.0,
+        _ => options.stroke_width,
+    }
+}
+
+// `show_arrows: false` suppresses every head regardless of the edge's own arrow kind.
+pub fn arrowhead_ends(arrow: &EdgeArrow, options: &SkiaRenderOptions) -> (bool, bool) {
+    (
+        options.show_arrows && arrow.has_start_head(),
+        options.show_arrows && arrow.has_end_head(),
+    )
+}
+
+// Moves each endpoint back along its end segment, never past the segment's other point.
+fn pull_back_endpoints(points: &[(f32, f32)], start_gap: f32, end_gap: f32) -> Vec<(f32, f32)> {
+    let mut points = points.to_vec();
+    if points.len() < 2 {
+        return points;
+    }
+    let last = points.len() - 1;
+    points[0] = step_toward(points[0], points[1], start_gap);
+    points[last] = step_toward(points[last], points[last - 1], end_gap);
+    points
+}
+
+fn step_toward(from: (f32, f32), to: (f32, f32), amount: f32) -> (f32, f32) {
+    let len = distance(from, to);
+    if amount <= 0.0 || len <= f32::EPSILON {
+        return from;
+    }
+    let t = (amount / len).min(1.0);
+    (from.0 + (to.0 - from.0) * t, from.1 + (to.1 - from.1) * t)
+}
+
+pub fn edge_path_commands(points: &[(f32, f32)], corner_rounding: f32) -> Vec<EdgePathCommand> {
+    let mut commands = Vec::new();
+    let Some(&first) = points.first() else {
+        return commands;
+    };
+    commands.push(EdgePathCommand::MoveTo(first));
+    if points.len() < 2 {
+        return commands;
+    }
 
-    draw_arrowhead(canvas, edge, transform, _options);
+    for i in 1..points.len() - 1 {
+        let corner = points[i];
+        let Some(cut) = corner_cut(points[i - 1], corner, points[i + 1], corner_rounding) else {
+            commands.push(EdgePathCommand::LineTo(corner));
+            continue;
+        };
+        // A conic with weight cos(turn / 2) traces a circular arc between the tangent points.
+        let weight = ((1.0 + cut.cos_turn) / 2.0).sqrt();
+        commands.push(EdgePathCommand::LineTo(cut.entry));
+        commands.push(EdgePathCommand::ConicTo {
+            control: corner,
+            end: cut.exit,
+            weight,
+        });
+    }
+
+    commands.push(EdgePathCommand::LineTo(points[points.len() - 1]));
+    commands
+}
+
+// Like `edge_path_commands`, but each bend becomes a quadratic bezier pulled toward the
+// corner, which reads softer than an arc on shallow turns.
+pub fn curved_edge_path_commands(points: &[(f32, f32)], radius: f32) -> Vec<EdgePathCommand> {
+    let mut commands = Vec::new();
+    let Some(&first) = points.first() else {
+        return commands;
+    };
+    commands.push(EdgePathCommand::MoveTo(first));
+    if points.len() < 2 {
+        return commands;
+    }
+
+    for i in 1..points.len() - 1 {
+        let corner = points[i];
+        match corner_cut(points[i - 1], corner, points[i + 1], radius) {
+            Some(cut) => {
+                commands.push(EdgePathCommand::LineTo(cut.entry));
+                commands.push(EdgePathCommand::QuadTo {
+                    control: corner,
+                    end: cut.exit,
+                });
+            }
+            None => commands.push(EdgePathCommand::LineTo(corner)),
+        }
+    }
+
+    commands.push(EdgePathCommand::LineTo(points[points.len() - 1]));
+    commands
+}
+
+struct CornerCut {
+    entry: (f32, f32),
+    exit: (f32, f32),
+    cos_turn: f32,
+}
+
+// Tangent points `radius` before and after a bend (capped at half of either segment);
+// `None` when the bend is too short or too straight to round.
+fn corner_cut(
+    prev: (f32, f32),
+    corner: (f32, f32),
+    next: (f32, f32),
+    radius: f32,
+) -> Option<CornerCut> {
+    let in_len = distance(prev, corner);
+    let out_len = distance(corner, next);
+    let cut = radius.min(in_len / 2.0).min(out_len / 2.0);
+    if cut <= 0.01 {
+        return None;
+    }
+    let in_dir = ((corner.0 - prev.0) / in_len, (corner.1 - prev.1) / in_len);
+    let out_dir = ((next.0 - corner.0) / out_len, (next.1 - corner.1) / out_len);
+    let cos_turn = (in_dir.0 * out_dir.0 + in_dir.1 * out_dir.1).clamp(-1.0, 1.0);
+    if cos_turn > 0.9999 {
+        return None;
+    }
+    let entry = (corner.0 - in_dir.0 * cut, corner.1 - in_dir.1 * cut);
+    let exit = (corner.0 + out_dir.0 * cut, corner.1 + out_dir.1 * cut);
+    Some(CornerCut {
+        entry,
+        exit,
+        cos_turn,
+    })
+}
+
+fn distance(a: (f32, f32), b: (f32, f32)) -> f32 {
+    let dx = b.0 - a.0;
+    let dy = b.1 - a.1;
+    (dx * dx + dy * dy).sqrt()
 }
 
 fn draw_edge_label(
@@ -587,53 +1662,14 @@ fn draw_edge_label(
         .map(|&point| transform_point(point, transform))
         .collect();
     let (text_width, text_bounds) = font.measure_str(label, Some(text_paint));
-    let mut best_segment = None;
-    let mut best_len = 0.0f32;
-    let mut best_is_horizontal = false;
-    for segment in points.windows(2) {
-        let start = segment[0];
-        let end = segment[1];
-        let dx = (end.x - start.x).abs();
-        let dy = (end.y - start.y).abs();
-        let segment_len = segment_length(start, end);
-        if segment_len <= f32::EPSILON {
-            continue;
-        }
-        let is_horizontal = dy <= dx * 0.3;
-        if edge.is_cross && is_horizontal && segment_len >= text_width + 8.0 {
-            best_segment = Some((start, end));
-            break;
-        }
-        if !edge.is_cross {
-            let use_segment = if is_horizontal && !best_is_horizontal {
-                true
-            } else if is_horizontal == best_is_horizontal {
-                segment_len > best_len
-            } else {
-                false
-            };
-            if use_segment {
-                best_len = segment_len;
-                best_is_horizontal = is_horizontal;
-                best_segment = Some((start, end));
-            }
-        }
-    }
-
-    let (segment_start, segment_end, direction) = if let Some((start, end)) = best_segment {
-        let dir = Point::new(end.x - start.x, end.y - start.y);
-        (start, end, dir)
-    } else {
+    let canvas_points: Vec<(f32, f32)> = points.iter().map(|point| (point.x, point.y)).collect();
+    let Some(index) = label_segment(&canvas_points, edge.is_cross, text_width + 8.0) else {
         return;
     };
+    let (segment_start, segment_end) = (points[index], points[index + 1]);
 
-    let mut normal = Point::new(0.0, -1.0);
-    if direction.x.abs() < direction.y.abs() {
-        normal = Point::new(1.0, 0.0);
-    }
     let offset = options.stroke_width * 4.0 + 6.0;
     let text_height = text_bounds.height().max(options.font_size);
-    let normal = normalize_point(normal);
     let step = text_height + options.stroke_width * 2.0 + 4.0;
     let max_steps = 6;
 
@@ -658,22 +1694,7 @@ fn draw_edge_label(
         }
         let (center, rect) =
             chosen.unwrap_or((base_center, centered_rect(base_center, text_width, text_height)));
-        let mut label_bg = Paint::default();
-        label_bg.set_style(PaintStyle::Fill);
-        label_bg.set_color(Color::from_argb(
-            options.background.3,
-            options.background.0,
-            options.background.1,
-            options.background.2,
-        ));
-        let pad = 4.0;
-        let bg_rect = skia_safe::Rect::from_xywh(
-            rect.left() - pad,
-            rect.top() - pad,
-            rect.width() + pad * 2.0,
-            rect.height() + pad * 2.0,
-        );
-        canvas.draw_rect(bg_rect, &label_bg);
+        draw_label_background(canvas, rect, options);
 
         let text_x = snap_point(center.x - text_width / 2.0);
         let text_y = snap_point(center.y + text_height / 2.0);
@@ -682,86 +1703,41 @@ fn draw_edge_label(
         return;
     }
 
-    let mut candidate_centers = Vec::new();
-    if edge.is_cross {
-        let ts = [0.5f32, 0.35, 0.65, 0.2, 0.8];
-        for t in ts {
-            let base = Point::new(
-                segment_start.x + (segment_end.x - segment_start.x) * t,
-                segment_start.y + (segment_end.y - segment_start.y) * t,
-            );
-            candidate_centers.push(base);
-        }
-    } else {
-        let base = Point::new(
-            (segment_start.x + segment_end.x) / 2.0,
-            (segment_start.y + segment_end.y) / 2.0,
-        );
-        candidate_centers.push(base);
-    }
-
-    let mut chosen = None;
-    for base_center in &candidate_centers {
-        let base_center = *base_center;
-        let mut found = None;
-        if edge.is_cross {
-            for offset_idx in 0..=max_steps {
-                let offset = offset + offset_idx as f32 * step;
-                let center = Point::new(
-                    base_center.x + normal.x * offset,
-                    base_center.y + normal.y * offset,
-                );
-                let rect = centered_rect(center, text_width, text_height);
-                if !rects_intersect_any(rect, avoid_rects) && !rects_intersect_any(rect, placed) {
-                    found = Some((center, rect));
-                    break;
-                }
-            }
+    let candidates = edge_label_candidates(
+        (segment_start.x, segment_start.y),
+        (segment_end.x, segment_end.y),
+        &edge.label_position,
+        edge.is_cross,
+        offset,
+        step,
+    );
+    let chosen = candidates.iter().find_map(|&(x, y)| {
+        let center = Point::new(x, y);
+        let rect = centered_rect(center, text_width, text_height);
+        if !rects_intersect_any(rect, avoid_rects) && !rects_intersect_any(rect, placed) {
+            Some((center, rect))
         } else {
-            for offset_idx in 0..=max_steps * 2 {
-                let k = (offset_idx + 1) / 2;
-                let sign = if offset_idx == 0 {
-                    0.0
-                } else if offset_idx % 2 == 1 {
-                    1.0
-                } else {
-                    -1.0
-                };
-                let offset = sign * k as f32 * step + offset;
-                let center = Point::new(
-                    base_center.x + normal.x * offset,
-                    base_center.y + normal.y * offset,
-                );
-                let rect = centered_rect(center, text_width, text_height);
-                if !rects_intersect_any(rect, avoid_rects) && !rects_intersect_any(rect, placed) {
-                    found = Some((center, rect));
-                    break;
-                }
-            }
-        }
-        if found.is_some() {
-            chosen = found;
-            break;
+            None
         }
-    }
+    });
 
-    let fallback_center = candidate_centers
-        .first()
-        .copied()
-        .unwrap_or(Point::new(
-            (segment_start.x + segment_end.x) / 2.0,
-            (segment_start.y + segment_end.y) / 2.0,
-        ));
+    let fallback_center = Point::new(
+        (segment_start.x + segment_end.x) / 2.0,
+        (segment_start.y + segment_end.y) / 2.0,
+    );
     let (center, rect) =
         chosen.unwrap_or((fallback_center, centered_rect(fallback_center, text_width, text_height)));
-    let mut label_bg = Paint::default();
-    label_bg.set_style(PaintStyle::Fill);
-    label_bg.set_color(Color::from_argb(
-        options.background.3,
-        options.background.0,
-        options.background.1,
-        options.background.2,
-    ));
+    draw_label_background(canvas, rect, options);
+
+    let text_x = snap_point(center.x - text_width / 2.0);
+    let text_y = snap_point(center.y + text_height / 2.0);
+    canvas.draw_str(label, (text_x, text_y), font, text_paint);
+    placed.push(rect);
+}
+
+// Hides the edge line behind a label. A fully transparent background would hide nothing,
+// so the label gets a translucent halo in the node fill color instead.
+fn draw_label_background(canvas: &Canvas, rect: skia_safe::Rect, options: &SkiaRenderOptions) {
     let pad = 4.0;
     let bg_rect = skia_safe::Rect::from_xywh(
         rect.left() - pad,
@@ -769,48 +1745,147 @@ fn draw_edge_label(
         rect.width() + pad * 2.0,
         rect.height() + pad * 2.0,
     );
-    canvas.draw_rect(bg_rect, &label_bg);
+    let mut label_bg = Paint::default();
+    label_bg.set_style(PaintStyle::Fill);
+    let background = options.theme.background;
+    if background.3 == 0 {
+        let SkiaColor(r, g, b, _) = options.theme.node_fill;
+        label_bg.set_color(Color::from_argb(160, r, g, b));
+        label_bg.set_anti_alias(true);
+        canvas.draw_round_rect(bg_rect, pad, pad, &label_bg);
+    } else {
+        label_bg.set_color(to_color(background));
+        canvas.draw_rect(bg_rect, &label_bg);
+    }
+}
 
-    let text_x = snap_point(center.x - text_width / 2.0);
-    let text_y = snap_point(center.y + text_height / 2.0);
-    canvas.draw_str(label, (text_x, text_y), font, text_paint);
-    placed.push(rect);
+pub fn edge_label_candidates(
+    start: (f32, f32),
+    end: (f32, f32),
+    position: &EdgeLabelPosition,
+    is_cross: bool,
+    offset: f32,
+    step: f32,
+) -> Vec<(f32, f32)> {
+    const MAX_STEPS: usize = 6;
+    // Positive offsets go "above": up for horizontal segments, right for vertical ones.
+    let normal = if (end.0 - start.0).abs() < (end.1 - start.1).abs() {
+        (1.0, 0.0)
+    } else {
+        (0.0, -1.0)
+    };
+    let ts: &[f32] = if is_cross {
+        &[0.5, 0.35, 0.65, 0.2, 0.8]
+    } else {
+        &[0.5]
+    };
+
+    let outward = |base: f32| (0..=MAX_STEPS).map(move |k| base + k as f32 * step);
+    let alternating = |base: f32| {
+        (0..=MAX_STEPS * 2).map(move |idx| {
+            let k = idx.div_ceil(2);
+            let sign = if idx == 0 {
+                0.0
+            } else if idx % 2 == 1 {
+                1.0
+            } else {
+                -1.0
+            };
+            base + sign * k as f32 * step
+        })
+    };
+    let offsets: Vec<f32> = match (position, is_cross) {
+        (EdgeLabelPosition::Auto, true) => outward(offset).collect(),
+        (EdgeLabelPosition::Auto, false) => alternating(offset).collect(),
+        (EdgeLabelPosition::Above, _) => outward(offset).chain(outward(offset).map(|o| -o)).collect(),
+        (EdgeLabelPosition::Below, _) => outward(offset).map(|o| -o).chain(outward(offset)).collect(),
+        (EdgeLabelPosition::Inline, _) => alternating(0.0).collect(),
+    };
+
+    let mut candidates = Vec::with_capacity(ts.len() * offsets.len());
+    for &t in ts {
+        let base = (
+            start.0 + (end.0 - start.0) * t,
+            start.1 + (end.1 - start.1) * t,
+        );
+        for &o in &offsets {
+            candidates.push((base.0 + normal.0 * o, base.1 + normal.1 * o));
+        }
+    }
+    candidates
 }
 
-fn segment_length(start: Point, end: Point) -> f32 {
-    let dx = end.x - start.x;
-    let dy = end.y - start.y;
-    (dx * dx + dy * dy).sqrt()
+// What sits at an edge end: a filled head, or the `--x` cross and `--o` open circle.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum EndMark {
+    Head,
+    Cross,
+    Circle,
 }
 
-fn draw_arrowhead(canvas: &Canvas, edge: &LayoutEdge, transform: &Transform, options: &SkiaRenderOptions) {
-    if edge.points.len() < 2 {
+// `tip` is the index of the (canvas-space) endpoint the head points at: 0 or the last point.
+// `colors` is the mark's color and the background an open circle is filled with.
+fn draw_arrowhead(
+    canvas: &Canvas,
+    points: &[(f32, f32)],
+    tip: usize,
+    mark: EndMark,
+    stroke_width: f32,
+    (color, background): (Color, Color),
+) {
+    if points.len() < 2 {
         return;
     }
-    let end = transform_point(*edge.points.last().unwrap(), transform);
-    let prev = transform_point(edge.points[edge.points.len() - 2], transform);
+    let prev_index = if tip == 0 { 1 } else { tip - 1 };
+    let end = Point::new(points[tip].0, points[tip].1);
+    let prev = Point::new(points[prev_index].0, points[prev_index].1);
     let dir = Point::new(end.x - prev.x, end.y - prev.y);
     let len = (dir.x * dir.x + dir.y * dir.y).sqrt().max(1.0);
     let ux = dir.x / len;
     let uy = dir.y / len;
-    let arrow_len = options.stroke_width * 6.0;
-    let arrow_w = options.stroke_width * 3.0;
-
-    let tip = end;
-    let base = Point::new(end.x - ux * arrow_len, end.y - uy * arrow_len);
-    let left = Point::new(base.x + -uy * arrow_w, base.y + ux * arrow_w);
-    let right = Point::new(base.x + uy * arrow_w, base.y + -ux * arrow_w);
+    let arrow_len = stroke_width * 6.0;
+    let arrow_w = stroke_width * 3.0;
 
     let mut paint = Paint::default();
     paint.set_style(PaintStyle::Fill);
-    paint.set_color(Color::BLACK);
+    paint.set_color(color);
     paint.set_anti_alias(true);
 
-    let mut builder = PathBuilder::new();
-    builder.move_to(tip);
-    builder.line_to(left);
-    builder.line_to(right);
-    builder.close();
-    let path = builder.detach();
-    canvas.draw_path(&path, &paint);
+    match mark {
+        EndMark::Head => {
+            let base = Point::new(end.x - ux * arrow_len, end.y - uy * arrow_len);
+            let left = Point::new(base.x + -uy * arrow_w, base.y + ux * arrow_w);
+            let right = Point::new(base.x + uy * arrow_w, base.y + -ux * arrow_w);
+            let mut builder = PathBuilder::new();
+            builder.move_to(end);
+            builder.line_to(left);
+            builder.line_to(right);
+            builder.close();
+            let path = builder.detach();
+            canvas.draw_path(&path, &paint);
+        }
+        EndMark::Cross => {
+            // Two strokes at 45 degrees to the edge, crossing one head width before the tip.
+            let center = Point::new(end.x - ux * arrow_w, end.y - uy * arrow_w);
+            let arm = arrow_w / std::f32::consts::SQRT_2;
+            paint.set_style(PaintStyle::Stroke);
+            paint.set_stroke_width(stroke_width);
+            for (dx, dy) in [(ux - uy, uy + ux), (ux + uy, uy - ux)] {
+                let from = Point::new(center.x - dx * arm, center.y - dy * arm);
+                let to = Point::new(center.x + dx * arm, center.y + dy * arm);
+                canvas.draw_line(from, to, &paint);
+            }
+        }
+        EndMark::Circle => {
+            // Filled with the background first so the edge line doesn't show through.
+            let radius = arrow_w * 0.75;
+            let center = Point::new(end.x - ux * radius, end.y - uy * radius);
+            paint.set_color(background);
+            canvas.draw_circle(center, radius, &paint);
+            paint.set_color(color);
+            paint.set_style(PaintStyle::Stroke);
+            paint.set_stroke_width(stroke_width);
+            canvas.draw_circle(center, radius, &paint);
+        }
+    }
 }
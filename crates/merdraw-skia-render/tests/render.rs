@@ -1,6 +1,13 @@
 use merdraw_layout::{layout_flowchart, LayoutStyle};
-use merdraw_parser::parse_flowchart;
-use merdraw_skia_render::{render_to_bytes, ImageFormat, SkiaRenderOptions, SkiaRenderError};
+use merdraw_parser::{parse_flowchart, EdgeArrow, EdgeLabelPosition, EdgeStyle, NodeShape, Rgba};
+use merdraw_skia_render::{
+    arrowhead_ends, curved_edge_path_commands, diagram_viewport, edge_draw_passes, edge_junctions,
+    edge_label_candidates, edge_path_commands, edge_stroke_paint, edge_stroke_width,
+    footer_band_height, footer_origin, layout_flowchart_skia, overview_size, plan_page_tiles,
+    render_node_icon, render_overview, render_page_tiles, render_to_bytes, source_group_colors,
+    subgraph_frame_paint, EdgeCurve, EdgeJoin, EdgePathCommand, EdgeRouting, FontEdging, FrameStyle,
+    ImageFormat, SkiaColor, SkiaLayoutOptions, SkiaRenderError, SkiaRenderOptions, Theme,
+};
 
 #[test]
 fn encodes_png() {
@@ -31,3 +38,1119 @@ fn renders_labels_without_error() {
         .expect("render failed");
     assert!(bytes.starts_with(b"\x89PNG"));
 }
+
+#[test]
+fn subgraph_frame_coverage_is_stable_across_dpr() {
+    let graph = parse_flowchart("flowchart TB\nsubgraph g[G]\nA\nend\n").expect("parse failed");
+    // Device pixels the frame's two side strokes cover on the canvas's middle row.
+    let frame_row_coverage = |device_pixel_ratio: f32| {
+        let options = SkiaRenderOptions {
+            width: 300,
+            height: 200,
+            device_pixel_ratio,
+            subgraph_stroke_width: 3.0,
+            subgraph_frame_style: FrameStyle::Solid,
+            theme: Theme {
+                subgraph_border: SkiaColor(255, 0, 0, 255),
+                ..Theme::default()
+            },
+            ..SkiaRenderOptions::default()
+        };
+        let layout = layout_flowchart_skia(&graph, &options, &SkiaLayoutOptions::default())
+            .expect("layout failed");
+        let bytes = render_to_bytes(&layout, ImageFormat::Png, &options).expect("png render failed");
+        let (width, pixels) = decode_png_pixels(&bytes);
+        let middle = pixels.len() / 4 / width / 2;
+        pixels[middle * width * 4..(middle + 1) * width * 4]
+            .chunks(4)
+            .filter(|px| px[0] > 200 && px[1] < 60 && px[2] < 60)
+            .count()
+    };
+    let base = frame_row_coverage(1.0);
+    let retina = frame_row_coverage(2.0);
+    assert!(base > 0, "frame was not drawn");
+    assert!(base.abs_diff(retina) <= 2, "dpr 1 covers {base}px, dpr 2 covers {retina}px");
+}
+
+#[test]
+fn rounded_corners_keep_edge_endpoints() {
+    let points = [(0.0, 0.0), (0.0, 40.0), (60.0, 40.0), (60.0, 100.0)];
+    let commands = edge_path_commands(&points, 8.0);
+    assert_eq!(commands.first(), Some(&EdgePathCommand::MoveTo((0.0, 0.0))));
+    assert_eq!(commands.last(), Some(&EdgePathCommand::LineTo((60.0, 100.0))));
+    let arcs = commands
+        .iter()
+        .filter(|command| matches!(command, EdgePathCommand::ConicTo { .. }))
+        .count();
+    assert_eq!(arcs, 2);
+}
+
+#[test]
+fn skia_layout_keeps_subgraph_members_contiguous() {
+    let graph = parse_flowchart(
+        "flowchart TB\nA-->X\nA-->S1\nA-->Y\nA-->S2\nsubgraph grp\nS1\nS2\nend\n",
+    )
+    .expect("parse failed");
+    let layout = layout_flowchart_skia(
+        &graph,
+        &SkiaRenderOptions::default(),
+        &SkiaLayoutOptions::default(),
+    )
+    .expect("layout failed");
+
+    let members = &graph.subgraphs[0].nodes;
+    let max_layer = layout.nodes.iter().map(|n| n.layer).max().unwrap_or(0);
+    for layer in 0..=max_layer {
+        let orders: Vec<usize> = layout
+            .nodes
+            .iter()
+            .filter(|n| n.layer == layer && members.contains(&n.id))
+            .map(|n| n.order)
+            .collect();
+        if let (Some(min), Some(max)) = (orders.iter().min(), orders.iter().max()) {
+            assert_eq!(max - min + 1, orders.len(), "layer {layer} splits the subgraph");
+        }
+    }
+}
+
+#[test]
+fn skia_layout_packs_disconnected_components() {
+    let options = SkiaLayoutOptions {
+        uniform_node_width: Some(0.0),
+        ..SkiaLayoutOptions::default()
+    };
+    let layout = |source: &str| {
+        let graph = parse_flowchart(source).expect("parse failed");
+        layout_flowchart_skia(&graph, &SkiaRenderOptions::default(), &options)
+            .expect("layout failed")
+    };
+    let first = layout("flowchart LR\nA-->B\n");
+    let second = layout("flowchart LR\nC[A much wider label]-->D\n");
+    let packed = layout("flowchart LR\nA-->B\nC[A much wider label]-->D\n");
+    assert!(packed.width < first.width + second.width, "{} wide", packed.width);
+
+    let ids: Vec<&str> = packed.nodes.iter().map(|node| node.id.as_str()).collect();
+    assert_eq!(ids, ["A", "B", "C", "D"]);
+    let edges: Vec<(&str, &str)> =
+        packed.edges.iter().map(|edge| (edge.from.as_str(), edge.to.as_str())).collect();
+    assert_eq!(edges, [("A", "B"), ("C", "D")]);
+    let widths: Vec<f32> = packed.nodes.iter().map(|node| node.width).collect();
+    assert!(widths.iter().all(|width| *width == widths[2]), "{widths:?}");
+}
+
+#[test]
+fn label_position_hint_picks_side_of_segment() {
+    let start = (0.0, 50.0);
+    let end = (100.0, 50.0);
+    let first = |position: EdgeLabelPosition| {
+        edge_label_candidates(start, end, &position, false, 10.0, 20.0)[0]
+    };
+
+    let below = first(EdgeLabelPosition::Below);
+    assert!(below.1 > 50.0);
+    assert_eq!(below.0, 50.0);
+
+    let above = first(EdgeLabelPosition::Above);
+    assert!(above.1 < 50.0);
+
+    let inline = first(EdgeLabelPosition::Inline);
+    assert_eq!(inline, (50.0, 50.0));
+
+    let graph = parse_flowchart("flowchart LR\nA -->|go| B\nlinkStyle 0 label-position:below\n")
+        .expect("parse failed");
+    let layout = layout_flowchart(&graph, &LayoutStyle::default());
+    assert_eq!(layout.edges[0].label_position, EdgeLabelPosition::Below);
+    render_to_bytes(&layout, ImageFormat::Png, &SkiaRenderOptions::default())
+        .expect("render failed");
+}
+
+#[test]
+fn hexagon_reserves_room_for_angled_sides() {
+    let graph = parse_flowchart("flowchart TB\nA[Same label]\nB{{Same label}}\n")
+        .expect("parse failed");
+    let layout = layout_flowchart_skia(
+        &graph,
+        &SkiaRenderOptions::default(),
+        &SkiaLayoutOptions::default(),
+    )
+    .expect("layout failed");
+    let bracket = layout.nodes.iter().find(|n| n.id == "A").unwrap();
+    let hexagon = layout.nodes.iter().find(|n| n.id == "B").unwrap();
+    assert!(hexagon.width >= bracket.width + hexagon.height / 2.0 - 0.01);
+    assert_eq!(hexagon.height, bracket.height);
+}
+
+#[test]
+fn cylinder_label_clears_the_top_ellipse() {
+    let graph = parse_flowchart("flowchart TB\nA[Two<br>lines]\nB[(Two<br>lines)]\n")
+        .expect("parse failed");
+    // No vertical padding, so only the inset keeps the text off the rim.
+    let layout_options = SkiaLayoutOptions {
+        node_padding_y: 0.0,
+        ..SkiaLayoutOptions::default()
+    };
+    let options = SkiaRenderOptions {
+        width: 300,
+        height: 300,
+        theme: Theme {
+            node_text: SkiaColor(255, 0, 0, 255),
+            ..Theme::default()
+        },
+        ..SkiaRenderOptions::default()
+    };
+    let layout = layout_flowchart_skia(&graph, &options, &layout_options).expect("layout failed");
+    let bracket = layout.nodes.iter().find(|n| n.id == "A").unwrap();
+    let cylinder = layout.nodes.iter().find(|n| n.id == "B").unwrap();
+    assert!(cylinder.height > bracket.height);
+    assert!((cylinder.height - bracket.height * 4.0 / 3.0).abs() < 0.01);
+
+    let bytes = render_to_bytes(&layout, ImageFormat::Png, &options).expect("png render failed");
+    let (width, pixels) = decode_png_pixels(&bytes);
+    let (left, top, right, _) = diagram_viewport(&layout, &options);
+    let scale = (right - left) / layout.width;
+    let node_top = top + (cylinder.y - cylinder.height / 2.0) * scale;
+    let node_bottom = top + (cylinder.y + cylinder.height / 2.0) * scale;
+    let node_left = left + (cylinder.x - cylinder.width / 2.0) * scale;
+    let node_right = left + (cylinder.x + cylinder.width / 2.0) * scale;
+    // The rim ellipse fills the top quarter of the drawn cylinder.
+    let ellipse_bottom = node_top + (node_bottom - node_top) / 4.0;
+    let text_rows: Vec<f32> = pixels
+        .chunks(4)
+        .enumerate()
+        .filter(|(_, px)| px[0] > 200 && px[1] < 60 && px[2] < 60)
+        .map(|(index, _)| ((index % width) as f32, (index / width) as f32))
+        .filter(|&(column, row)| {
+            (node_left..=node_right).contains(&column) && (node_top..=node_bottom).contains(&row)
+        })
+        .map(|(_, row)| row)
+        .collect();
+    assert!(!text_rows.is_empty(), "cylinder label was not drawn");
+    assert!(
+        text_rows.iter().all(|&row| row >= ellipse_bottom - 1.0),
+        "label reaches row {} above the ellipse bottom {ellipse_bottom}",
+        text_rows.iter().cloned().fold(f32::MAX, f32::min)
+    );
+}
+
+#[test]
+fn new_shapes_reserve_room_and_render() {
+    let graph = parse_flowchart(
+        "flowchart LR\nA[Same label]\nB([Same label])\nC[[Same label]]\nD[(Same label)]\nA-->B-->C-->D\n",
+    )
+    .expect("parse failed");
+    let layout = layout_flowchart_skia(
+        &graph,
+        &SkiaRenderOptions::default(),
+        &SkiaLayoutOptions::default(),
+    )
+    .expect("layout failed");
+    let node = |id: &str| layout.nodes.iter().find(|n| n.id == id).unwrap();
+    let bracket = node("A");
+    assert!(node("B").width > bracket.width);
+    assert!(node("C").width > bracket.width);
+    assert!(node("D").height > bracket.height);
+    assert_eq!(node("D").width, bracket.width);
+
+    let bytes = render_to_bytes(&layout, ImageFormat::Png, &SkiaRenderOptions::default())
+        .expect("render failed");
+    assert!(bytes.starts_with(b"\x89PNG"));
+}
+
+#[test]
+fn renders_single_node_icon() {
+    let options = SkiaRenderOptions {
+        width: 96,
+        height: 64,
+        padding: 4.0,
+        ..SkiaRenderOptions::default()
+    };
+    let bytes = render_node_icon(NodeShape::Diamond, "Decide", &options).expect("render failed");
+    assert!(bytes.starts_with(b"\x89PNG"));
+}
+
+#[test]
+fn marks_junction_where_edges_merge() {
+    let graph = parse_flowchart("flowchart TB\nA-->D\nB-->D\nC-->D\n").expect("parse failed");
+    let mut layout = layout_flowchart(&graph, &LayoutStyle::default());
+    let d = layout.nodes.iter().find(|n| n.id == "D").unwrap();
+    let shared = (d.x, d.y - d.height / 2.0);
+    for edge in &mut layout.edges {
+        *edge.points.last_mut().unwrap() = shared;
+    }
+    let junctions = edge_junctions(&layout, 1.0);
+    assert_eq!(junctions.len(), 1);
+    assert_eq!(junctions[0].point, shared);
+    assert_eq!(junctions[0].edges, vec![0, 1, 2]);
+
+    let options = SkiaRenderOptions {
+        junction_dots: true,
+        ..SkiaRenderOptions::default()
+    };
+    render_to_bytes(&layout, ImageFormat::Png, &options).expect("render failed");
+}
+
+#[test]
+fn page_tiles_cover_the_full_canvas() {
+    let graph = parse_flowchart("flowchart LR\nA-->B-->C-->D-->E-->F-->G-->H\n").expect("parse failed");
+    let layout = layout_flowchart(&graph, &LayoutStyle::default());
+    let options = SkiaRenderOptions {
+        width: 1800,
+        height: 300,
+        ..SkiaRenderOptions::default()
+    };
+    let page = (794, 1123);
+    let tiles = plan_page_tiles((options.width, options.height), page, 16);
+    assert!(tiles.len() > 1);
+    assert!(tiles.iter().all(|t| t.width <= page.0 && t.height <= page.1));
+
+    for x in (0..options.width).step_by(7) {
+        for y in (0..options.height).step_by(7) {
+            assert!(
+                tiles
+                    .iter()
+                    .any(|t| x >= t.x && x < t.x + t.width && y >= t.y && y < t.y + t.height),
+                "pixel ({x}, {y}) not covered"
+            );
+        }
+    }
+    let right = tiles.iter().map(|t| t.x + t.width).max().unwrap();
+    let bottom = tiles.iter().map(|t| t.y + t.height).max().unwrap();
+    assert_eq!((right, bottom), (options.width, options.height));
+
+    let pages = render_page_tiles(&layout, ImageFormat::Png, &options, page, 16)
+        .expect("render failed");
+    assert_eq!(pages.len(), tiles.len());
+    assert!(pages.iter().all(|bytes| bytes.starts_with(b"\x89PNG")));
+}
+
+#[test]
+fn colors_cross_edges_by_source_group() {
+    let graph = parse_flowchart(
+        "flowchart TB\nsubgraph one\nA\nend\nsubgraph two\nB\nend\nsubgraph three\nC\nend\nA-->C\nB-->C\n",
+    )
+    .expect("parse failed");
+    let layout = layout_flowchart(&graph, &LayoutStyle::default());
+    let from_a = layout.edges.iter().find(|e| e.from == "A").unwrap();
+    let from_b = layout.edges.iter().find(|e| e.from == "B").unwrap();
+    assert!(from_a.is_cross && from_b.is_cross);
+    assert_eq!(from_a.source_group.as_deref(), Some("one"));
+    assert_eq!(from_b.source_group.as_deref(), Some("two"));
+
+    let colors = source_group_colors(&layout);
+    assert_ne!(colors["one"], colors["two"]);
+
+    let options = SkiaRenderOptions {
+        color_cross_edges_by_source: true,
+        ..SkiaRenderOptions::default()
+    };
+    render_to_bytes(&layout, ImageFormat::Png, &options).expect("render failed");
+}
+
+#[test]
+fn footer_sits_in_reserved_corner() {
+    let graph = parse_flowchart("flowchart TB\nA-->B-->C\n").expect("parse failed");
+    let layout = layout_flowchart(&graph, &LayoutStyle::default());
+    let options = SkiaRenderOptions {
+        width: 400,
+        height: 300,
+        footer: Some("Generated by merdraw".to_string()),
+        ..SkiaRenderOptions::default()
+    };
+    let band = footer_band_height(&options);
+    assert!(band > 0.0);
+
+    let (_, _, _, diagram_bottom) = diagram_viewport(&layout, &options);
+    assert!(diagram_bottom <= 300.0 - band);
+
+    let text_width = 120.0;
+    let (x, y) = footer_origin(&options, text_width);
+    assert!(x >= 200.0 && x + text_width <= 400.0);
+    assert!(y > 300.0 - band && y <= 300.0);
+
+    // Ink the diagram blue so the gray footer is the only neutral, non-white ink.
+    let options = SkiaRenderOptions {
+        theme: Theme {
+            node_stroke: SkiaColor(0, 0, 200, 255),
+            node_text: SkiaColor(0, 0, 200, 255),
+            edge: SkiaColor(0, 0, 200, 255),
+            ..Theme::default()
+        },
+        ..options
+    };
+    let bytes = render_to_bytes(&layout, ImageFormat::Png, &options).expect("render failed");
+    let (width, pixels) = decode_png_pixels(&bytes);
+    let band_top = 300.0 - band;
+    let (mut footer_ink, mut diagram_ink) = (Vec::new(), Vec::new());
+    for (index, px) in pixels.chunks(4).enumerate() {
+        let (x, y) = ((index % width) as f32, (index / width) as f32);
+        if px[0] == px[1] && px[1] == px[2] && px[0] < 230 {
+            footer_ink.push((x, y));
+        } else if px[..3] != [255, 255, 255] {
+            diagram_ink.push((x, y));
+        }
+    }
+    assert!(!footer_ink.is_empty(), "footer text was not drawn");
+    assert!(
+        footer_ink.iter().all(|&(x, y)| x >= 200.0 && y >= band_top),
+        "footer ink outside the bottom-right band"
+    );
+    assert!(!diagram_ink.is_empty(), "diagram was not drawn");
+    assert!(diagram_ink.iter().all(|&(_, y)| y < band_top), "diagram ink inside the footer band");
+}
+
+#[test]
+fn subgraph_frames_are_dashed_unless_configured_solid() {
+    let mut options = SkiaRenderOptions::default();
+    assert!(subgraph_frame_paint(&options, "outer").path_effect().is_some());
+
+    options
+        .subgraph_frame_styles
+        .insert("outer/inner".to_string(), FrameStyle::Solid);
+    assert!(subgraph_frame_paint(&options, "outer/inner").path_effect().is_none());
+    assert!(subgraph_frame_paint(&options, "outer").path_effect().is_some());
+
+    options.subgraph_frame_style = FrameStyle::Solid;
+    assert!(subgraph_frame_paint(&options, "outer").path_effect().is_none());
+}
+
+#[test]
+fn arrow_gap_pulls_tip_back_from_node_boundary() {
+    let graph = parse_flowchart("flowchart TB\nA-->B\n").expect("parse failed");
+    let layout = layout_flowchart(&graph, &LayoutStyle::default());
+    let edge = &layout.edges[0];
+    let target = layout.nodes.iter().find(|n| n.id == "B").unwrap();
+    let boundary = target.y - target.height / 2.0;
+    let tip = *edge.points.last().unwrap();
+    assert!((tip.1 - boundary).abs() < 0.5);
+
+    // Lowest device row of edge ink, which is the arrow tip for a TB edge.
+    let tip_row = |arrow_gap: f32, device_pixel_ratio: f32| {
+        let options = SkiaRenderOptions {
+            width: 200,
+            height: 300,
+            arrow_gap,
+            device_pixel_ratio,
+            theme: Theme {
+                edge: SkiaColor(0, 0, 255, 255),
+                ..Theme::default()
+            },
+            ..SkiaRenderOptions::default()
+        };
+        let bytes = render_to_bytes(&layout, ImageFormat::Png, &options).expect("png render failed");
+        let (width, pixels) = decode_png_pixels(&bytes);
+        let (left, _, right, _) = diagram_viewport(&layout, &options);
+        let row = pixels
+            .chunks(4)
+            .enumerate()
+            .filter(|(_, px)| px[2] > 200 && px[0] < 60)
+            .map(|(index, _)| index / width)
+            .max()
+            .expect("no edge ink");
+        (row as f32, (right - left) / layout.width)
+    };
+    // Both gaps keep the tip clear of the node stroke drawn over it.
+    for dpr in [1.0, 2.0] {
+        let (near, scale) = tip_row(4.0, dpr);
+        let (far, _) = tip_row(10.0, dpr);
+        let expected = 6.0 * scale * dpr;
+        assert!(
+            (near - far - expected).abs() <= 1.5,
+            "dpr {dpr}: tip moved {} device px, expected {expected}",
+            near - far
+        );
+    }
+}
+
+#[test]
+fn cross_and_circle_ends_differ_from_arrowheads() {
+    let graph = parse_flowchart("flowchart TB\nA-->B\n").expect("parse failed");
+    let layout = layout_flowchart(&graph, &LayoutStyle::default());
+    let options = SkiaRenderOptions {
+        width: 200,
+        height: 300,
+        stroke_width: 4.0,
+        arrow_gap: 10.0,
+        theme: Theme {
+            edge: SkiaColor(0, 0, 255, 255),
+            ..Theme::default()
+        },
+        ..SkiaRenderOptions::default()
+    };
+    let render = |arrow: EdgeArrow| {
+        let mut layout = layout.clone();
+        layout.edges[0].arrow = arrow;
+        let bytes =
+            render_to_bytes(&layout, ImageFormat::Png, &options).expect("png render failed");
+        decode_png_pixels(&bytes)
+    };
+    let blue = |px: &[u8]| px[2] > 200 && px[0] < 60;
+
+    // The tip in canvas pixels: the routed end, pulled back by the arrow gap.
+    let (left, top, right, _) = diagram_viewport(&layout, &options);
+    let scale = (right - left) / layout.width;
+    let end = *layout.edges[0].points.last().unwrap();
+    let tip = (
+        (left + end.0 * scale).round() as usize,
+        (top + (end.1 - options.arrow_gap) * scale).round() as usize,
+    );
+    let (width, head) = render(EdgeArrow::Forward);
+    // Probes one head width before the tip, on the axis and off to the side, with the
+    // default head width of three strokes.
+    let probes = |pixels: &[u8]| {
+        let at = |dx: usize, up: usize| blue(&pixels[((tip.1 - up) * width + tip.0 + dx) * 4..]);
+        (at(0, 12), at(5, 7))
+    };
+    assert_eq!(probes(&head), (true, false), "the head fills the axis but not beside it");
+    let (_, cross) = render(EdgeArrow::Cross);
+    assert_eq!(probes(&cross), (true, true), "the cross arms reach beside the axis");
+    let (_, circle) = render(EdgeArrow::Circle);
+    assert_eq!(probes(&circle), (false, false), "the open circle hides the line inside it");
+}
+
+#[test]
+fn renders_diamond_nodes_to_png() {
+    let graph = parse_flowchart("flowchart TB\nA{Decide}-->B((Done))\nA-->C{{Hex}}\n")
+        .expect("parse failed");
+    let layout = layout_flowchart_skia(
+        &graph,
+        &SkiaRenderOptions::default(),
+        &SkiaLayoutOptions::default(),
+    )
+    .expect("layout failed");
+    let diamond = layout.nodes.iter().find(|n| n.id == "A").unwrap();
+    assert_eq!(diamond.shape, NodeShape::Diamond);
+    let bytes = render_to_bytes(&layout, ImageFormat::Png, &SkiaRenderOptions::default())
+        .expect("render failed");
+    assert!(bytes.starts_with(b"\x89PNG"));
+}
+
+#[test]
+fn cross_group_edges_draw_after_frames() {
+    let graph = parse_flowchart(
+        "flowchart TB\nsubgraph left[Left]\nA-->B\nend\nsubgraph right[Right]\nC-->D\nend\nB-->C\n",
+    )
+    .expect("parse failed");
+    let layout = layout_flowchart(&graph, &LayoutStyle::default());
+    let (behind, over) = edge_draw_passes(&layout);
+
+    let index_of = |from: &str, to: &str| {
+        layout.edges.iter().position(|e| e.from == from && e.to == to).unwrap()
+    };
+    assert!(behind.contains(&index_of("A", "B")));
+    assert!(behind.contains(&index_of("C", "D")));
+    assert_eq!(over, vec![index_of("B", "C")]);
+    assert!(layout.edges[over[0]].is_cross);
+}
+
+#[test]
+fn renders_with_aliased_text() {
+    let options = SkiaRenderOptions {
+        font_edging: FontEdging::Aliased,
+        ..SkiaRenderOptions::default()
+    };
+    let graph = parse_flowchart("flowchart TB\nA[Alpha]-->|edge|B[Beta]\n").expect("parse failed");
+    let layout =
+        layout_flowchart_skia(&graph, &options, &SkiaLayoutOptions::default()).expect("layout failed");
+    let bytes = render_to_bytes(&layout, ImageFormat::Png, &options).expect("render failed");
+    assert!(bytes.starts_with(b"\x89PNG"));
+}
+
+#[test]
+fn show_arrows_false_suppresses_every_head() {
+    let options = SkiaRenderOptions {
+        show_arrows: false,
+        ..SkiaRenderOptions::default()
+    };
+    for arrow in EdgeArrow::ALL {
+        assert_eq!(arrowhead_ends(&arrow, &options), (false, false));
+    }
+    assert_eq!(
+        arrowhead_ends(&EdgeArrow::Both, &SkiaRenderOptions::default()),
+        (true, true)
+    );
+
+    let graph = parse_flowchart("flowchart TB\nA-->B\n").expect("parse failed");
+    let layout = layout_flowchart(&graph, &LayoutStyle::default());
+    let bytes = render_to_bytes(&layout, ImageFormat::Png, &options).expect("render failed");
+    assert!(bytes.starts_with(b"\x89PNG"));
+}
+
+#[test]
+fn parallel_edges_get_separate_paths() {
+    for source in [
+        "flowchart TB\nA-->|x|B\nA-->|y|B\n",
+        "flowchart LR\nA-->B\nB-->A\n",
+    ] {
+        let graph = parse_flowchart(source).expect("parse failed");
+        let layout = layout_flowchart_skia(
+            &graph,
+            &SkiaRenderOptions::default(),
+            &SkiaLayoutOptions::default(),
+        )
+        .expect("layout failed");
+        let first = &layout.edges[0].points;
+        let mut second = layout.edges[1].points.clone();
+        if layout.edges[1].from != layout.edges[0].from {
+            second.reverse();
+        }
+        assert_ne!(first, &second, "{source}");
+        let a = layout.nodes.iter().find(|n| n.id == "A").unwrap();
+        for edge in &layout.edges {
+            let start = if edge.from == "A" { edge.points[0] } else { *edge.points.last().unwrap() };
+            assert!((start.0 - a.x).abs() <= a.width / 2.0 + 0.01);
+            assert!((start.1 - a.y).abs() <= a.height / 2.0 + 0.01);
+        }
+    }
+
+    // Spread endpoints stay on the node boxes even when the bundle runs diagonally.
+    for routing in [EdgeRouting::Straight, EdgeRouting::Orthogonal] {
+        let graph = parse_flowchart("flowchart TB\nA-->B\nA-->|x|C\nA-->|y|C\n")
+            .expect("parse failed");
+        let layout_options = SkiaLayoutOptions {
+            routing,
+            ..SkiaLayoutOptions::default()
+        };
+        let layout = layout_flowchart_skia(&graph, &SkiaRenderOptions::default(), &layout_options)
+            .expect("layout failed");
+        let node = |id: &str| layout.nodes.iter().find(|n| n.id == id).unwrap();
+        let on_boundary = |id: &str, point: (f32, f32)| {
+            let node = node(id);
+            let reach = ((point.0 - node.x).abs() / (node.width / 2.0))
+                .max((point.1 - node.y).abs() / (node.height / 2.0));
+            (reach - 1.0).abs() < 0.01
+        };
+        let bundle: Vec<_> = layout.edges.iter().filter(|edge| edge.to == "C").collect();
+        assert_ne!(bundle[0].points, bundle[1].points, "{routing:?}");
+        for edge in bundle {
+            let (start, end) = (edge.points[0], *edge.points.last().unwrap());
+            assert!(on_boundary("A", start), "{routing:?}: start {start:?} off A");
+            assert!(on_boundary("C", end), "{routing:?}: end {end:?} off C");
+        }
+    }
+}
+
+#[test]
+fn orthogonal_routing_uses_axis_aligned_segments() {
+    for source in [
+        "flowchart TB\nA-->B\nA-->C\nB-->D\nC-->D\nA-->D\nD-->A\n",
+        "flowchart LR\nA-->B\nA-->C\nB-->D\nC-->D\nA-->|x|D\nA-->|y|D\n",
+    ] {
+        let graph = parse_flowchart(source).expect("parse failed");
+        let layout_options = SkiaLayoutOptions {
+            routing: EdgeRouting::Orthogonal,
+            ..SkiaLayoutOptions::default()
+        };
+        let layout = layout_flowchart_skia(&graph, &SkiaRenderOptions::default(), &layout_options)
+            .expect("layout failed");
+        for edge in &layout.edges {
+            assert!(edge.points.len() >= 2, "{source}");
+            for pair in edge.points.windows(2) {
+                let (a, b) = (pair[0], pair[1]);
+                assert!(
+                    (a.0 - b.0).abs() < 0.01 || (a.1 - b.1).abs() < 0.01,
+                    "{source}: {}->{} has a diagonal segment {:?}",
+                    edge.from,
+                    edge.to,
+                    edge.points
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn invisible_edges_are_left_out_of_draw_passes() {
+    let graph = parse_flowchart("flowchart TB\nA ~~~ B\nA-->C\n").expect("parse failed");
+    let layout = layout_flowchart(&graph, &LayoutStyle::default());
+    let (under, over) = edge_draw_passes(&layout);
+    let drawn: Vec<usize> = under.into_iter().chain(over).collect();
+    assert_eq!(drawn.len(), 1);
+    assert_eq!(layout.edges[drawn[0]].to, "C");
+    assert!(edge_junctions(&layout, 1.0).is_empty());
+}
+
+#[test]
+fn renders_dotted_and_thick_edges() {
+    let graph = parse_flowchart("flowchart TB\nA-.->B\nA==>C\n").expect("parse failed");
+    let layout = layout_flowchart(&graph, &LayoutStyle::default());
+    let options = SkiaRenderOptions::default();
+    let bytes = render_to_bytes(&layout, ImageFormat::Png, &options).expect("png render failed");
+    assert!(bytes.starts_with(b"\x89PNG"));
+    assert!(edge_stroke_width(&EdgeStyle::Thick, &options) > options.stroke_width);
+    assert_eq!(edge_stroke_width(&EdgeStyle::Dotted, &options), options.stroke_width);
+}
+
+#[test]
+fn oversized_surface_reports_its_dimensions() {
+    let graph = parse_flowchart("flowchart TB\nA-->B\n").expect("parse failed");
+    let layout = layout_flowchart(&graph, &LayoutStyle::default());
+    let options = SkiaRenderOptions {
+        width: 100_000,
+        height: 100_000,
+        ..SkiaRenderOptions::default()
+    };
+    match render_to_bytes(&layout, ImageFormat::Png, &options) {
+        Err(SkiaRenderError::SurfaceCreationFailed { width, height }) => {
+            assert_eq!((width, height), (100_000, 100_000));
+        }
+        other => panic!("expected SurfaceCreationFailed, got {:?}", other.map(|bytes| bytes.len())),
+    }
+}
+
+#[test]
+fn jpeg_past_its_dimension_limit_reports_an_encode_error() {
+    let graph = parse_flowchart("flowchart LR\nA-->B\n").expect("parse failed");
+    let layout = layout_flowchart(&graph, &LayoutStyle::default());
+    // JPEG tops out at 65,535 pixels a side, so the surface succeeds and the encoder fails.
+    let options = SkiaRenderOptions {
+        width: 70_000,
+        height: 8,
+        ..SkiaRenderOptions::default()
+    };
+    match render_to_bytes(&layout, ImageFormat::Jpeg { quality: 80 }, &options) {
+        Err(err @ SkiaRenderError::Encode("JPEG")) => {
+            assert_eq!(err.to_string(), "could not encode the rendered image as JPEG");
+        }
+        Err(SkiaRenderError::EncodeUnsupported("JPEG")) => {}
+        other => panic!("expected Encode, got {:?}", other.map(|bytes| bytes.len())),
+    }
+}
+
+#[test]
+fn overview_fits_within_max_px() {
+    let graph = parse_flowchart("flowchart LR\nA-->B-->C-->D-->E\nB-->F\n").expect("parse failed");
+    let layout = layout_flowchart(&graph, &LayoutStyle::default());
+    let (width, height) = overview_size(&layout, 96);
+    assert_eq!(width.max(height), 96);
+    assert!(width.min(height) >= 1 && width > height);
+    let bytes = render_overview(&layout, 96, &SkiaRenderOptions::default()).expect("overview failed");
+    assert!(bytes.starts_with(b"\x89PNG"));
+    if bytes.len() >= 24 {
+        let png_width = u32::from_be_bytes(bytes[16..20].try_into().unwrap());
+        let png_height = u32::from_be_bytes(bytes[20..24].try_into().unwrap());
+        assert_eq!((png_width, png_height), (width, height));
+    }
+}
+
+#[test]
+fn leading_glyph_widens_the_node_box() {
+    let options = SkiaRenderOptions::default();
+    let node_width = |source: &str| {
+        let graph = parse_flowchart(source).expect("parse failed");
+        let layout = layout_flowchart_skia(&graph, &options, &SkiaLayoutOptions::default())
+            .expect("layout failed");
+        layout.nodes[0].width
+    };
+    assert!(node_width("flowchart TB\nA[🚀 Launch]\n") > node_width("flowchart TB\nA[X Launch]\n"));
+
+    let graph = parse_flowchart("flowchart TB\nA[🚀 Launch]-->B[✅]\n").expect("parse failed");
+    let layout = layout_flowchart_skia(&graph, &options, &SkiaLayoutOptions::default())
+        .expect("layout failed");
+    let bytes = render_to_bytes(&layout, ImageFormat::Png, &options).expect("png render failed");
+    assert!(bytes.starts_with(b"\x89PNG"));
+}
+
+#[test]
+fn edge_join_sets_the_stroke_corner_style() {
+    let mut options = SkiaRenderOptions::default();
+    assert_eq!(edge_stroke_paint(&options).stroke_join(), skia_safe::paint::Join::Round);
+    for (join, expected) in [
+        (EdgeJoin::Miter, skia_safe::paint::Join::Miter),
+        (EdgeJoin::Bevel, skia_safe::paint::Join::Bevel),
+    ] {
+        options.edge_join = join;
+        assert_eq!(edge_stroke_paint(&options).stroke_join(), expected);
+    }
+
+    let graph = parse_flowchart("flowchart TB\nA-->B\nA-->C\nB-->D\nC-->D\n").expect("parse failed");
+    let options = SkiaRenderOptions {
+        width: 400,
+        height: 400,
+        stroke_width: 8.0,
+        theme: Theme {
+            edge: SkiaColor(0, 0, 255, 255),
+            ..Theme::default()
+        },
+        ..SkiaRenderOptions::default()
+    };
+    let layout_options = SkiaLayoutOptions {
+        routing: EdgeRouting::Orthogonal,
+        ..SkiaLayoutOptions::default()
+    };
+    let layout = layout_flowchart_skia(&graph, &options, &layout_options).expect("layout failed");
+    let (left, top, right, _) = diagram_viewport(&layout, &options);
+    let scale = (right - left) / layout.width;
+    let canvas_point = |(x, y): (f32, f32)| (left + x * scale, top + y * scale);
+
+    // The bend above C, with the diagonal pointing away from its inside; the bend under A
+    // has the straight A-->B edge running through it.
+    let (corner, outward) = layout
+        .edges
+        .iter()
+        .find(|edge| edge.from == "A" && edge.to == "C")
+        .expect("no edge from A to C")
+        .points
+        .windows(3)
+        .rev()
+        .find_map(|w| {
+            let (a, b, c) = (canvas_point(w[0]), canvas_point(w[1]), canvas_point(w[2]));
+            let inside = ((a.0 - b.0) + (c.0 - b.0), (a.1 - b.1) + (c.1 - b.1));
+            let square = ((a.0 - b.0) * (c.0 - b.0) + (a.1 - b.1) * (c.1 - b.1)).abs() < 1e-3;
+            (square && inside.0.abs() > 8.0 && inside.1.abs() > 8.0)
+                .then(|| (b, (-inside.0.signum(), -inside.1.signum())))
+        })
+        .expect("no right-angle bend");
+    let outer_corner_is_inked = |edge_join: EdgeJoin| {
+        let options = SkiaRenderOptions {
+            edge_join,
+            ..options.clone()
+        };
+        let bytes = render_to_bytes(&layout, ImageFormat::Png, &options).expect("png render failed");
+        let (width, pixels) = decode_png_pixels(&bytes);
+        // Past the round join's radius along the diagonal, but inside the miter's square.
+        let reach = options.stroke_width / 2.0 * 0.8;
+        let x = (corner.0 + outward.0 * reach) as usize;
+        let y = (corner.1 + outward.1 * reach) as usize;
+        let at = (y * width + x) * 4;
+        pixels[at + 2] > 200 && pixels[at] < 60
+    };
+    assert!(outer_corner_is_inked(EdgeJoin::Miter));
+    assert!(!outer_corner_is_inked(EdgeJoin::Round));
+}
+
+#[test]
+fn class_fill_reaches_the_layout_and_renders() {
+    let graph = parse_flowchart(
+        "flowchart TB\nclassDef important fill:#f00\nA-->B\nclass A important\n",
+    )
+    .expect("parse failed");
+    let options = SkiaRenderOptions::default();
+    let layout = layout_flowchart_skia(&graph, &options, &SkiaLayoutOptions::default())
+        .expect("layout failed");
+    let fill = |id: &str| layout.nodes.iter().find(|node| node.id == id).unwrap().style.fill;
+    assert_eq!(fill("A"), Some(Rgba(255, 0, 0, 255)));
+    assert_eq!(fill("B"), None);
+    let bytes = render_to_bytes(&layout, ImageFormat::Png, &options).expect("png render failed");
+    let (width, pixels) = decode_png_pixels(&bytes);
+
+    // Sample just inside each node's left side, clear of the stroke and the centered label.
+    let (left, top, right, _) = diagram_viewport(&layout, &options);
+    let scale = (right - left) / layout.width;
+    let interior = |id: &str| {
+        let node = layout.nodes.iter().find(|node| node.id == id).unwrap();
+        let x = (left + (node.x - node.width / 2.0) * scale + 4.0) as usize;
+        let y = (top + node.y * scale) as usize;
+        let at = (y * width + x) * 4;
+        [pixels[at], pixels[at + 1], pixels[at + 2]]
+    };
+    assert_eq!(interior("A"), [255, 0, 0]);
+    assert_eq!(interior("B"), [255, 255, 255]);
+}
+
+#[test]
+fn br_labels_stack_into_taller_nodes() {
+    let options = SkiaRenderOptions::default();
+    let node_size = |source: &str| {
+        let graph = parse_flowchart(source).expect("parse failed");
+        let layout = layout_flowchart_skia(&graph, &options, &SkiaLayoutOptions::default())
+            .expect("layout failed");
+        (layout.nodes[0].width, layout.nodes[0].height)
+    };
+    let (one_width, one_height) = node_size("flowchart TB\nA[First line]\n");
+    let (two_width, two_height) = node_size("flowchart TB\nA[First line<br/>Second]\n");
+    assert!(two_height > one_height);
+    assert_eq!(two_width, one_width);
+
+    let graph = parse_flowchart("flowchart TB\nA[First line<BR>Second line]-->B\n").expect("parse failed");
+    let layout = layout_flowchart_skia(&graph, &options, &SkiaLayoutOptions::default())
+        .expect("layout failed");
+    let bytes = render_to_bytes(&layout, ImageFormat::Png, &options).expect("png render failed");
+    assert!(bytes.starts_with(b"\x89PNG"));
+}
+
+#[test]
+fn line_spacing_grows_multiline_nodes() {
+    let graph = parse_flowchart("flowchart TB\nA[One<br>Two<br>Three]\nB[Single]\n").expect("parse failed");
+    let heights = |line_spacing: f32| {
+        let options = SkiaRenderOptions {
+            line_spacing,
+            ..SkiaRenderOptions::default()
+        };
+        let layout = layout_flowchart_skia(&graph, &options, &SkiaLayoutOptions::default())
+            .expect("layout failed");
+        (layout.nodes[0].height, layout.nodes[1].height)
+    };
+    let (tight, single) = heights(1.2);
+    let (loose, single_loose) = heights(2.0);
+    assert!(loose > tight);
+    assert_eq!(single, single_loose);
+}
+
+#[test]
+fn max_node_width_wraps_long_labels() {
+    let words = "wrap ".repeat(30);
+    let long = format!("{words}{}", "x".repeat(200 - words.len()));
+    let graph = parse_flowchart(&format!("flowchart TB\nA[{long}]-->B\n")).expect("parse failed");
+    let options = SkiaRenderOptions::default();
+    let layout_options = SkiaLayoutOptions {
+        max_node_width: Some(120.0),
+        ..SkiaLayoutOptions::default()
+    };
+    let wide = layout_flowchart_skia(&graph, &options, &SkiaLayoutOptions::default())
+        .expect("layout failed");
+    let wrapped = layout_flowchart_skia(&graph, &options, &layout_options).expect("layout failed");
+    let node = &wrapped.nodes[0];
+    assert!(node.width <= 120.0 + layout_options.node_padding_x * 2.0, "{}", node.width);
+    assert!(node.height > wide.nodes[0].height);
+    // The run of x's is one word too long for any line and must be hard-broken.
+    let label = node.label.as_deref().unwrap();
+    assert!(label.split("<br>").count() > 2);
+    assert!(!label.contains(&"x".repeat(60)));
+
+    let bytes = render_to_bytes(&wrapped, ImageFormat::Png, &options).expect("png render failed");
+    assert!(bytes.starts_with(b"\x89PNG"));
+}
+
+#[test]
+fn dark_theme_paints_a_dark_background() {
+    let graph = parse_flowchart("flowchart TB\nA-->B\n").expect("parse failed");
+    let top_left = |theme: Theme| {
+        let options = SkiaRenderOptions {
+            theme,
+            ..SkiaRenderOptions::default()
+        };
+        let layout = layout_flowchart_skia(&graph, &options, &SkiaLayoutOptions::default())
+            .expect("layout failed");
+        let bytes =
+            render_to_bytes(&layout, ImageFormat::Png, &options).expect("png render failed");
+        let (_, pixels) = decode_png_pixels(&bytes);
+        [pixels[0], pixels[1], pixels[2], pixels[3]]
+    };
+    assert_eq!(top_left(Theme::default()), [255, 255, 255, 255]);
+    let dark = top_left(Theme::dark());
+    assert_ne!(dark, [255, 255, 255, 255]);
+    assert!(dark[..3].iter().all(|&channel| channel < 128), "{dark:?}");
+}
+
+#[test]
+fn rounded_curves_bend_with_quads_and_still_render() {
+    let points = [(0.0, 0.0), (0.0, 40.0), (60.0, 40.0), (60.0, 100.0)];
+    let commands = curved_edge_path_commands(&points, 12.0);
+    let quads = commands
+        .iter()
+        .filter(|command| matches!(command, EdgePathCommand::QuadTo { .. }))
+        .count();
+    assert_eq!(quads, 2);
+    // The last leg stays straight and on the routed line, so the head keeps its direction.
+    assert_eq!(
+        &commands[commands.len() - 2..],
+        &[
+            EdgePathCommand::QuadTo {
+                control: (60.0, 40.0),
+                end: (60.0, 52.0),
+            },
+            EdgePathCommand::LineTo((60.0, 100.0)),
+        ]
+    );
+
+    let graph = parse_flowchart("flowchart TB\nA-->B\nA-->C\nB-->D\nC-->D\nA-->D\n")
+        .expect("parse failed");
+    let options = SkiaRenderOptions {
+        edge_curve: EdgeCurve::Rounded,
+        ..SkiaRenderOptions::default()
+    };
+    let layout = layout_flowchart_skia(&graph, &options, &SkiaLayoutOptions::default())
+        .expect("layout failed");
+    let bytes = render_to_bytes(&layout, ImageFormat::Png, &options).expect("png render failed");
+    assert!(bytes.starts_with(b"\x89PNG"));
+}
+
+#[test]
+fn gradient_edges_shade_from_start_to_end_color() {
+    let graph = parse_flowchart("flowchart TB\nA-->B\n").expect("parse failed");
+    let options = SkiaRenderOptions {
+        width: 200,
+        height: 300,
+        edge_gradient: Some((SkiaColor(255, 0, 0, 255), SkiaColor(0, 0, 255, 255))),
+        ..SkiaRenderOptions::default()
+    };
+    let layout = layout_flowchart_skia(&graph, &options, &SkiaLayoutOptions::default())
+        .expect("layout failed");
+    let bytes = render_to_bytes(&layout, ImageFormat::Png, &options).expect("png render failed");
+    let (width, pixels) = decode_png_pixels(&bytes);
+
+    // Mean row of the pixels that lean clearly red or clearly blue.
+    let mean_row = |leans: fn(&[u8]) -> bool| {
+        let rows: Vec<usize> = pixels
+            .chunks(4)
+            .enumerate()
+            .filter(|(_, px)| leans(px))
+            .map(|(index, _)| index / width)
+            .collect();
+        assert!(!rows.is_empty(), "no pixels of the expected color");
+        rows.iter().sum::<usize>() as f32 / rows.len() as f32
+    };
+    let red = mean_row(|px| px[0] > 160 && px[2] < 96);
+    let blue = mean_row(|px| px[2] > 160 && px[0] < 96);
+    assert!(red < blue, "red rows {red}, blue rows {blue}");
+}
+
+// Row width and RGBA bytes of a rendered PNG.
+fn decode_png_pixels(bytes: &[u8]) -> (usize, Vec<u8>) {
+    let image = skia_safe::images::deferred_from_encoded_data(skia_safe::Data::new_copy(bytes), None)
+        .expect("png decode failed");
+    let (width, height) = (image.width(), image.height());
+    let info = skia_safe::ImageInfo::new(
+        (width, height),
+        skia_safe::ColorType::RGBA8888,
+        skia_safe::AlphaType::Unpremul,
+        None,
+    );
+    let mut pixels = vec![0u8; (width * height * 4) as usize];
+    let row_bytes = width as usize * 4;
+    assert!(image.read_pixels(&info, &mut pixels, row_bytes, (0, 0), skia_safe::image::CachingHint::Allow));
+    (width as usize, pixels)
+}
+
+#[test]
+fn debug_labels_draw_node_coordinates_only_when_enabled() {
+    let graph = parse_flowchart("flowchart TB\nA-->B\n").expect("parse failed");
+    let overlay_pixels = |debug_labels: bool| {
+        let options = SkiaRenderOptions {
+            width: 400,
+            height: 400,
+            font_size: 32.0,
+            debug_labels,
+            ..SkiaRenderOptions::default()
+        };
+        let layout = layout_flowchart_skia(&graph, &options, &SkiaLayoutOptions::default())
+            .expect("layout failed");
+        let bytes = render_to_bytes(&layout, ImageFormat::Png, &options).expect("png render failed");
+        let (_, pixels) = decode_png_pixels(&bytes);
+        pixels
+            .chunks(4)
+            .filter(|px| px[0] > 150 && px[1] < 100 && px[2] > 100)
+            .count()
+    };
+    assert!(overlay_pixels(true) > 0);
+    assert_eq!(overlay_pixels(false), 0);
+}
+
+#[test]
+fn uniform_node_width_lines_every_node_up() {
+    let graph = parse_flowchart("flowchart TB\nA-->B[A much longer label]\nA-->C{?}\n")
+        .expect("parse failed");
+    let options = SkiaRenderOptions::default();
+    let measured = layout_flowchart_skia(&graph, &options, &SkiaLayoutOptions::default())
+        .expect("layout failed");
+    let widest = measured.nodes.iter().map(|node| node.width).fold(0.0, f32::max);
+
+    for uniform in [40.0, widest + 50.0] {
+        let layout_options = SkiaLayoutOptions {
+            uniform_node_width: Some(uniform),
+            ..SkiaLayoutOptions::default()
+        };
+        let layout = layout_flowchart_skia(&graph, &options, &layout_options).expect("layout failed");
+        let expected = uniform.max(widest);
+        for node in layout.nodes.iter().filter(|node| !node.is_dummy) {
+            assert_eq!(node.width, expected, "{}", node.id);
+        }
+    }
+}
+
+#[test]
+fn transparent_background_leaves_png_corners_clear() {
+    let graph = parse_flowchart("flowchart TB\nA-->|label|B\nA-->A\n").expect("parse failed");
+    let options = SkiaRenderOptions {
+        theme: Theme {
+            background: SkiaColor(0, 0, 0, 0),
+            ..Theme::default()
+        },
+        ..SkiaRenderOptions::default()
+    };
+    let layout = layout_flowchart_skia(&graph, &options, &SkiaLayoutOptions::default())
+        .expect("layout failed");
+    let bytes = render_to_bytes(&layout, ImageFormat::Png, &options).expect("png render failed");
+    let (width, pixels) = decode_png_pixels(&bytes);
+    let height = pixels.len() / 4 / width;
+    for (x, y) in [(0, 0), (width - 1, 0), (0, height - 1), (width - 1, height - 1)] {
+        let at = (y * width + x) * 4;
+        assert_eq!(pixels[at + 3], 0, "corner ({x}, {y}) is {:?}", &pixels[at..at + 4]);
+    }
+}
+
+#[test]
+fn skia_layout_draws_cross_edges_over_frames() {
+    let graph = parse_flowchart("flowchart TB\nsubgraph top[Top]\nA\nend\nB\nA-->B\n")
+        .expect("parse failed");
+    let options = SkiaRenderOptions {
+        width: 300,
+        height: 400,
+        subgraph_stroke_width: 6.0,
+        subgraph_frame_style: FrameStyle::Solid,
+        theme: Theme {
+            edge: SkiaColor(0, 0, 255, 255),
+            subgraph_border: SkiaColor(255, 0, 0, 255),
+            ..Theme::default()
+        },
+        ..SkiaRenderOptions::default()
+    };
+    let layout = layout_flowchart_skia(&graph, &options, &SkiaLayoutOptions::default())
+        .expect("layout failed");
+    assert!(layout.edges[0].is_cross);
+    assert_eq!(edge_draw_passes(&layout), (Vec::new(), vec![0]));
+
+    let bytes = render_to_bytes(&layout, ImageFormat::Png, &options).expect("png render failed");
+    let (width, pixels) = decode_png_pixels(&bytes);
+    let (left, top, right, _) = diagram_viewport(&layout, &options);
+    let scale = (right - left) / layout.width;
+    let points = &layout.edges[0].points;
+    assert!(points.iter().all(|point| (point.0 - points[0].0).abs() < 0.5));
+    let x = (left + points[0].0 * scale) as usize;
+    let (start, end) = (
+        (top + points[0].1 * scale) as usize,
+        (top + points[points.len() - 1].1 * scale) as usize,
+    );
+    let column: Vec<&[u8]> = (start..end)
+        .map(|y| &pixels[(y * width + x) * 4..(y * width + x) * 4 + 4])
+        .collect();
+    let is_red = |px: &[u8]| px[0] > 200 && px[2] < 60;
+    assert!(pixels.chunks(4).any(is_red), "frame was not drawn");
+    assert!(!column.iter().any(|px| is_red(px)), "frame covers the cross edge");
+}
+
+#[test]
+fn skia_layout_colors_cross_edges_by_source_group() {
+    let graph = parse_flowchart(
+        "flowchart TB\nsubgraph one\nA\nend\nsubgraph two\nB\nend\nsubgraph three\nC\nend\nA-->C\nB-->C\n",
+    )
+    .expect("parse failed");
+    let options = SkiaRenderOptions::default();
+    let layout = layout_flowchart_skia(&graph, &options, &SkiaLayoutOptions::default())
+        .expect("layout failed");
+    let source_of = |from: &str| {
+        let edge = layout.edges.iter().find(|edge| edge.from == from).unwrap();
+        assert!(edge.is_cross);
+        edge.source_group.clone()
+    };
+    assert_eq!(source_of("A").as_deref(), Some("one"));
+    assert_eq!(source_of("B").as_deref(), Some("two"));
+
+    let colors = source_group_colors(&layout);
+    let count_color = |color_cross_edges_by_source: bool, color: SkiaColor| {
+        let options = SkiaRenderOptions {
+            color_cross_edges_by_source,
+            ..SkiaRenderOptions::default()
+        };
+        let bytes = render_to_bytes(&layout, ImageFormat::Png, &options).expect("png render failed");
+        let (_, pixels) = decode_png_pixels(&bytes);
+        pixels
+            .chunks(4)
+            .filter(|px| px[..3] == [color.0, color.1, color.2])
+            .count()
+    };
+    for group in ["one", "two"] {
+        assert!(count_color(true, colors[group]) > 0, "no {group} edge pixels");
+        assert_eq!(count_color(false, colors[group]), 0);
+    }
+}
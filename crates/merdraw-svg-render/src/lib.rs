@@ -0,0 +1,397 @@
+use std::fmt::Write as _;
+
+use merdraw_layout::{label_anchors, subgraph_bounds, LayoutEdge, LayoutGraph, LayoutNode};
+use merdraw_parser::{label_lines, EdgeArrow, EdgeStyle, NodeShape, Rgba};
+
+#[derive(Debug, Clone)]
+pub struct SvgRenderOptions {
+    pub background: Option<String>,
+    pub stroke_width: f32,
+    pub font_family: String,
+    pub font_size: f32,
+    pub padding: f32,
+    pub subgraph_padding: f32,
+    pub show_arrows: bool,
+    // Written as `<title>` and `<desc>` for screen readers (from `accTitle` / `accDescr`).
+    pub title: Option<String>,
+    pub description: Option<String>,
+}
+
+impl Default for SvgRenderOptions {
+    fn default() -> Self {
+        Self {
+            background: Some("#ffffff".to_string()),
+            stroke_width: 1.5,
+            font_family: "sans-serif".to_string(),
+            font_size: 12.0,
+            padding: 16.0,
+            subgraph_padding: 12.0,
+            show_arrows: true,
+            title: None,
+            description: None,
+        }
+    }
+}
+
+// Coordinates are layout units as-is; the viewBox grows to fit subgraph frames that
+// stick out past the layout bounds.
+pub fn render_svg(layout: &LayoutGraph, options: &SvgRenderOptions) -> String {
+    let frames = subgraph_bounds(layout, options.subgraph_padding);
+    let mut left = 0.0f32;
+    let mut top = 0.0f32;
+    let mut right = layout.width.max(1.0);
+    let mut bottom = layout.height.max(1.0);
+    for frame in &frames {
+        left = left.min(frame.left);
+        top = top.min(frame.top);
+        right = right.max(frame.right);
+        bottom = bottom.max(frame.bottom);
+    }
+    let (x, y) = (left - options.padding, top - options.padding);
+    let width = right - left + options.padding * 2.0;
+    let height = bottom - top + options.padding * 2.0;
+
+    let mut out = String::new();
+    let _ = writeln!(
+        out,
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"{}\" viewBox=\"{} {} {} {}\">",
+        num(width),
+        num(height),
+        num(x),
+        num(y),
+        num(width),
+        num(height)
+    );
+    if let Some(title) = options.title.as_deref() {
+        let _ = writeln!(out, "<title>{}</title>", escape(title));
+    }
+    if let Some(description) = options.description.as_deref() {
+        let _ = writeln!(out, "<desc>{}</desc>", escape(description));
+    }
+    if let Some(background) = options.background.as_deref() {
+        let _ = writeln!(
+            out,
+            "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"{}\"/>",
+            num(x),
+            num(y),
+            num(width),
+            num(height),
+            escape(background)
+        );
+    }
+    let _ = writeln!(
+        out,
+        "<g font-family=\"{}\" font-size=\"{}\">",
+        escape(&options.font_family),
+        num(options.font_size)
+    );
+
+    // Frames are collected innermost first; paint outer frames underneath.
+    for frame in frames.iter().rev() {
+        let _ = writeln!(
+            out,
+            "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"none\" stroke=\"#5a5a5a\" stroke-width=\"{}\"/>",
+            num(frame.left),
+            num(frame.top),
+            num(frame.right - frame.left),
+            num(frame.bottom - frame.top),
+            num(options.stroke_width)
+        );
+        let _ = writeln!(
+            out,
+            "<text x=\"{}\" y=\"{}\">{}</text>",
+            num(frame.left + options.subgraph_padding / 2.0),
+            num(frame.title_baseline),
+            escape(&frame.label)
+        );
+    }
+
+    for edge in layout.edges.iter().filter(|edge| edge.style != EdgeStyle::Invisible) {
+        write_edge(&mut out, edge, options);
+    }
+
+    let anchors = label_anchors(layout);
+    for edge in &layout.edges {
+        let Some(label) = edge.label.as_deref() else {
+            continue;
+        };
+        if edge.style == EdgeStyle::Invisible {
+            continue;
+        }
+        let key = (edge.from.clone(), edge.to.clone(), label.to_string());
+        if let Some(&(ax, ay)) = anchors.get(&key) {
+            let _ = writeln!(
+                out,
+                "<text x=\"{}\" y=\"{}\" text-anchor=\"middle\" dominant-baseline=\"central\" stroke=\"#ffffff\" stroke-width=\"3\" paint-order=\"stroke\">{}</text>",
+                num(ax),
+                num(ay),
+                escape(label)
+            );
+        }
+    }
+
+    for node in layout.nodes.iter().filter(|node| !node.is_dummy) {
+        write_node(&mut out, node, options);
+    }
+
+    out.push_str("</g>\n</svg>\n");
+    out
+}
+
+fn write_edge(out: &mut String, edge: &LayoutEdge, options: &SvgRenderOptions) {
+    if edge.points.len() < 2 {
+        return;
+    }
+    let width = match edge.style {
+        EdgeStyle::Thick => options.stroke_width * 2.0,
+        _ => options.stroke_width,
+    };
+    let dash = match edge.style {
+        EdgeStyle::Dotted => format!(" stroke-dasharray=\"{} {}\"", num(width * 2.0), num(width * 3.0)),
+        _ => String::new(),
+    };
+    let _ = writeln!(
+        out,
+        "<polyline points=\"{}\" fill=\"none\" stroke=\"#000000\" stroke-width=\"{}\" stroke-linejoin=\"round\"{dash}/>",
+        point_list(&edge.points),
+        num(width)
+    );
+    if !options.show_arrows {
+        return;
+    }
+    let last = edge.points.len() - 1;
+    if edge.arrow.has_end_head() {
+        let (tip, prev) = (edge.points[last], edge.points[last - 1]);
+        let background = options.background.as_deref().unwrap_or("#ffffff");
+        match edge.arrow {
+            EdgeArrow::Cross => write_cross(out, tip, prev, width),
+            EdgeArrow::Circle => write_circle(out, tip, prev, width, background),
+            _ => write_arrowhead(out, tip, prev, width),
+        }
+    }
+    if edge.arrow.has_start_head() {
+        write_arrowhead(out, edge.points[0], edge.points[1], width);
+    }
+}
+
+fn write_arrowhead(out: &mut String, tip: (f32, f32), prev: (f32, f32), stroke_width: f32) {
+    let (ux, uy) = unit(tip, prev);
+    let arrow_len = stroke_width * 6.0;
+    let arrow_w = stroke_width * 3.0;
+    let base = (tip.0 - ux * arrow_len, tip.1 - uy * arrow_len);
+    let left = (base.0 - uy * arrow_w, base.1 + ux * arrow_w);
+    let right = (base.0 + uy * arrow_w, base.1 - ux * arrow_w);
+    let _ = writeln!(
+        out,
+        "<polygon points=\"{}\" fill=\"#000000\"/>",
+        point_list(&[tip, left, right])
+    );
+}
+
+// The `--x` end: two strokes at 45 degrees to the edge, crossing one head width before the tip.
+fn write_cross(out: &mut String, tip: (f32, f32), prev: (f32, f32), stroke_width: f32) {
+    let (ux, uy) = unit(tip, prev);
+    let arrow_w = stroke_width * 3.0;
+    let center = (tip.0 - ux * arrow_w, tip.1 - uy * arrow_w);
+    let arm = arrow_w / std::f32::consts::SQRT_2;
+    for (dx, dy) in [(ux - uy, uy + ux), (ux + uy, uy - ux)] {
+        let _ = writeln!(
+            out,
+            "<line x1=\"{}\" y1=\"{}\" x2=\"{}\" y2=\"{}\" stroke=\"#000000\" stroke-width=\"{}\"/>",
+            num(center.0 - dx * arm),
+            num(center.1 - dy * arm),
+            num(center.0 + dx * arm),
+            num(center.1 + dy * arm),
+            num(stroke_width)
+        );
+    }
+}
+
+// The `--o` end: an open circle touching the tip, filled with the background so the line
+// doesn't show through.
+fn write_circle(
+    out: &mut String,
+    tip: (f32, f32),
+    prev: (f32, f32),
+    stroke_width: f32,
+    background: &str,
+) {
+    let (ux, uy) = unit(tip, prev);
+    let radius = stroke_width * 3.0 * 0.75;
+    let _ = writeln!(
+        out,
+        "<circle cx=\"{}\" cy=\"{}\" r=\"{}\" fill=\"{}\" stroke=\"#000000\" stroke-width=\"{}\"/>",
+        num(tip.0 - ux * radius),
+        num(tip.1 - uy * radius),
+        num(radius),
+        escape(background),
+        num(stroke_width)
+    );
+}
+
+fn unit(tip: (f32, f32), prev: (f32, f32)) -> (f32, f32) {
+    let (dx, dy) = (tip.0 - prev.0, tip.1 - prev.1);
+    let len = (dx * dx + dy * dy).sqrt().max(1.0);
+    (dx / len, dy / len)
+}
+
+fn write_node(out: &mut String, node: &LayoutNode, options: &SvgRenderOptions) {
+    let (cx, cy) = (node.x, node.y);
+    let (hw, hh) = (node.width / 2.0, node.height / 2.0);
+    let (left, top, right, bottom) = (cx - hw, cy - hh, cx + hw, cy + hh);
+    let fill = node.style.fill.map_or("#ffffff".to_string(), css_color);
+    let stroke = node.style.stroke.map_or("#000000".to_string(), css_color);
+    let paint = format!(
+        "fill=\"{fill}\" stroke=\"{stroke}\" stroke-width=\"{}\"",
+        num(options.stroke_width)
+    );
+    let rect = |rx: f32| {
+        format!(
+            "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" rx=\"{}\" {paint}/>",
+            num(left),
+            num(top),
+            num(node.width),
+            num(node.height),
+            num(rx)
+        )
+    };
+    let shape = match node.shape {
+        NodeShape::Plain | NodeShape::Bracket => rect(0.0),
+        NodeShape::Stadium => rect(hh),
+        NodeShape::Subroutine => {
+            let inset = 6.0f32.min(hw / 2.0);
+            format!(
+                "{}\n<polyline points=\"{}\" fill=\"none\" stroke=\"{stroke}\" stroke-width=\"{}\"/>\n<polyline points=\"{}\" fill=\"none\" stroke=\"{stroke}\" stroke-width=\"{}\"/>",
+                rect(0.0),
+                point_list(&[(left + inset, top), (left + inset, bottom)]),
+                num(options.stroke_width),
+                point_list(&[(right - inset, top), (right - inset, bottom)]),
+                num(options.stroke_width)
+            )
+        }
+        NodeShape::Round | NodeShape::Circle => format!(
+            "<ellipse cx=\"{}\" cy=\"{}\" rx=\"{}\" ry=\"{}\" {paint}/>",
+            num(cx),
+            num(cy),
+            num(hw),
+            num(hh)
+        ),
+        NodeShape::Diamond => format!(
+            "<polygon points=\"{}\" {paint}/>",
+            point_list(&[(cx, top), (right, cy), (cx, bottom), (left, cy)])
+        ),
+        NodeShape::Hexagon => {
+            let cut = node.height / 4.0;
+            format!(
+                "<polygon points=\"{}\" {paint}/>",
+                point_list(&[
+                    (left + cut, top),
+                    (right - cut, top),
+                    (right, cy),
+                    (right - cut, bottom),
+                    (left + cut, bottom),
+                    (left, cy),
+                ])
+            )
+        }
+        NodeShape::Cylinder => {
+            let rim = node.height / 8.0;
+            format!(
+                "<path d=\"M {} {} L {} {} A {} {} 0 0 0 {} {} L {} {} A {} {} 0 0 0 {} {} Z\" {paint}/>\n<ellipse cx=\"{}\" cy=\"{}\" rx=\"{}\" ry=\"{}\" fill=\"none\" stroke=\"{stroke}\" stroke-width=\"{}\"/>",
+                num(left),
+                num(top + rim),
+                num(left),
+                num(bottom - rim),
+                num(hw),
+                num(rim),
+                num(right),
+                num(bottom - rim),
+                num(right),
+                num(top + rim),
+                num(hw),
+                num(rim),
+                num(left),
+                num(top + rim),
+                num(cx),
+                num(top + rim),
+                num(hw),
+                num(rim),
+                num(options.stroke_width)
+            )
+        }
+    };
+    let _ = writeln!(out, "{shape}");
+    let text = node.label.as_deref().unwrap_or(node.id.as_str());
+    let text_fill = node
+        .style
+        .color
+        .map(|color| format!(" fill=\"{}\"", css_color(color)))
+        .unwrap_or_default();
+    // Lines sit 1.2em apart, centered as a block on the node.
+    let lines = label_lines(text);
+    let line_height = options.font_size * 1.2;
+    let first_y = cy - (lines.len() - 1) as f32 * line_height / 2.0;
+    let body = if lines.len() == 1 {
+        escape(text)
+    } else {
+        lines
+            .iter()
+            .enumerate()
+            .map(|(index, line)| {
+                format!(
+                    "<tspan x=\"{}\" y=\"{}\">{}</tspan>",
+                    num(cx),
+                    num(first_y + index as f32 * line_height),
+                    escape(line)
+                )
+            })
+            .collect()
+    };
+    let _ = writeln!(
+        out,
+        "<text x=\"{}\" y=\"{}\" text-anchor=\"middle\" dominant-baseline=\"central\"{text_fill}>{body}</text>",
+        num(cx),
+        num(first_y)
+    );
+}
+
+fn css_color(Rgba(r, g, b, a): Rgba) -> String {
+    if a == 255 {
+        format!("#{r:02x}{g:02x}{b:02x}")
+    } else {
+        format!("rgba({r},{g},{b},{})", num(a as f32 / 255.0))
+    }
+}
+
+fn point_list(points: &[(f32, f32)]) -> String {
+    points
+        .iter()
+        .map(|&(x, y)| format!("{},{}", num(x), num(y)))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+// Two decimals is well below a device pixel and keeps the output diffable.
+fn num(value: f32) -> String {
+    let text = format!("{value:.2}");
+    let text = text.trim_end_matches('0').trim_end_matches('.');
+    if text == "-0" {
+        "0".to_string()
+    } else {
+        text.to_string()
+    }
+}
+
+fn escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            _ => out.push(c),
+        }
+    }
+    out
+}
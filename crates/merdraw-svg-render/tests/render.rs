@@ -0,0 +1,65 @@
+use merdraw_layout::{layout_flowchart, LayoutStyle};
+use merdraw_parser::parse_flowchart;
+use merdraw_svg_render::{render_svg, SvgRenderOptions};
+
+#[test]
+fn emits_one_polyline_per_edge() {
+    let source = "flowchart TB\nsubgraph g [Group]\nA[Start] --> B{Check}\nend\nB -->|yes| C((Done))\nB -.-> D[(Store)]\nA ==> D\n";
+    let graph = parse_flowchart(source).expect("parse failed");
+    let layout = layout_flowchart(&graph, &LayoutStyle::default());
+    let svg = render_svg(&layout, &SvgRenderOptions::default());
+    assert!(svg.starts_with("<svg"));
+    assert!(svg.trim_end().ends_with("</svg>"));
+    assert_eq!(svg.matches("<polyline").count(), layout.edges.len());
+    assert!(svg.contains("<ellipse"));
+    assert!(svg.contains(">Group</text>"));
+    assert!(svg.contains(">yes</text>"));
+    assert!(svg.contains("stroke-dasharray"));
+}
+
+#[test]
+fn cross_and_circle_ends_replace_the_arrowhead() {
+    let svg = |source: &str| {
+        let graph = parse_flowchart(source).expect("parse failed");
+        let layout = layout_flowchart(&graph, &LayoutStyle::default());
+        render_svg(&layout, &SvgRenderOptions::default())
+    };
+    let head = svg("flowchart TB\nA-->B\n");
+    assert_eq!(head.matches("<polygon").count(), 1);
+    assert!(!head.contains("<line") && !head.contains("<circle"));
+    let cross = svg("flowchart TB\nA --x B\n");
+    assert_eq!(cross.matches("<line").count(), 2);
+    assert!(!cross.contains("<polygon") && !cross.contains("<circle"));
+    let circle = svg("flowchart TB\nA --o B\n");
+    let ring = circle.lines().filter(|line| line.starts_with("<circle")).collect::<Vec<_>>();
+    assert_eq!(ring.len(), 1);
+    assert!(ring[0].contains("fill=\"#ffffff\" stroke=\"#000000\""), "{}", ring[0]);
+    assert!(!circle.contains("<polygon") && !circle.contains("<line"));
+}
+
+#[test]
+fn class_colors_fill_styled_nodes() {
+    let source = "flowchart TB\nclassDef important fill:#f00,color:#fff\nA-->B\nclass A important\n";
+    let graph = parse_flowchart(source).expect("parse failed");
+    let layout = layout_flowchart(&graph, &LayoutStyle::default());
+    let svg = render_svg(&layout, &SvgRenderOptions::default());
+    assert_eq!(svg.matches("fill=\"#ff0000\" stroke=\"#000000\"").count(), 1);
+    assert_eq!(svg.matches("fill=\"#ffffff\" stroke=\"#000000\"").count(), 1);
+    assert!(svg.contains("dominant-baseline=\"central\" fill=\"#ffffff\">A</text>"));
+}
+
+#[test]
+fn accessibility_text_becomes_title_and_desc() {
+    let graph = parse_flowchart("flowchart TB\naccTitle: A & B\naccDescr: Two <nodes>\nA-->B\n")
+        .expect("parse failed");
+    let layout = layout_flowchart(&graph, &LayoutStyle::default());
+    let options = SvgRenderOptions {
+        title: graph.acc_title.clone(),
+        description: graph.acc_descr.clone(),
+        ..SvgRenderOptions::default()
+    };
+    let svg = render_svg(&layout, &options);
+    assert!(svg.contains("<title>A &amp; B</title>"), "{svg}");
+    assert!(svg.contains("<desc>Two &lt;nodes&gt;</desc>"), "{svg}");
+    assert!(!render_svg(&layout, &SvgRenderOptions::default()).contains("<title>"));
+}
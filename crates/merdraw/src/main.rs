@@ -1,140 +1,402 @@
+use std::collections::HashMap;
 use std::env;
 use std::fs;
 use std::io::{self, Read};
+#[cfg(feature = "skia")]
+use std::io::Write;
+#[cfg(feature = "skia")]
+use std::path::Path;
 use std::path::PathBuf;
+use std::thread;
+use std::time::Duration;
 
 use merdraw_ascii_render::{render_ascii, AsciiRenderOptions};
-use merdraw_layout::{layout_flowchart, suggest_canvas_size, LayoutStyle};
-use merdraw_parser::parse_flowchart;
+use merdraw_core::ascii_layout_style;
+use merdraw_dot::{parse_dot, to_dot};
+use merdraw_layout::{layout_flowchart, to_graphviz_plain, LayoutGraph, LayoutStyle};
+#[cfg(feature = "skia")]
+use merdraw_layout::{find_highlight_path, suggest_canvas_size};
+use merdraw_parser::{
+    alt_text, format_flowchart, parse_flowchart, relabel, truncate_labels, Direction, EdgeArrow,
+    EdgeStyle, Graph, NodeShape,
+};
+use merdraw_svg_render::{render_svg, SvgRenderOptions};
+#[cfg(feature = "skia")]
 use merdraw_skia_render::{
-    layout_flowchart_skia, render_to_file, ImageFormat, SkiaLayoutOptions, SkiaRenderOptions,
+    footer_band_height, layout_flowchart_skia, render_page_tiles, render_to_bytes, render_to_file, ImageFormat,
+    SkiaLayoutOptions, SkiaRenderError, SkiaRenderOptions,
 };
 
 fn main() {
-    let options = parse_args(env::args().skip(1).collect());
-    let input = read_input(options.input.as_deref());
-
-    let graph = parse_flowchart(&input).expect("failed to parse flowchart");
-
-    let ascii_layout_style = LayoutStyle {
-        min_width: 24.0,
-        min_height: 16.0,
-        char_width: 6.0,
-        char_height: 10.0,
-        node_padding_x: 6.0,
-        node_padding_y: 4.0,
-        node_gap: 8.0,
-        layer_gap: 12.0,
-    };
+    if let Err(err) = run() {
+        eprintln!("{err}");
+        std::process::exit(err.exit_code());
+    }
+}
 
-    if options.ascii {
-        let layout_style = ascii_layout_style;
-        let layout = layout_flowchart(&graph, &layout_style);
-        let output = render_ascii(&layout, &AsciiRenderOptions::default());
-        println!("{output}");
-        return;
+fn run() -> Result<(), CliError> {
+    let options = parse_args(env::args().skip(1).collect())?;
+    if options.list_shapes || options.list_arrows {
+        if options.list_shapes {
+            print!("{}", format_shape_list());
+        }
+        if options.list_arrows {
+            print!("{}", format_arrow_list());
+        }
+        return Ok(());
+    }
+    if options.watch {
+        return watch(&options);
     }
+    read_input(options.input.as_deref()).and_then(|input| render_input(&input, &options))
+}
 
-    let out_path = options.out.clone().unwrap_or_else(default_output_path);
-    if let Some(parent) = out_path.parent() {
-        if let Err(err) = std::fs::create_dir_all(parent) {
-            eprintln!("failed to create output directory: {err}");
-            std::process::exit(1);
+// A failure the CLI reports on stderr; scripts can branch on the exit code.
+#[derive(Debug)]
+enum CliError {
+    Usage(String),
+    Io(String),
+    Parse(String),
+    Render(String),
+}
+
+impl CliError {
+    fn exit_code(&self) -> i32 {
+        match self {
+            CliError::Usage(_) | CliError::Io(_) => 1,
+            CliError::Parse(_) => 2,
+            CliError::Render(_) => 3,
         }
     }
-    {
-        let format = match options.format.as_deref() {
-            Some("png") => ImageFormat::Png,
-            Some("jpg") | Some("jpeg") => ImageFormat::Jpeg {
-                quality: options.quality,
-            },
-            Some(other) => {
-                eprintln!("unsupported format: {other}");
-                std::process::exit(1);
-            }
-            None => infer_format_from_path(&out_path).unwrap_or(ImageFormat::Png),
-        };
+}
 
-        let mut render_options = SkiaRenderOptions {
-            width: 0,
-            height: 0,
-            jpeg_quality: options.quality,
-            font_path: options.font,
-            debug: options.debug,
-            device_pixel_ratio: options.dpr,
-            ..SkiaRenderOptions::default()
-        };
-        let layout = match layout_flowchart_skia(
-            &graph,
-            &render_options,
-            &SkiaLayoutOptions::default(),
-        ) {
-            Ok(layout) => layout,
-            Err(err) => {
-                eprintln!("layout failed: {err:?}");
-                std::process::exit(1);
+impl std::fmt::Display for CliError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CliError::Usage(message)
+            | CliError::Io(message)
+            | CliError::Parse(message)
+            | CliError::Render(message) => f.write_str(message),
+        }
+    }
+}
+
+// Polls the input file's mtime and re-renders on every change. Parse errors are printed and
+// the watch goes on; Ctrl-C ends it.
+fn watch(options: &CliOptions) -> Result<(), CliError> {
+    let Some(path) = options.input.as_deref().filter(|path| *path != "-") else {
+        return Err(CliError::Usage("--watch needs an input file, not stdin".to_string()));
+    };
+    let mut last_modified = None;
+    let mut renders = 0;
+    loop {
+        let modified = fs::metadata(path).and_then(|meta| meta.modified()).ok();
+        if modified.is_some() && modified != last_modified {
+            last_modified = modified;
+            renders += 1;
+            match fs::read_to_string(path) {
+                Ok(input) => match render_input(&input, options) {
+                    Ok(()) => eprintln!("watch: rendered {path} (#{renders})"),
+                    Err(err) => eprintln!("watch: {err} (#{renders}); waiting for changes"),
+                },
+                Err(err) => eprintln!("watch: failed to read {path}: {err}"),
+            }
+        }
+        thread::sleep(WATCH_POLL_INTERVAL);
+    }
+}
+
+fn render_input(input: &str, options: &CliOptions) -> Result<(), CliError> {
+    let graph = match options.input_format.as_deref() {
+        None | Some("mermaid") => parse_flowchart(input)
+            .map_err(|err| CliError::Parse(format!("failed to parse flowchart: {err}")))?,
+        Some("dot") => parse_dot(input)
+            .map_err(|err| CliError::Parse(format!("failed to parse DOT graph: {err}")))?,
+        Some(other) => {
+            return Err(CliError::Usage(format!(
+                "unsupported input format: {other} (use mermaid or dot)"
+            )));
+        }
+    };
+
+    let mut graph = if options.relabel.is_empty() {
+        graph
+    } else {
+        relabel(&graph, |text| options.relabel.get(text).cloned())
+    };
+    if let Some(max_chars) = options.max_label_chars {
+        graph = truncate_labels(&graph, max_chars);
+    }
+    if let Some(direction) = options.direction.clone() {
+        graph.direction = direction;
+    }
+
+    if let Some(emit) = options.emit.as_deref() {
+        let json = match emit {
+            "dot" => {
+                print!("{}", to_dot(&graph));
+                return Ok(());
+            }
+            "mermaid" => {
+                print!("{}", format_flowchart(&graph));
+                return Ok(());
+            }
+            "alt-text" => {
+                println!("{}", alt_text(&graph));
+                return Ok(());
             }
+            "layout-json" => {
+                serde_json::to_string_pretty(&reported_layout(&graph, &LayoutStyle::default()))
+            }
+            _ => serde_json::to_string_pretty(&graph),
         };
-        let padding = render_options.padding;
-        let (width, height) = match (options.width, options.height) {
-            (Some(w), Some(h)) => (w, h),
-            (Some(w), None) => {
-                let scale = ((w as f32 - padding * 2.0) / layout.width.max(1.0)).max(0.1);
-                let h = (layout.height.max(1.0) * scale + padding * 2.0).ceil().max(1.0) as u32;
-                (w, h)
-            }
-            (None, Some(h)) => {
-                let scale = ((h as f32 - padding * 2.0) / layout.height.max(1.0)).max(0.1);
-                let w = (layout.width.max(1.0) * scale + padding * 2.0).ceil().max(1.0) as u32;
-                (w, h)
-            }
-            (None, None) => suggest_canvas_size(&layout, padding, 1.0),
+        let json =
+            json.map_err(|err| CliError::Render(format!("failed to serialize JSON: {err}")))?;
+        println!("{json}");
+        return Ok(());
+    }
+
+    // Text formats go to stdout unless `--out` is given; `.svg` is also inferred from it.
+    let svg_out = options
+        .out
+        .as_ref()
+        .and_then(|path| path.extension())
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("svg"));
+    let text_output = match options.format.as_deref() {
+        Some("plain") => Some(to_graphviz_plain(&reported_layout(&graph, &LayoutStyle::default()))),
+        Some("svg") => Some(svg_output(&graph, options)),
+        None if svg_out => Some(svg_output(&graph, options)),
+        _ => None,
+    };
+    if let Some(output) = text_output {
+        match &options.out {
+            Some(path) => {
+                fs::write(path, output).map_err(|err| {
+                    CliError::Io(format!("failed to write {}: {err}", path.display()))
+                })?;
+            }
+            None => print!("{output}"),
+        }
+        return Ok(());
+    }
+
+    if wants_ascii(options) {
+        let layout = reported_layout(&graph, &ascii_layout_style());
+        let ascii_options = AsciiRenderOptions {
+            show_arrows: options.arrowheads,
+            ..AsciiRenderOptions::default()
         };
-        render_options.width = width;
-        render_options.height = height;
-        if options.debug {
-            let scale_x = ((width as f32 - padding * 2.0) / layout.width.max(1.0)).max(0.1);
-            let scale_y = ((height as f32 - padding * 2.0) / layout.height.max(1.0)).max(0.1);
-            let scale = scale_x.min(scale_y);
-            eprintln!(
-                "layout: nodes={} edges={} subgraphs={} size=({:.1},{:.1}) padding={:.1}",
-                graph.nodes.len(),
-                graph.edges.len(),
-                graph.subgraphs.len(),
-                layout.width,
-                layout.height,
-                padding
-            );
-            eprintln!(
-                "canvas: width={} height={} scale={:.2} (scale_x={:.2}, scale_y={:.2})",
-                width, height, scale, scale_x, scale_y
-            );
-            if let Some(path) = render_options.font_path.as_ref() {
-                eprintln!("font path: {}", path.display());
-            } else {
-                eprintln!("font path: <default>");
-            }
-            eprintln!("device pixel ratio: {:.2}", render_options.device_pixel_ratio);
+        let output = render_ascii(&layout, &ascii_options);
+        println!("{output}");
+        return Ok(());
+    }
+
+    render_image(&graph, options)
+}
+
+// Lays out the graph and prints any constraints the layout had to drop.
+fn reported_layout(graph: &Graph, style: &LayoutStyle) -> LayoutGraph {
+    let layout = layout_flowchart(graph, style);
+    for warning in &layout.warnings {
+        eprintln!("warning: {warning}");
+    }
+    layout
+}
+
+fn svg_output(graph: &Graph, options: &CliOptions) -> String {
+    let layout = reported_layout(graph, &LayoutStyle::default());
+    let svg_options = SvgRenderOptions {
+        show_arrows: options.arrowheads,
+        title: graph.acc_title.clone(),
+        description: graph.acc_descr.clone(),
+        ..SvgRenderOptions::default()
+    };
+    render_svg(&layout, &svg_options)
+}
+
+#[cfg(feature = "skia")]
+fn render_image(graph: &Graph, options: &CliOptions) -> Result<(), CliError> {
+    let out_path = options.out.clone().unwrap_or_else(default_output_path);
+    if let Some(parent) = out_path.parent().filter(|_| !options.stdout) {
+        std::fs::create_dir_all(parent)
+            .map_err(|err| CliError::Io(format!("failed to create output directory: {err}")))?;
+    }
+    let format = match options.format.as_deref() {
+        Some("png") => ImageFormat::Png,
+        Some("jpg") | Some("jpeg") => ImageFormat::Jpeg {
+            quality: options.quality,
+        },
+        Some(other) => return Err(CliError::Usage(format!("unsupported format: {other}"))),
+        None => infer_format_from_path(&out_path).unwrap_or(ImageFormat::Png),
+    };
+
+    let mut render_options = SkiaRenderOptions {
+        width: 0,
+        height: 0,
+        jpeg_quality: options.quality,
+        font_path: options.font.clone(),
+        debug: options.debug,
+        debug_labels: options.debug_labels,
+        device_pixel_ratio: options.dpr,
+        footer: options.footer.clone(),
+        show_arrows: options.arrowheads,
+        color_cross_edges_by_source: options.color_cross_edges,
+        ..SkiaRenderOptions::default()
+    };
+    let defaults = SkiaLayoutOptions::default();
+    let layout_options = SkiaLayoutOptions {
+        node_gap: options.node_gap.unwrap_or(defaults.node_gap),
+        layer_gap: options.layer_gap.unwrap_or(defaults.layer_gap),
+        node_padding_x: options.node_padding_x.unwrap_or(defaults.node_padding_x),
+        node_padding_y: options.node_padding_y.unwrap_or(defaults.node_padding_y),
+        ..defaults
+    };
+    let layout = layout_flowchart_skia(graph, &render_options, &layout_options)
+        .map_err(|err| CliError::Render(format!("layout failed: {err}")))?;
+    let padding = render_options.padding;
+    let (width, height) = match (options.width, options.height) {
+        (Some(w), Some(h)) => (w, h),
+        (Some(w), None) => {
+            let scale = ((w as f32 - padding * 2.0) / layout.width.max(1.0)).max(0.1);
+            let h = (layout.height.max(1.0) * scale + padding * 2.0).ceil().max(1.0) as u32;
+            (w, h)
         }
-        if let Err(err) = render_to_file(&layout, format, &render_options, &out_path) {
-            eprintln!("render failed: {err:?}");
-            std::process::exit(1);
+        (None, Some(h)) => {
+            let scale = ((h as f32 - padding * 2.0) / layout.height.max(1.0)).max(0.1);
+            let w = (layout.width.max(1.0) * scale + padding * 2.0).ceil().max(1.0) as u32;
+            (w, h)
         }
-        if options.out.is_none() {
-            eprintln!("wrote {}", out_path.display());
+        (None, None) => suggest_canvas_size(&layout, padding, 1.0),
+    };
+    let height = if options.height.is_none() {
+        height + footer_band_height(&render_options).ceil() as u32
+    } else {
+        height
+    };
+    render_options.width = width;
+    render_options.height = height;
+    if let Some((from, to)) = options.highlight_path.as_ref() {
+        render_options.highlight = find_highlight_path(&layout, from, to);
+        if render_options.highlight.is_none() {
+            eprintln!("warning: no path from {from} to {to}");
+        }
+    }
+    if options.debug {
+        let scale_x = ((width as f32 - padding * 2.0) / layout.width.max(1.0)).max(0.1);
+        let scale_y = ((height as f32 - padding * 2.0) / layout.height.max(1.0)).max(0.1);
+        let scale = scale_x.min(scale_y);
+        eprintln!(
+            "layout: nodes={} edges={} subgraphs={} size=({:.1},{:.1}) padding={:.1}",
+            graph.nodes.len(),
+            graph.edges.len(),
+            graph.subgraphs.len(),
+            layout.width,
+            layout.height,
+            padding
+        );
+        eprintln!(
+            "canvas: width={} height={} scale={:.2} (scale_x={:.2}, scale_y={:.2})",
+            width, height, scale, scale_x, scale_y
+        );
+        if let Some(path) = render_options.font_path.as_ref() {
+            eprintln!("font path: {}", path.display());
+        } else {
+            eprintln!("font path: <default>");
+        }
+        eprintln!("device pixel ratio: {:.2}", render_options.device_pixel_ratio);
+        for (a, b) in layout.overlaps() {
+            eprintln!("warning: node overlap: {a} <-> {b}");
+        }
+    }
+    if let Some(page) = options.page_size {
+        let pages = render_page_tiles(&layout, format, &render_options, page, PAGE_OVERLAP)
+            .map_err(|err| CliError::Render(format!("render failed: {err}")))?;
+        for (index, bytes) in pages.iter().enumerate() {
+            let path = page_output_path(&out_path, index + 1);
+            fs::write(&path, bytes)
+                .map_err(|err| CliError::Io(format!("failed to write {}: {err}", path.display())))?;
+            if options.out.is_none() {
+                eprintln!("wrote {}", path.display());
+            }
+        }
+        return Ok(());
+    }
+    if options.stdout {
+        let bytes = render_to_bytes(&layout, format, &render_options)
+            .map_err(|err| CliError::Render(format!("render failed: {err}")))?;
+        // `Stdout` passes bytes through untranslated on every platform, so binary output
+        // survives a shell redirect.
+        let mut stdout = io::stdout().lock();
+        stdout
+            .write_all(&bytes)
+            .and_then(|()| stdout.flush())
+            .map_err(|err| CliError::Io(format!("failed to write to stdout: {err}")))?;
+        return Ok(());
+    }
+    render_to_file(&layout, format, &render_options, &out_path).map_err(|err| match err {
+        SkiaRenderError::Io(err) => {
+            CliError::Io(format!("failed to write {}: {err}", out_path.display()))
+        }
+        err => CliError::Render(format!("render failed: {err}")),
+    })?;
+    if options.out.is_none() {
+        eprintln!("wrote {}", out_path.display());
+    }
+    Ok(())
+}
+
+#[cfg(feature = "skia")]
+const PAGE_OVERLAP: u32 = 16;
+
+#[cfg(feature = "skia")]
+fn page_output_path(path: &Path, page: usize) -> PathBuf {
+    let stem = path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "page".to_string());
+    let name = match path.extension() {
+        Some(ext) => format!("{stem}-{page}.{}", ext.to_string_lossy()),
+        None => format!("{stem}-{page}"),
+    };
+    path.with_file_name(name)
+}
+
+// Page sizes in CSS pixels (96 per inch), portrait.
+fn parse_page_size(value: &str) -> Option<(u32, u32)> {
+    match value.to_lowercase().as_str() {
+        "a3" => Some((1123, 1587)),
+        "a4" => Some((794, 1123)),
+        "a5" => Some((559, 794)),
+        "letter" => Some((816, 1056)),
+        "legal" => Some((816, 1344)),
+        other => {
+            let (w, h) = other.split_once('x')?;
+            let (w, h) = (w.trim().parse().ok()?, h.trim().parse().ok()?);
+            (w > 0 && h > 0).then_some((w, h))
         }
-        return;
     }
 }
 
+#[cfg(not(feature = "skia"))]
+fn render_image(_graph: &Graph, _options: &CliOptions) -> Result<(), CliError> {
+    Err(CliError::Usage(
+        "image output requires the `skia` feature; rebuild with default features or pass --ascii"
+            .to_string(),
+    ))
+}
+
+const WATCH_POLL_INTERVAL: Duration = Duration::from_millis(250);
+
 #[cfg(target_os = "macos")]
 const DEFAULT_DPR: f32 = 2.0;
 
 #[cfg(not(target_os = "macos"))]
 const DEFAULT_DPR: f32 = 1.0;
 
+#[cfg_attr(not(feature = "skia"), allow(dead_code))]
 struct CliOptions {
     input: Option<String>,
+    input_format: Option<String>,
     out: Option<PathBuf>,
     format: Option<String>,
     width: Option<u32>,
@@ -143,11 +405,30 @@ struct CliOptions {
     font: Option<PathBuf>,
     dpr: f32,
     debug: bool,
+    debug_labels: bool,
+    color_cross_edges: bool,
     ascii: bool,
+    arrowheads: bool,
+    list_shapes: bool,
+    list_arrows: bool,
+    highlight_path: Option<(String, String)>,
+    page_size: Option<(u32, u32)>,
+    footer: Option<String>,
+    emit: Option<String>,
+    relabel: HashMap<String, String>,
+    max_label_chars: Option<usize>,
+    direction: Option<Direction>,
+    watch: bool,
+    stdout: bool,
+    node_gap: Option<f32>,
+    layer_gap: Option<f32>,
+    node_padding_x: Option<f32>,
+    node_padding_y: Option<f32>,
 }
 
-fn parse_args(args: Vec<String>) -> CliOptions {
+fn parse_args(args: Vec<String>) -> Result<CliOptions, CliError> {
     let mut input = None;
+    let mut input_format = None;
     let mut out = None;
     let mut format = None;
     let mut width = None;
@@ -156,7 +437,25 @@ fn parse_args(args: Vec<String>) -> CliOptions {
     let mut font = None;
     let mut dpr = DEFAULT_DPR;
     let mut debug = false;
+    let mut debug_labels = false;
+    let mut color_cross_edges = false;
     let mut ascii = false;
+    let mut arrowheads = true;
+    let mut list_shapes = false;
+    let mut list_arrows = false;
+    let mut highlight_path = None;
+    let mut page_size = None;
+    let mut footer = None;
+    let mut emit = None;
+    let mut relabel = HashMap::new();
+    let mut max_label_chars = None;
+    let mut direction = None;
+    let mut watch = false;
+    let mut stdout = false;
+    let mut node_gap = None;
+    let mut layer_gap = None;
+    let mut node_padding_x = None;
+    let mut node_padding_y = None;
 
     let mut iter = args.into_iter();
     while let Some(arg) = iter.next() {
@@ -166,6 +465,11 @@ fn parse_args(args: Vec<String>) -> CliOptions {
                     out = Some(PathBuf::from(path));
                 }
             }
+            "--input-format" => {
+                if let Some(value) = iter.next() {
+                    input_format = Some(value.to_lowercase());
+                }
+            }
             "--format" => {
                 if let Some(value) = iter.next() {
                     format = Some(value.to_lowercase());
@@ -207,9 +511,98 @@ fn parse_args(args: Vec<String>) -> CliOptions {
             "--debug" => {
                 debug = true;
             }
+            "--debug-labels" => {
+                debug_labels = true;
+            }
+            "--color-cross-edges" => {
+                color_cross_edges = true;
+            }
+            "--watch" => {
+                watch = true;
+            }
+            "--stdout" => {
+                stdout = true;
+            }
+            "--node-gap" => node_gap = parse_spacing(&arg, iter.next()),
+            "--layer-gap" => layer_gap = parse_spacing(&arg, iter.next()),
+            "--node-padding-x" => node_padding_x = parse_spacing(&arg, iter.next()),
+            "--node-padding-y" => node_padding_y = parse_spacing(&arg, iter.next()),
             "--ascii" => {
                 ascii = true;
             }
+            "--no-arrowheads" => {
+                arrowheads = false;
+            }
+            "--list-shapes" => {
+                list_shapes = true;
+            }
+            "--list-arrows" => {
+                list_arrows = true;
+            }
+            "--highlight-path" => {
+                let value = iter.next().unwrap_or_default();
+                let Some((from, to)) = value.split_once("..") else {
+                    return Err(CliError::Usage(format!(
+                        "invalid highlight path: {value} (use FROM..TO)"
+                    )));
+                };
+                highlight_path = Some((from.to_string(), to.to_string()));
+            }
+            "--footer" => {
+                footer = iter.next();
+            }
+            "--emit" => {
+                let value = iter.next().unwrap_or_default();
+                let targets = ["ast-json", "layout-json", "dot", "mermaid", "alt-text"];
+                if !targets.contains(&value.as_str()) {
+                    return Err(CliError::Usage(format!(
+                        "unsupported emit target: {value} \
+                         (use ast-json, layout-json, dot, mermaid, or alt-text)"
+                    )));
+                }
+                emit = Some(value);
+            }
+            "--relabel" => {
+                let value = iter.next().unwrap_or_default();
+                let Some((from, to)) = value.split_once('=') else {
+                    return Err(CliError::Usage(format!("invalid relabel: {value} (use FROM=TO)")));
+                };
+                relabel.insert(from.to_string(), to.to_string());
+            }
+            "--max-label-chars" => {
+                let value = iter.next().unwrap_or_default();
+                max_label_chars = match value.parse::<usize>() {
+                    Ok(max_chars) if max_chars > 0 => Some(max_chars),
+                    _ => {
+                        return Err(CliError::Usage(format!(
+                            "invalid max label chars: {value} (use a positive integer)"
+                        )));
+                    }
+                };
+            }
+            "--direction" => {
+                let value = iter.next().unwrap_or_default();
+                direction = match value.to_uppercase().as_str() {
+                    "TB" | "TD" => Some(Direction::TB),
+                    "BT" => Some(Direction::BT),
+                    "LR" => Some(Direction::LR),
+                    "RL" => Some(Direction::RL),
+                    _ => {
+                        return Err(CliError::Usage(format!(
+                            "unsupported direction: {value} (use TB, BT, LR, or RL)"
+                        )));
+                    }
+                };
+            }
+            "--page-size" => {
+                let value = iter.next().unwrap_or_default();
+                page_size = parse_page_size(&value);
+                if page_size.is_none() {
+                    return Err(CliError::Usage(format!(
+                        "unsupported page size: {value} (use A3, A4, A5, Letter, Legal, or WxH)"
+                    )));
+                }
+            }
             _ => {
                 if input.is_none() {
                     input = Some(arg);
@@ -218,8 +611,18 @@ fn parse_args(args: Vec<String>) -> CliOptions {
         }
     }
 
-    CliOptions {
+    if stdout && out.is_some() {
+        return Err(CliError::Usage("--stdout and --out cannot be used together".to_string()));
+    }
+    if stdout && page_size.is_some() {
+        return Err(CliError::Usage(
+            "--stdout writes a single image and cannot be combined with --page-size".to_string(),
+        ));
+    }
+
+    Ok(CliOptions {
         input,
+        input_format,
         out,
         format,
         width,
@@ -228,23 +631,92 @@ fn parse_args(args: Vec<String>) -> CliOptions {
         font,
         dpr,
         debug,
+        debug_labels,
+        color_cross_edges,
         ascii,
+        arrowheads,
+        list_shapes,
+        list_arrows,
+        highlight_path,
+        page_size,
+        footer,
+        emit,
+        relabel,
+        max_label_chars,
+        direction,
+        watch,
+        stdout,
+        node_gap,
+        layer_gap,
+        node_padding_x,
+        node_padding_y,
+    })
+}
+
+// Spacing flags must be positive; anything else is reported and the layout default is kept.
+fn parse_spacing(flag: &str, value: Option<String>) -> Option<f32> {
+    let value = value.unwrap_or_default();
+    match value.parse::<f32>() {
+        Ok(parsed) if parsed.is_finite() && parsed > 0.0 => Some(parsed),
+        _ => {
+            eprintln!("warning: ignoring {flag} {value:?}; expected a positive number");
+            None
+        }
+    }
+}
+
+// Builds without Skia fall back to ASCII unless an image was explicitly requested.
+fn wants_ascii(options: &CliOptions) -> bool {
+    if options.ascii || options.format.as_deref() == Some("ascii") {
+        return true;
+    }
+    !cfg!(feature = "skia") && options.out.is_none() && options.format.is_none()
+}
+
+fn format_shape_list() -> String {
+    let mut output = String::from("Node shapes:\n");
+    for shape in NodeShape::ALL {
+        output.push_str(&format!("  {:<10} {}\n", format!("{shape:?}"), shape.example()));
+    }
+    output
+}
+
+fn format_arrow_list() -> String {
+    let mut output = String::from("Edge operators:\n");
+    for style in EdgeStyle::ALL {
+        // `~~~` takes no heads, so it gets a single row.
+        let arrows: &[EdgeArrow] = match style {
+            EdgeStyle::Invisible => &[EdgeArrow::None],
+            _ => &EdgeArrow::ALL,
+        };
+        for arrow in arrows {
+            output.push_str(&format!(
+                "  {:<6} {:<8} {:<8} A {} B\n",
+                style.operator(arrow),
+                format!("{style:?}"),
+                format!("{arrow:?}"),
+                style.operator(arrow)
+            ));
+        }
     }
+    output
 }
 
-fn read_input(path: Option<&str>) -> String {
+fn read_input(path: Option<&str>) -> Result<String, CliError> {
     match path {
         Some("-") | None => {
             let mut buffer = String::new();
             io::stdin()
                 .read_to_string(&mut buffer)
-                .expect("failed to read stdin");
-            buffer
+                .map_err(|err| CliError::Io(format!("failed to read stdin: {err}")))?;
+            Ok(buffer)
         }
-        Some(path) => fs::read_to_string(path).expect("failed to read input file"),
+        Some(path) => fs::read_to_string(path)
+            .map_err(|err| CliError::Io(format!("failed to read {path}: {err}"))),
     }
 }
 
+#[cfg(feature = "skia")]
 fn infer_format_from_path(path: &PathBuf) -> Option<ImageFormat> {
     let ext = path.extension()?.to_string_lossy().to_lowercase();
     match ext.as_str() {
@@ -254,6 +726,7 @@ fn infer_format_from_path(path: &PathBuf) -> Option<ImageFormat> {
     }
 }
 
+#[cfg(feature = "skia")]
 fn default_output_path() -> PathBuf {
     PathBuf::from("tmp/merdraw.png")
 }
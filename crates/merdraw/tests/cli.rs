@@ -0,0 +1,419 @@
+use std::io::{BufRead, BufReader, Read, Write};
+use std::process::{Child, Command, Stdio};
+use std::sync::mpsc;
+use std::thread;
+use std::time::Duration;
+
+use merdraw_parser::{parse_flowchart, truncate_labels, EdgeArrow, EdgeStyle, Graph, NodeShape};
+
+fn run_cli(args: &[&str]) -> String {
+    let output = Command::new(env!("CARGO_BIN_EXE_merdraw"))
+        .args(args)
+        .output()
+        .expect("failed to run merdraw");
+    assert!(output.status.success());
+    String::from_utf8(output.stdout).expect("stdout was not utf-8")
+}
+
+fn run_cli_with_stdin(args: &[&str], input: &str) -> String {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_merdraw"))
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .expect("failed to run merdraw");
+    child
+        .stdin
+        .take()
+        .expect("stdin not piped")
+        .write_all(input.as_bytes())
+        .expect("failed to write stdin");
+    let output = child.wait_with_output().expect("failed to wait for merdraw");
+    assert!(output.status.success());
+    String::from_utf8(output.stdout).expect("stdout was not utf-8")
+}
+
+#[test]
+fn format_ascii_renders_text_from_stdin() {
+    let output = run_cli_with_stdin(&["--format", "ascii", "-"], "flowchart TB\nA-->B\n");
+    assert!(output.contains('A'));
+    assert!(output.contains('B'));
+    assert!(output.contains('v'));
+}
+
+#[cfg(not(feature = "skia"))]
+#[test]
+fn renders_ascii_by_default_without_skia() {
+    let output = run_cli_with_stdin(&["-"], "flowchart TB\nA-->B\n");
+    assert!(output.contains('A'));
+    assert!(output.contains('B'));
+}
+
+#[test]
+fn lists_every_node_shape() {
+    let output = run_cli(&["--list-shapes"]);
+    for shape in NodeShape::ALL {
+        assert!(output.contains(&format!("{shape:?}")), "missing {shape:?}");
+        assert!(output.contains(shape.example()));
+    }
+}
+
+#[test]
+fn lists_every_edge_operator() {
+    let output = run_cli(&["--list-arrows"]);
+    for style in EdgeStyle::ALL {
+        assert!(output.contains(&format!("{style:?}")), "missing {style:?}");
+        for arrow in EdgeArrow::ALL {
+            assert!(output.contains(&format!("{arrow:?}")), "missing {arrow:?}");
+            assert!(output.contains(style.operator(&arrow)));
+        }
+    }
+    assert!(output.contains("  ~~~    Invisible None     A ~~~ B\n"), "{output}");
+}
+
+#[cfg(feature = "skia")]
+#[test]
+fn page_size_writes_numbered_tiles() {
+    let dir = std::env::temp_dir().join(format!("merdraw-pages-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).expect("failed to create temp dir");
+    let out = dir.join("page.png");
+    let out_arg = out.to_str().unwrap();
+    run_cli_with_stdin(
+        &["--page-size", "200x200", "--width", "700", "--out", out_arg, "-"],
+        "flowchart LR\nA-->B-->C-->D-->E-->F\n",
+    );
+    assert!(dir.join("page-1.png").exists());
+    assert!(dir.join("page-2.png").exists());
+    assert!(!out.exists());
+    std::fs::remove_dir_all(&dir).ok();
+}
+
+#[test]
+fn emit_ast_json_round_trips_the_graph() {
+    let source = "flowchart LR\nsubgraph g [Group]\nA[Start] -->|go| B{Check}\nend\nB -.-> C((Done))\nC ~~~ A\n";
+    let output = run_cli_with_stdin(&["--emit", "ast-json", "-"], source);
+    let graph: Graph = serde_json::from_str(&output).expect("output was not a Graph");
+    assert_eq!(graph, parse_flowchart(source).expect("parse failed"));
+}
+
+#[test]
+fn emit_layout_json_lists_node_positions() {
+    let output = run_cli_with_stdin(&["--emit", "layout-json", "-"], "flowchart TB\nA-->B\n");
+    let layout: serde_json::Value = serde_json::from_str(&output).expect("output was not JSON");
+    let nodes = layout["nodes"].as_array().expect("nodes array");
+    assert_eq!(nodes.len(), 2);
+    for node in nodes {
+        assert!(node["x"].is_number() && node["y"].is_number());
+    }
+    let points = layout["edges"][0]["points"].as_array().expect("points array");
+    assert!(points.iter().all(|point| point.as_array().is_some_and(|pair| pair.len() == 2)));
+}
+
+#[test]
+fn emit_applies_relabel_label_limit_and_direction() {
+    let output = run_cli_with_stdin(
+        &["--emit", "mermaid", "--direction", "LR", "--relabel", "Start=Go", "-"],
+        "flowchart TB\nA[Start]-->B\n",
+    );
+    assert!(output.starts_with("flowchart LR"), "{output}");
+    assert!(output.contains("Go") && !output.contains("Start"), "{output}");
+
+    let output = run_cli_with_stdin(
+        &["--emit", "ast-json", "--max-label-chars", "5", "-"],
+        "flowchart TB\nA[A long label]-->B\n",
+    );
+    let graph: Graph = serde_json::from_str(&output).expect("output was not a Graph");
+    assert_eq!(graph.nodes[0].label.as_deref(), Some("A lo…"));
+}
+
+#[test]
+fn emit_alt_text_prefers_accessibility_directives() {
+    let output = run_cli_with_stdin(&["--emit", "alt-text", "-"], "flowchart LR\nA[Start]-->B\n");
+    assert_eq!(output, "Flowchart, left to right, with 2 nodes and 1 edge: Start to B.\n");
+
+    let output = run_cli_with_stdin(
+        &["--emit", "alt-text", "-"],
+        "flowchart LR\naccTitle: Login\naccDescr: How a user signs in\nA-->B\n",
+    );
+    assert_eq!(output, "Login. How a user signs in\n");
+}
+
+#[test]
+fn highlight_path_without_a_range_is_a_usage_error() {
+    let output = Command::new(env!("CARGO_BIN_EXE_merdraw"))
+        .args(["--ascii", "--highlight-path", "A-B", "does/not/matter.mmd"])
+        .output()
+        .expect("failed to run merdraw");
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert_eq!(output.status.code(), Some(1), "{stderr}");
+    assert_eq!(stderr.trim(), "invalid highlight path: A-B (use FROM..TO)");
+}
+
+#[test]
+fn bad_flags_and_watching_stdin_are_usage_errors() {
+    let cases: [(&[&str], &str); 3] = [
+        (&["--direction", "up", "-"], "unsupported direction: up (use TB, BT, LR, or RL)"),
+        (&["--stdout", "--out", "x.png", "-"], "--stdout and --out cannot be used together"),
+        (&["--watch", "-"], "--watch needs an input file, not stdin"),
+    ];
+    for (args, message) in cases {
+        let output = Command::new(env!("CARGO_BIN_EXE_merdraw"))
+            .args(args)
+            .stdin(Stdio::null())
+            .output()
+            .expect("failed to run merdraw");
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        assert_eq!(output.status.code(), Some(1), "{args:?}: {stderr}");
+        assert_eq!(stderr.trim(), message);
+    }
+}
+
+#[test]
+fn relabel_replaces_drawn_label_text() {
+    let output = run_cli_with_stdin(
+        &["--ascii", "--relabel", "Start=Début", "--relabel", "B=Fin", "-"],
+        "flowchart TB\nA[Start]-->B\n",
+    );
+    assert!(output.contains("Début"), "{output}");
+    assert!(output.contains("Fin"), "{output}");
+    assert!(!output.contains("Start"), "{output}");
+}
+
+#[test]
+fn max_label_chars_truncates_the_same_text_for_every_backend() {
+    let source = "flowchart TB\nA[An extremely long label]-- and a long edge label -->B\n";
+    let output = run_cli_with_stdin(&["--ascii", "--max-label-chars", "12", "-"], source);
+    assert!(output.contains("An extremel…"), "{output}");
+    assert!(!output.contains("label"), "{output}");
+
+    let truncated = truncate_labels(&parse_flowchart(source).expect("parse failed"), 12);
+    let literal =
+        parse_flowchart("flowchart TB\nA[An extremel…]-- and a long… -->B\n").expect("parse failed");
+    assert_eq!(truncated, literal);
+    #[cfg(feature = "skia")]
+    {
+        use merdraw_skia_render::{layout_flowchart_skia, SkiaLayoutOptions, SkiaRenderOptions};
+        let width = |graph: &Graph| {
+            let layout = layout_flowchart_skia(
+                graph,
+                &SkiaRenderOptions::default(),
+                &SkiaLayoutOptions::default(),
+            )
+            .expect("layout failed");
+            layout.nodes[0].width
+        };
+        assert_eq!(width(&truncated), width(&literal));
+    }
+}
+
+// Kills the watched child however the test exits, so a failed assertion can't leak it.
+struct KillOnDrop(Child);
+
+impl Drop for KillOnDrop {
+    fn drop(&mut self) {
+        self.0.kill().ok();
+        self.0.wait().ok();
+    }
+}
+
+#[test]
+fn watch_rerenders_on_change_and_survives_parse_errors() {
+    let dir = std::env::temp_dir().join(format!("merdraw-watch-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).expect("failed to create temp dir");
+    let input = dir.join("chart.mmd");
+    let write = |source: &str, bump: u64| {
+        std::fs::write(&input, source).expect("failed to write input");
+        let file = std::fs::File::options().write(true).open(&input).expect("failed to open");
+        let at = std::time::SystemTime::now() + Duration::from_secs(bump);
+        file.set_modified(at).expect("failed to set mtime");
+    };
+    write("flowchart TB\nA-->B\n", 0);
+    let mut child = KillOnDrop(
+        Command::new(env!("CARGO_BIN_EXE_merdraw"))
+            .args(["--ascii", "--watch", input.to_str().unwrap()])
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .expect("failed to run merdraw"),
+    );
+    // Both pipes drain on their own threads; status lines come back over a channel so a
+    // hung watcher fails the test at the deadline instead of blocking it.
+    let stderr = BufReader::new(child.0.stderr.take().expect("stderr not piped"));
+    let (lines, statuses) = mpsc::channel();
+    thread::spawn(move || {
+        for line in stderr.lines().map_while(Result::ok) {
+            if lines.send(line).is_err() {
+                break;
+            }
+        }
+    });
+    let mut stdout = child.0.stdout.take().expect("stdout not piped");
+    let stdout = thread::spawn(move || {
+        let mut text = String::new();
+        stdout.read_to_string(&mut text).ok();
+        text
+    });
+    let next_status = || {
+        statuses
+            .recv_timeout(Duration::from_secs(10))
+            .expect("watch printed no status before the deadline")
+    };
+
+    assert!(next_status().contains("rendered"));
+    write("flowchart TB\nA --> <B>\n", 10);
+    let status = next_status();
+    assert!(status.contains("failed to parse flowchart"), "{status}");
+    write("flowchart TB\nX-->Y\n", 20);
+    assert!(next_status().contains("rendered"));
+
+    drop(child);
+    let stdout = stdout.join().expect("stdout reader panicked");
+    assert!(stdout.contains('A') && stdout.contains('X'), "{stdout}");
+    std::fs::remove_dir_all(&dir).ok();
+}
+
+#[test]
+fn bad_input_exits_with_a_concise_error_and_status() {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_merdraw"))
+        .args(["--ascii", "-"])
+        .stdin(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("failed to run merdraw");
+    child
+        .stdin
+        .take()
+        .expect("stdin not piped")
+        .write_all(b"flowchart TB\nA --> <B>\n")
+        .expect("failed to write stdin");
+    let output = child.wait_with_output().expect("failed to wait for merdraw");
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert_eq!(output.status.code(), Some(2), "{stderr}");
+    assert_eq!(stderr.trim(), "failed to parse flowchart: unexpected character '<' at 2:7");
+
+    let output = Command::new(env!("CARGO_BIN_EXE_merdraw"))
+        .args(["--ascii", "does/not/exist.mmd"])
+        .output()
+        .expect("failed to run merdraw");
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert_eq!(output.status.code(), Some(1), "{stderr}");
+    assert!(stderr.starts_with("failed to read does/not/exist.mmd:"), "{stderr}");
+    assert!(!stderr.contains("panicked"), "{stderr}");
+}
+
+#[test]
+fn dropped_same_rank_constraints_are_reported_on_stderr() {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_merdraw"))
+        .args(["--ascii", "-"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("failed to run merdraw");
+    child
+        .stdin
+        .take()
+        .expect("stdin not piped")
+        .write_all(b"flowchart TB\nA-->B\nrank same A B\n")
+        .expect("failed to write stdin");
+    let output = child.wait_with_output().expect("failed to wait for merdraw");
+    assert!(output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert_eq!(
+        stderr.trim(),
+        "warning: same-rank nodes A and B are connected; dropping B from the constraint"
+    );
+}
+
+#[cfg(feature = "skia")]
+#[test]
+fn stdout_emits_raw_png_bytes() {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_merdraw"))
+        .args(["--format", "png", "--stdout", "-"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("failed to run merdraw");
+    child
+        .stdin
+        .take()
+        .expect("stdin not piped")
+        .write_all(b"flowchart TB\nA-->B\n")
+        .expect("failed to write stdin");
+    let output = child.wait_with_output().expect("failed to wait for merdraw");
+    assert!(output.status.success());
+    assert!(output.stdout.starts_with(b"\x89PNG\r\n\x1a\n"));
+    assert!(output.stderr.is_empty(), "{}", String::from_utf8_lossy(&output.stderr));
+
+    let output = Command::new(env!("CARGO_BIN_EXE_merdraw"))
+        .args(["--stdout", "--out", "x.png", "-"])
+        .output()
+        .expect("failed to run merdraw");
+    assert_eq!(output.status.code(), Some(1));
+    assert!(output.stdout.is_empty());
+}
+
+#[cfg(feature = "skia")]
+#[test]
+fn larger_layer_gap_makes_a_taller_layout() {
+    let height = |extra: &[&str]| {
+        let mut args = vec!["--format", "png", "--stdout"];
+        args.extend_from_slice(extra);
+        args.push("-");
+        let mut child = Command::new(env!("CARGO_BIN_EXE_merdraw"))
+            .args(&args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .expect("failed to run merdraw");
+        child
+            .stdin
+            .take()
+            .expect("stdin not piped")
+            .write_all(b"flowchart TB\nA-->B-->C\n")
+            .expect("failed to write stdin");
+        let output = child.wait_with_output().expect("failed to wait for merdraw");
+        assert!(output.status.success());
+        let stderr = String::from_utf8(output.stderr).expect("stderr was not utf-8");
+        // PNG height sits in the IHDR chunk, right after the width.
+        (u32::from_be_bytes(output.stdout[20..24].try_into().unwrap()), stderr)
+    };
+    let (default, _) = height(&[]);
+    let (tall, _) = height(&["--layer-gap", "200"]);
+    assert!(tall > default + 200, "{tall} vs {default}");
+
+    let (fallback, warning) = height(&["--layer-gap", "-5"]);
+    assert_eq!(fallback, default);
+    assert!(warning.contains("ignoring --layer-gap"), "{warning}");
+}
+
+#[cfg(feature = "skia")]
+#[test]
+fn color_cross_edges_changes_only_grouped_renders() {
+    let png = |source: &[u8], extra: &[&str]| {
+        let mut args = vec!["--format", "png", "--stdout"];
+        args.extend_from_slice(extra);
+        args.push("-");
+        let mut child = Command::new(env!("CARGO_BIN_EXE_merdraw"))
+            .args(&args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .expect("failed to run merdraw");
+        child
+            .stdin
+            .take()
+            .expect("stdin not piped")
+            .write_all(source)
+            .expect("failed to write stdin");
+        let output = child.wait_with_output().expect("failed to wait for merdraw");
+        assert!(output.status.success());
+        output.stdout
+    };
+    let grouped = b"flowchart TB\nsubgraph one\nA\nend\nsubgraph two\nB\nend\nA-->B\n";
+    assert_ne!(png(grouped, &[]), png(grouped, &["--color-cross-edges"]));
+    let flat = b"flowchart TB\nA-->B\n";
+    assert_eq!(png(flat, &[]), png(flat, &["--color-cross-edges"]));
+}